@@ -0,0 +1,22 @@
+// Generates the C header for the API in src/ffi.rs into OUT_DIR; the copy in
+// include/ is checked against it by tests/c_api.rs
+fn main() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    // The C API is only built for native targets
+    if std::env::var("CARGO_CFG_TARGET_ARCH").as_deref() == Ok("wasm32") {
+        return;
+    }
+
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("cargo sets CARGO_MANIFEST_DIR");
+    let out_dir = std::env::var("OUT_DIR").expect("cargo sets OUT_DIR");
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).expect("cbindgen.toml is valid");
+    match cbindgen::generate_with_config(&crate_dir, config) {
+        Ok(bindings) => {
+            bindings.write_to_file(format!("{}/restoration.h", out_dir));
+        }
+        // A syntax error should be reported by rustc, not hidden behind a build script panic
+        Err(e) => println!("cargo:warning=Failed to generate C header: {}", e),
+    }
+}
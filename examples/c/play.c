@@ -0,0 +1,82 @@
+/*
+ * Plays the first steps of a story through the C API.
+ *
+ *   cargo build
+ *   cc examples/c/play.c -Iinclude -Ltarget/debug -lrestoration_project -o play
+ *   LD_LIBRARY_PATH=target/debug ./play the_cellar.md
+ */
+#include <stdio.h>
+#include <stdlib.h>
+#include "restoration.h"
+
+static char *read_file(const char *path) {
+    FILE *file = fopen(path, "rb");
+    if (!file) {
+        return NULL;
+    }
+    fseek(file, 0, SEEK_END);
+    long length = ftell(file);
+    fseek(file, 0, SEEK_SET);
+    char *content = malloc(length + 1);
+    if (content && fread(content, 1, length, file) == (size_t)length) {
+        content[length] = '\0';
+    } else {
+        free(content);
+        content = NULL;
+    }
+    fclose(file);
+    return content;
+}
+
+/* Print a string from the library and free it */
+static int print_result(const char *label, char *result) {
+    if (!result) {
+        printf("%s failed: %s\n", label, restoration_last_error());
+        return 0;
+    }
+    printf("%s: %s\n", label, result);
+    restoration_string_free(result);
+    return 1;
+}
+
+int main(int argc, char **argv) {
+    if (argc != 2) {
+        fprintf(stderr, "Usage: %s <story.md>\n", argv[0]);
+        return 2;
+    }
+
+    char *story = read_file(argv[1]);
+    if (!story) {
+        fprintf(stderr, "Could not read %s\n", argv[1]);
+        return 1;
+    }
+    RestorationSession *session = restoration_session_new(story, 42);
+    free(story);
+    if (!session) {
+        fprintf(stderr, "Could not load story: %s\n", restoration_last_error());
+        return 1;
+    }
+
+    int ok = print_result("description", restoration_session_description(session))
+        && print_result("choices", restoration_session_choices(session))
+        && print_result("events", restoration_session_choose(session, 1));
+
+    /* Ask for the size first, then save into a buffer that fits */
+    size_t length = restoration_session_save(session, NULL, 0);
+    char *save = malloc(length + 1);
+    ok = ok && save && restoration_session_save(session, save, length + 1) == length;
+
+    ok = ok && print_result("events", restoration_session_choose_id(session, "cellar_choice_2"))
+        && print_result("choices", restoration_session_choices(session));
+
+    ok = ok && print_result("restore notes", restoration_session_restore(session, save, length))
+        && print_result("restored choices", restoration_session_choices(session));
+    free(save);
+
+    /* Errors come back as NULL with a message */
+    print_result("bad choice", restoration_session_choose(session, 99));
+    printf("over: %s\n", restoration_session_is_over(session) ? "yes" : "no");
+
+    restoration_session_free(session);
+    return ok ? 0 : 1;
+}
@@ -0,0 +1,124 @@
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#ifndef RESTORATION_H
+#define RESTORATION_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Something the player can bind keys to in the full-screen and web UIs.
+ */
+typedef struct KeyAction KeyAction;
+
+/**
+ * A story being played. Create with `restoration_session_new`, release with
+ * `restoration_session_free`.
+ */
+typedef struct RestorationSession RestorationSession;
+
+/**
+ * The message of the last failed call on this thread, or NULL. The pointer stays
+ * valid until the next failing call on the same thread.
+ */
+const char *restoration_last_error(void);
+
+/**
+ * Free a string returned by this library.
+ *
+ * # Safety
+ * `text` must be NULL or a string returned by this library that was not freed yet.
+ */
+void restoration_string_free(char *text);
+
+/**
+ * Load a story from Markdown and start it. Returns NULL if the story is invalid.
+ *
+ * # Safety
+ * `story_markdown` must be NULL or a NUL-terminated string.
+ */
+struct RestorationSession *restoration_session_new(const char *story_markdown, uint64_t seed);
+
+/**
+ * # Safety
+ * `session` must be NULL or a session from `restoration_session_new` that was not freed yet.
+ */
+void restoration_session_free(struct RestorationSession *session);
+
+/**
+ * What the player sees now, as JSON:
+ * `{"description": "...", "is_over": false, "waiting_for_input": false}`.
+ * Free the result with `restoration_string_free`; NULL on error.
+ *
+ * # Safety
+ * `session` must be a live session from `restoration_session_new`.
+ */
+char *restoration_session_description(const struct RestorationSession *session);
+
+/**
+ * The available choices as a JSON array of `{"index", "id", "text"}`.
+ * Free the result with `restoration_string_free`; NULL on error.
+ *
+ * # Safety
+ * `session` must be a live session from `restoration_session_new`.
+ */
+char *restoration_session_choices(const struct RestorationSession *session);
+
+/**
+ * Make the choice at zero-based `index` and return the resulting events as a JSON
+ * array. Free the result with `restoration_string_free`; NULL on error.
+ *
+ * # Safety
+ * `session` must be a live session from `restoration_session_new`.
+ */
+char *restoration_session_choose(struct RestorationSession *session, size_t index);
+
+/**
+ * Like `restoration_session_choose`, selecting the choice by its ID.
+ *
+ * # Safety
+ * `session` must be a live session and `choice_id` a NUL-terminated string.
+ */
+char *restoration_session_choose_id(struct RestorationSession *session, const char *choice_id);
+
+/**
+ * Answer a pending text prompt and return the resulting events as a JSON array.
+ *
+ * # Safety
+ * `session` must be a live session and `answer` a NUL-terminated string.
+ */
+char *restoration_session_answer(struct RestorationSession *session, const char *answer);
+
+/**
+ * # Safety
+ * `session` must be NULL or a live session from `restoration_session_new`.
+ */
+bool restoration_session_is_over(const struct RestorationSession *session);
+
+/**
+ * Write the game state into `buffer` as NUL-terminated JSON. Returns the length of
+ * the save without the NUL; if that is not less than `capacity`, nothing is written
+ * and the call should be repeated with a larger buffer. Returns 0 on error.
+ *
+ * # Safety
+ * `session` must be a live session and `buffer` NULL or valid for `capacity` bytes.
+ */
+size_t restoration_session_save(const struct RestorationSession *session,
+                                char *buffer,
+                                size_t capacity);
+
+/**
+ * Continue from `length` bytes of JSON written by `restoration_session_save`.
+ * Returns a JSON array of notes about anything changed to fit the current story,
+ * to show the player. Free it with `restoration_string_free`; NULL, leaving the
+ * session unchanged, if the save can't be used.
+ *
+ * # Safety
+ * `session` must be a live session and `save` valid for `length` bytes.
+ */
+char *restoration_session_restore(struct RestorationSession *session,
+                                  const char *save,
+                                  size_t length);
+
+#endif  /* RESTORATION_H */
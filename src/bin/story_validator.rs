@@ -1,19 +1,23 @@
-use restoration_project::world::{load_world_from_markdown, World, Condition};
+use restoration_project::world::{load_world_from_markdown, World, Condition, Action, DescriptionMode};
 use restoration_project::errors::GameError;
+use restoration_project::config::{ConfigLayers, GameConfig};
 use std::env;
 use std::collections::HashSet;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Usage: story_validator <story.md>");
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    // Strict mode fails on any warning and requires every flag and counter to be declared with @flag or @var
+    let strict = args.iter().any(|arg| arg == "--strict");
+    args.retain(|arg| arg != "--strict");
+    if args.len() != 1 {
+        eprintln!("Usage: story_validator [--strict] <story.md>");
         eprintln!("Example: cargo run --bin story_validator the_cellar.md");
         std::process::exit(1);
     }
 
-    let story_file = &args[1];
+    let story_file = &args[0];
     
-    match validate_story(story_file) {
+    match validate_story(story_file, strict) {
         Ok(stats) => {
             println!("✅ Story file '{}' is valid!", story_file);
             println!("\n📊 Story Statistics:");
@@ -35,27 +39,91 @@ pub struct StoryStats {
     pub rooms_with_no_exit: Vec<String>,
     pub max_depth: usize,
     pub total_flags: HashSet<String>,
+    pub total_endings: usize,
+    pub unreachable_endings: Vec<String>,
+    pub total_achievements: usize,
+    pub unreachable_achievements: Vec<String>,
+    pub total_dialogues: usize,
+    pub unreachable_dialogues: Vec<String>,
+    pub shadowed_descriptions: Vec<String>,
+    pub total_variables: usize,
+    pub undeclared_flags: Vec<String>,
+    pub undeclared_counters: Vec<String>,
 }
 
-fn validate_story(path: &str) -> Result<StoryStats, GameError> {
+fn validate_story(path: &str, strict: bool) -> Result<StoryStats, GameError> {
     let world = load_world_from_markdown(path)?;
     
     // Additional validation checks
     let stats = analyze_story(&world)?;
+    let warnings = story_warnings(&world, &stats);
+    for warning in &warnings {
+        eprintln!("⚠️  Warning: {}", warning);
+    }
+    
+    // Strict mode treats every warning, and every undeclared flag or counter, as an error
+    if strict {
+        let mut undeclared: Vec<String> = stats.undeclared_flags.iter()
+            .map(|flag| format!("flag '{}'", flag))
+            .chain(stats.undeclared_counters.iter().map(|counter| format!("counter '{}'", counter)))
+            .collect();
+        if !undeclared.is_empty() {
+            undeclared.sort();
+            return Err(GameError::ValidationError(format!("Undeclared variable(s) in strict mode: {}", undeclared.join(", "))));
+        }
+        if !warnings.is_empty() {
+            return Err(GameError::ValidationError(format!("{} warning(s) in strict mode", warnings.len())));
+        }
+    }
+    
+    Ok(stats)
+}
+
+fn story_warnings(world: &World, stats: &StoryStats) -> Vec<String> {
+    let mut warnings = Vec::new();
     
     // Check for unreachable rooms
     if !stats.unreachable_rooms.is_empty() {
-        eprintln!("⚠️  Warning: Found {} unreachable room(s): {:?}", 
-                 stats.unreachable_rooms.len(), stats.unreachable_rooms);
+        warnings.push(format!("Found {} unreachable room(s): {:?}", 
+                 stats.unreachable_rooms.len(), stats.unreachable_rooms));
     }
     
     // Check for dead ends
     if !stats.dead_end_rooms.is_empty() {
-        eprintln!("⚠️  Warning: Found {} dead-end room(s): {:?}", 
-                 stats.dead_end_rooms.len(), stats.dead_end_rooms);
+        warnings.push(format!("Found {} dead-end room(s): {:?}", 
+                 stats.dead_end_rooms.len(), stats.dead_end_rooms));
     }
     
-    Ok(stats)
+    // Check for endings no player can reach
+    if !stats.unreachable_endings.is_empty() {
+        warnings.push(format!("Found {} unreachable ending(s): {:?}", 
+                 stats.unreachable_endings.len(), stats.unreachable_endings));
+    }
+    
+    // Check for achievements that can never be unlocked
+    if !stats.unreachable_achievements.is_empty() {
+        warnings.push(format!("Found {} unreachable achievement(s): {:?}", 
+                 stats.unreachable_achievements.len(), stats.unreachable_achievements));
+    }
+    
+    // Check for conversations no player can have
+    if !stats.unreachable_dialogues.is_empty() {
+        warnings.push(format!("Found {} unreachable dialogue node(s): {:?}", 
+                 stats.unreachable_dialogues.len(), stats.unreachable_dialogues));
+    }
+    
+    // Check for descriptions that can never be shown
+    if !stats.shadowed_descriptions.is_empty() {
+        warnings.push(format!("Found {} room(s) where an unconditional description hides later ones: {:?}", 
+                 stats.shadowed_descriptions.len(), stats.shadowed_descriptions));
+    }
+    
+    // Check for front matter settings the config doesn't understand
+    let mut layers = ConfigLayers::new(&GameConfig::default());
+    layers.apply_story(world);
+    warnings.extend(layers.warnings);
+    
+    warnings
 }
 
 fn analyze_story(world: &World) -> Result<StoryStats, GameError> {
@@ -63,7 +131,7 @@ fn analyze_story(world: &World) -> Result<StoryStats, GameError> {
     let mut flags = HashSet::new();
     
     // Find all reachable rooms starting from the starting room
-    find_reachable_rooms(world, &world.starting_room_id, &mut reachable_rooms, &mut HashSet::new(), 0);
+    find_reachable_rooms(world, &world.starting_room_id, &mut reachable_rooms, &mut HashSet::new());
     
     // Collect all flags used in the story
     for choice in world.choices.values() {
@@ -87,8 +155,11 @@ fn analyze_story(world: &World) -> Result<StoryStats, GameError> {
                 }
                 restoration_project::world::Condition::CounterGreaterThan(_, _) |
                 restoration_project::world::Condition::CounterLessThan(_, _) |
-                restoration_project::world::Condition::CounterEquals(_, _) => {
-                    // Counters don't use flags
+                restoration_project::world::Condition::CounterEquals(_, _) |
+                restoration_project::world::Condition::StringEquals(_, _) |
+                restoration_project::world::Condition::StringNotEquals(_, _) |
+                restoration_project::world::Condition::SkillCheck(_) => {
+                    // Counters and string variables don't use flags
                 }
                 restoration_project::world::Condition::And(left, right) => {
                     collect_flags_from_condition(left, &mut flags);
@@ -101,7 +172,7 @@ fn analyze_story(world: &World) -> Result<StoryStats, GameError> {
             }
         }
         
-        for action in &choice.actions {
+        for action in choice.actions.iter().flat_map(Action::flattened) {
             match action {
                 restoration_project::world::Action::SetFlag(flag) => {
                     flags.insert(flag.0.clone());
@@ -136,35 +207,54 @@ fn analyze_story(world: &World) -> Result<StoryStats, GameError> {
         .cloned()
         .collect();
     
+    // Find endings and achievements that no choice in a reachable room leads to
+    let mut reachable_endings = HashSet::new();
+    let mut reachable_achievements = HashSet::new();
+    let mut reachable_dialogues = HashSet::new();
+    for room_id in &reachable_rooms {
+        for action in world.actions_from_room(room_id) {
+            match action {
+                restoration_project::world::Action::Ending(ending_id) => {
+                    reachable_endings.insert(ending_id.clone());
+                }
+                restoration_project::world::Action::Achieve(achievement_id) => {
+                    reachable_achievements.insert(achievement_id.clone());
+                }
+                restoration_project::world::Action::Dialogue(node_id) => {
+                    reachable_dialogues.insert(node_id.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+    let unreachable_endings: Vec<String> = world.endings.iter()
+        .filter(|ending| !reachable_endings.contains(&ending.id))
+        .map(|ending| ending.id.clone())
+        .collect();
+    // Conditional achievements unlock on their own, so only count the action-driven ones
+    let unreachable_achievements: Vec<String> = world.achievements.iter()
+        .filter(|achievement| achievement.condition.is_none() && !reachable_achievements.contains(&achievement.id))
+        .map(|achievement| achievement.id.clone())
+        .collect();
+    let unreachable_dialogues: Vec<String> = world.dialogues.keys()
+        .filter(|node_id| !reachable_dialogues.contains(*node_id))
+        .cloned()
+        .collect();
+    
     // Find dead end rooms (rooms with no valid choices that lead anywhere)
     let mut dead_end_rooms = Vec::new();
     let mut rooms_with_no_exit = Vec::new();
     
     for (room_id, room) in &world.rooms {
-        let mut has_exit = false;
-        let mut has_any_choice = false;
-        
-        for choice_id in &room.choices {
-            if let Some(choice) = world.choices.get(choice_id) {
-                has_any_choice = true;
-                for action in &choice.actions {
-                    match action {
-                        restoration_project::world::Action::GoTo(_) => {
-                            has_exit = true;
-                            break;
-                        }
-                        restoration_project::world::Action::Quit => {
-                            has_exit = true;
-                            break;
-                        }
-                        _ => {}
-                    }
-                }
-                if has_exit {
-                    break;
-                }
-            }
-        }
+        let has_any_choice = room.choices.iter().any(|choice_id| world.choices.contains_key(choice_id));
+        let has_exit = world.actions_from_room(room_id).into_iter().any(|action| {
+            matches!(
+                action,
+                restoration_project::world::Action::GoTo(_) |
+                restoration_project::world::Action::Quit |
+                restoration_project::world::Action::Ending(_)
+            )
+        });
         
         if !has_any_choice {
             rooms_with_no_exit.push(room_id.clone());
@@ -173,6 +263,29 @@ fn analyze_story(world: &World) -> Result<StoryStats, GameError> {
         }
     }
     
+    // Under first-match, nothing after an unconditional description is ever shown
+    let mut shadowed_descriptions: Vec<String> = world.rooms.iter()
+        .filter(|(_, room)| room.description_mode == DescriptionMode::FirstMatch)
+        .filter(|(_, room)| {
+            room.descriptions.iter()
+                .position(|description| description.condition.is_none())
+                .is_some_and(|index| index + 1 < room.descriptions.len())
+        })
+        .map(|(room_id, _)| room_id.clone())
+        .collect();
+    shadowed_descriptions.sort();
+    
+    // Find flags and counters the story uses without declaring them
+    let (used_flags, used_counters) = world.variable_uses();
+    let mut undeclared_flags: Vec<String> = used_flags.into_iter()
+        .filter(|flag| !world.declares_flag(flag))
+        .collect();
+    undeclared_flags.sort();
+    let mut undeclared_counters: Vec<String> = used_counters.into_iter()
+        .filter(|counter| !world.declares_counter(counter))
+        .collect();
+    undeclared_counters.sort();
+    
     Ok(StoryStats {
         total_rooms: world.rooms.len(),
         total_choices: world.choices.len(),
@@ -181,6 +294,16 @@ fn analyze_story(world: &World) -> Result<StoryStats, GameError> {
         rooms_with_no_exit,
         max_depth: calculate_max_depth(world),
         total_flags: flags,
+        total_endings: world.endings.len(),
+        unreachable_endings,
+        total_achievements: world.achievements.len(),
+        unreachable_achievements,
+        total_dialogues: world.dialogues.len(),
+        unreachable_dialogues,
+        shadowed_descriptions,
+        total_variables: world.variables.len(),
+        undeclared_flags,
+        undeclared_counters,
     })
 }
 
@@ -189,7 +312,6 @@ fn find_reachable_rooms(
     room_id: &str,
     reachable: &mut HashSet<String>,
     visiting: &mut HashSet<String>,
-    depth: usize,
 ) {
     if reachable.contains(room_id) || visiting.contains(room_id) {
         return;
@@ -198,15 +320,10 @@ fn find_reachable_rooms(
     visiting.insert(room_id.to_string());
     reachable.insert(room_id.to_string());
     
-    if let Some(room) = world.rooms.get(room_id) {
-        for choice_id in &room.choices {
-            if let Some(choice) = world.choices.get(choice_id) {
-                for action in &choice.actions {
-                    if let restoration_project::world::Action::GoTo(next_room) = action {
-                        find_reachable_rooms(world, next_room, reachable, visiting, depth + 1);
-                    }
-                }
-            }
+    // Both outcomes of checks and every branch of conversations count
+    for action in world.actions_from_room(room_id) {
+        if let restoration_project::world::Action::GoTo(next_room) = action {
+            find_reachable_rooms(world, next_room, reachable, visiting);
         }
     }
     
@@ -222,16 +339,10 @@ fn calculate_max_depth(world: &World) -> usize {
         visited.insert(room_id.to_string());
         let mut max_depth = depth;
         
-        if let Some(room) = world.rooms.get(room_id) {
-            for choice_id in &room.choices {
-                if let Some(choice) = world.choices.get(choice_id) {
-                    for action in &choice.actions {
-                        if let restoration_project::world::Action::GoTo(next_room) = action {
-                            let new_depth = dfs(world, next_room, visited, depth + 1);
-                            max_depth = max_depth.max(new_depth);
-                        }
-                    }
-                }
+        for action in world.actions_from_room(room_id) {
+            if let restoration_project::world::Action::GoTo(next_room) = action {
+                let new_depth = dfs(world, next_room, visited, depth + 1);
+                max_depth = max_depth.max(new_depth);
             }
         }
         
@@ -262,8 +373,11 @@ fn collect_flags_from_condition(condition: &Condition, flags: &mut HashSet<Strin
         }
         Condition::CounterGreaterThan(_, _) |
         Condition::CounterLessThan(_, _) |
-        Condition::CounterEquals(_, _) => {
-            // Counters don't use flags
+        Condition::CounterEquals(_, _) |
+        Condition::StringEquals(_, _) |
+        Condition::StringNotEquals(_, _) |
+        Condition::SkillCheck(_) => {
+            // Counters and string variables don't use flags
         }
         Condition::And(left, right) => {
             collect_flags_from_condition(left, flags);
@@ -281,6 +395,10 @@ fn print_story_stats(stats: &StoryStats) {
     println!("  🔀 Total choices: {}", stats.total_choices);
     println!("  🏁 Total flags: {}", stats.total_flags.len());
     println!("  📏 Maximum depth: {}", stats.max_depth);
+    println!("  🏆 Total endings: {}", stats.total_endings);
+    println!("  🎖️  Total achievements: {}", stats.total_achievements);
+    println!("  💬 Total dialogue nodes: {}", stats.total_dialogues);
+    println!("  📝 Declared variables: {}", stats.total_variables);
     
     if !stats.total_flags.is_empty() {
         println!("  🎌 Flags used: {:?}", stats.total_flags.iter().collect::<Vec<_>>());
@@ -294,7 +412,107 @@ fn print_story_stats(stats: &StoryStats) {
         println!("  ⚠️  Dead-end rooms: {:?}", stats.dead_end_rooms);
     }
     
+    if !stats.unreachable_endings.is_empty() {
+        println!("  🔒 Unreachable endings: {:?}", stats.unreachable_endings);
+    }
+    
+    if !stats.unreachable_achievements.is_empty() {
+        println!("  🔒 Unreachable achievements: {:?}", stats.unreachable_achievements);
+    }
+    
+    if !stats.unreachable_dialogues.is_empty() {
+        println!("  🔇 Unreachable dialogue nodes: {:?}", stats.unreachable_dialogues);
+    }
+    
+    if !stats.shadowed_descriptions.is_empty() {
+        println!("  🙈 Rooms with hidden descriptions: {:?}", stats.shadowed_descriptions);
+    }
+    
+    if !stats.undeclared_flags.is_empty() {
+        println!("  ❔ Undeclared flags: {:?}", stats.undeclared_flags);
+    }
+    
+    if !stats.undeclared_counters.is_empty() {
+        println!("  ❔ Undeclared counters: {:?}", stats.undeclared_counters);
+    }
+    
     if !stats.rooms_with_no_exit.is_empty() {
         println!("  🚪 Rooms with no choices: {:?}", stats.rooms_with_no_exit);
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use restoration_project::world::load_world_from_markdown_content;
+
+    const LOOSE_ENDS: &str = "# Loose Ends\n\n@start hall\n@flag calm\n@ending escaped \"Escaped\"\n@ending lost \"Lost\"\n@achievement curious \"Looked around\"\n@achievement patient \"Waited\"\n@achievement steady \"Kept calm\" [calm]\n\n## @room hall\nA hall.\n\n### Look around\n- achieve curious\n\n### Talk to the guard\n- -> guard\n\n### Leave\n- end: escaped\n\n## @dialogue guard\nGuard: \"Move along.\"\n\n### Nod\n- exit\n\n## @dialogue stranger\nStranger: \"Psst.\"\n\n### Ignore them\n- exit\n";
+
+    const TIDY: &str = "# Tidy\n\n@start hall\n@ending escaped \"Escaped\"\n\n## @room hall\nA hall.\n\n### Leave\n- end: escaped\n";
+
+    fn story_file(name: &str, content: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("story_validator_{}_{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("story.md");
+        std::fs::write(&path, content).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn reports_unreachable_endings_achievements_and_dialogues() {
+        let world = load_world_from_markdown_content(LOOSE_ENDS).unwrap();
+        let stats = analyze_story(&world).unwrap();
+
+        assert_eq!(stats.unreachable_endings, vec!["lost".to_string()]);
+        // The conditional achievement unlocks by itself, so only the action-driven one counts
+        assert_eq!(stats.unreachable_achievements, vec!["patient".to_string()]);
+        assert_eq!(stats.unreachable_dialogues, vec!["stranger".to_string()]);
+        assert_eq!(story_warnings(&world, &stats).len(), 3);
+    }
+
+    #[test]
+    fn tidy_story_has_no_warnings() {
+        let world = load_world_from_markdown_content(TIDY).unwrap();
+        let stats = analyze_story(&world).unwrap();
+
+        assert!(stats.unreachable_endings.is_empty());
+        assert!(story_warnings(&world, &stats).is_empty());
+    }
+
+    #[test]
+    fn strict_mode_turns_warnings_into_a_failure() {
+        let path = story_file("warnings", LOOSE_ENDS);
+
+        assert!(validate_story(&path, false).is_ok());
+        match validate_story(&path, true) {
+            Err(GameError::ValidationError(message)) => assert_eq!(message, "3 warning(s) in strict mode"),
+            other => panic!("expected a strict-mode failure, got {:?}", other),
+        }
+        assert!(validate_story(&story_file("tidy", TIDY), true).is_ok());
+    }
+
+    #[test]
+    fn strict_mode_rejects_undeclared_variables() {
+        let path = story_file("undeclared", "# Sloppy\n\n@start hall\n@ending escaped \"Escaped\"\n\n## @room hall\nA hall.\n\n### Leave\n- flag+ lantern\n- count+ steps\n- end: escaped\n");
+
+        assert!(validate_story(&path, false).is_ok());
+        match validate_story(&path, true) {
+            Err(GameError::ValidationError(message)) => {
+                assert_eq!(message, "Undeclared variable(s) in strict mode: counter 'steps', flag 'lantern'");
+            }
+            other => panic!("expected a strict-mode failure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn both_outcomes_of_a_check_are_reachable() {
+        let world = load_world_from_markdown_content(
+            "# Bridge\n\n@start ledge\n@var courage = 3\n\n## @room ledge\nA ledge.\n\n### Jump\n- check d20 + courage >= 12: @far_side else: @river\n\n## @room far_side\nThe far side.\n\n### Rest\n- quit\n\n## @room river\nA cold river.\n\n### Swim\n- quit\n",
+        )
+        .unwrap();
+        let stats = analyze_story(&world).unwrap();
+
+        assert!(stats.unreachable_rooms.is_empty(), "{:?}", stats.unreachable_rooms);
+        assert!(stats.dead_end_rooms.is_empty(), "{:?}", stats.dead_end_rooms);
+        assert_eq!(stats.max_depth, 1);
+    }
+}
@@ -1,21 +1,65 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::PathBuf;
+use crate::paths;
+use crate::keymap::Keymap;
+use crate::theme::{Palette, Theme};
+use crate::world::World;
 use crate::errors::{GameError, GameResult};
 
+/// Prefix of the environment variables that override config keys, e.g.
+/// `RESTORATION_TYPEWRITER_SPEED_MS` for `typewriter_speed_ms`.
+pub const ENV_PREFIX: &str = "RESTORATION_";
+
+/// Version of the config file layout, stored as `config_version`. Files without
+/// one are version 0 and are migrated when loaded.
+pub const CONFIG_VERSION: u32 = 1;
+
+const VERSION_KEY: &str = "config_version";
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum UiMode {
+    #[serde(alias = "plain")]
     Plain,    // Original terminal output
+    #[serde(alias = "centered")]
     Centered, // Full-screen centered UI with resize handling
 }
 
+/// How much text the typewriter shows at a time.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum RevealMode {
+    #[serde(alias = "character")]
+    Character,
+    #[serde(alias = "word")]
+    Word,
+    #[serde(alias = "sentence")]
+    Sentence,
+}
+
+// Keys missing from a config file take their values from `GameConfig::default`
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GameConfig {
     pub typewriter_speed_ms: u64,
     pub enable_typewriter: bool,
+    /// Type text a character, a word or a sentence at a time; each step takes
+    /// `typewriter_speed_ms` per character it shows.
+    pub reveal: RevealMode,
     pub allow_text_commands: bool,
     pub auto_save: bool,
+    /// Autosave after every choice instead of only when the room changes.
+    pub auto_save_every_choice: bool,
     pub ui_mode: UiMode,
+    /// Colour theme of the full-screen and web UIs: a built-in one or a key of `palettes`.
+    pub theme: String,
+    /// Themes defined in the config file.
+    pub palettes: BTreeMap<String, Palette>,
+    /// Keys for the full-screen and web UIs, by action name, e.g. `"undo": ["z"]`.
+    /// Actions left out keep their default keys.
+    pub keymap: BTreeMap<String, Vec<String>>,
 }
 
 impl Default for GameConfig {
@@ -23,33 +67,648 @@ impl Default for GameConfig {
         GameConfig {
             typewriter_speed_ms: 30,
             enable_typewriter: true,
+            reveal: RevealMode::Character,
             allow_text_commands: true,
             auto_save: false,
+            auto_save_every_choice: false,
             ui_mode: UiMode::Plain,
+            theme: "default".to_string(),
+            palettes: BTreeMap::new(),
+            keymap: BTreeMap::new(),
         }
     }
 }
 
 impl GameConfig {
-    pub fn load_or_create() -> GameResult<Self> {
-        let config_path = "restoration_config.json";
-        
-        if Path::new(config_path).exists() {
-            let content = fs::read_to_string(config_path)?;
-            let config: GameConfig = serde_json::from_str(&content)
-                .map_err(|e| GameError::ValidationError(format!("Failed to parse config: {}", e)))?;
-            Ok(config)
-        } else {
-            let default_config = GameConfig::default();
-            default_config.save()?;
-            Ok(default_config)
-        }
-    }
-    
-    pub fn save(&self) -> GameResult<()> {
-        let config_str = serde_json::to_string_pretty(self)
-            .map_err(|e| GameError::ValidationError(format!("Failed to serialize config: {}", e)))?;
-        fs::write("restoration_config.json", config_str)?;
+    /// The colour theme named by `theme`.
+    pub fn theme(&self) -> Result<Theme, String> {
+        Theme::named(&self.theme, &self.palettes)
+    }
+
+    /// The default key bindings with `keymap` applied.
+    pub fn keymap(&self) -> Result<Keymap, String> {
+        Keymap::new(&self.keymap)
+    }
+
+    /// Empty the config file, so every key uses its default, including defaults
+    /// changed by later versions.
+    pub fn reset_file() -> GameResult<()> {
+        write_config_file(&Map::new())
+    }
+
+    /// Set `key` in the config file to `raw`, parsed according to the key's type.
+    /// Returns the value stored.
+    pub fn set_in_file(key: &str, raw: &str) -> GameResult<Value> {
+        create_config_file(&mut Vec::new())?;
+        let mut file = migrated(read_config_file()?);
+        // Stored the way the config writes it, e.g. `Centered` for `centered`
+        let value = check_value(key, &parse_value(key, raw)?, &file).map_err(|e| invalid_value(key, raw, &e))?;
+        file.insert(key.to_string(), value.clone());
+        write_config_file(&file)?;
+        Ok(value)
+    }
+
+    /// Remove `key` from the config file so its default applies again.
+    /// Returns whether the file had set it.
+    pub fn unset_in_file(key: &str) -> GameResult<bool> {
+        if !config_keys().iter().any(|known| known == key) {
+            return Err(unknown_key(key));
+        }
+        create_config_file(&mut Vec::new())?;
+        let mut file = migrated(read_config_file()?);
+        if file.remove(key).is_none() {
+            return Ok(false);
+        }
+        write_config_file(&file)?;
+        Ok(true)
+    }
+}
+
+/// Where a config value came from, from lowest to highest precedence.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigOrigin {
+    Default,
+    File,
+    Story,
+    /// The environment variable that set it.
+    Env(String),
+    /// The command-line flag that set it.
+    Cli(String),
+}
+
+impl fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigOrigin::Default => write!(f, "default"),
+            ConfigOrigin::File => write!(f, "config file"),
+            ConfigOrigin::Story => write!(f, "story front matter"),
+            ConfigOrigin::Env(var) => write!(f, "environment ({})", var),
+            ConfigOrigin::Cli(flag) => write!(f, "command line ({})", flag),
+        }
+    }
+}
+
+/// A `GameConfig` built from layers, each overriding the ones before: built-in
+/// defaults, the config file, the story's front matter, `RESTORATION_*` environment
+/// variables and command-line flags. Remembers which layer set each key.
+pub struct ConfigLayers {
+    values: Map<String, Value>,
+    origins: BTreeMap<String, ConfigOrigin>,
+    /// Problems with the config file, story and environment overrides that were
+    /// skipped because they were invalid, and notes about old files being copied.
+    pub warnings: Vec<String>,
+}
+
+impl ConfigLayers {
+    /// Start from `base`, with every key marked as a default.
+    pub fn new(base: &GameConfig) -> Self {
+        let values = to_map(base);
+        let origins = values.keys().map(|key| (key.clone(), ConfigOrigin::Default)).collect();
+        ConfigLayers { values, origins, warnings: Vec::new() }
+    }
+
+    /// Defaults, the config file, the front matter of `world` if given, then the
+    /// environment. Command-line flags are applied by the caller with `set`.
+    pub fn load(world: Option<&World>) -> GameResult<Self> {
+        let mut layers = ConfigLayers::new(&GameConfig::default());
+        layers.apply_file()?;
+        if let Some(world) = world {
+            layers.apply_story(world);
+        }
+        layers.apply_env();
+        Ok(layers)
+    }
+
+    // Problems in the file are warnings: the keys involved keep their defaults. An
+    // older file is migrated in memory without one; `config doctor --fix` updates it
+    fn apply_file(&mut self) -> GameResult<()> {
+        create_config_file(&mut self.warnings)?;
+        let check = check_config_file()?;
+        for (key, value) in check.values {
+            self.values.insert(key.clone(), value);
+            self.origins.insert(key, ConfigOrigin::File);
+        }
+        if !check.issues.is_empty() {
+            for issue in &check.issues {
+                self.warnings.push(format!("Config file: {}", issue.problem));
+            }
+            self.warnings.push("Run `restoration config doctor` to see how to repair the config file".to_string());
+        }
         Ok(())
     }
-}
\ No newline at end of file
+
+    pub fn apply_story(&mut self, world: &World) {
+        for (key, raw) in &world.config {
+            if let Err(e) = self.set(key, raw, ConfigOrigin::Story) {
+                self.warnings.push(format!("Ignoring a setting in the story's front matter: {}", message(e)));
+            }
+        }
+    }
+
+    pub fn apply_env(&mut self) {
+        for key in config_keys() {
+            let var = env_var(&key);
+            if let Some(raw) = std::env::var(&var).ok().filter(|raw| !raw.is_empty()) {
+                if let Err(e) = self.set(&key, &raw, ConfigOrigin::Env(var.clone())) {
+                    self.warnings.push(format!("Ignoring {}: {}", var, message(e)));
+                }
+            }
+        }
+    }
+
+    /// Set `key` from `raw`, parsed according to the key's type in `GameConfig`.
+    pub fn set(&mut self, key: &str, raw: &str, origin: ConfigOrigin) -> GameResult<()> {
+        let value = parse_value(key, raw)?;
+        let previous = self.values.insert(key.to_string(), value);
+        match from_map(&self.values) {
+            Ok(config) => {
+                self.values = to_map(&config);
+                self.origins.insert(key.to_string(), origin);
+                Ok(())
+            }
+            Err(e) => {
+                if let Some(previous) = previous {
+                    self.values.insert(key.to_string(), previous);
+                }
+                Err(invalid_value(key, raw, &e))
+            }
+        }
+    }
+
+    pub fn config(&self) -> GameResult<GameConfig> {
+        from_map(&self.values).map_err(GameError::ValidationError)
+    }
+
+    pub fn get(&self, key: &str) -> GameResult<(&Value, &ConfigOrigin)> {
+        match (self.values.get(key), self.origins.get(key)) {
+            (Some(value), Some(origin)) => Ok((value, origin)),
+            _ => Err(unknown_key(key)),
+        }
+    }
+
+    /// Every key with its value and where it came from, in alphabetical order.
+    pub fn entries(&self) -> Vec<(&str, &Value, &ConfigOrigin)> {
+        self.values
+            .iter()
+            .filter_map(|(key, value)| self.origins.get(key).map(|origin| (key.as_str(), value, origin)))
+            .collect()
+    }
+}
+
+/// Something wrong with the config file, and what `config doctor --fix` does about it.
+#[derive(Debug, Clone)]
+pub struct ConfigIssue {
+    pub problem: String,
+    /// `None` when there is nothing to repair, only something to know.
+    pub repair: Option<String>,
+}
+
+/// The config file checked against `GameConfig`.
+pub struct ConfigCheck {
+    /// The keys that can be used as they are.
+    pub values: Map<String, Value>,
+    pub issues: Vec<ConfigIssue>,
+    /// Set when an older version of the game wrote the file. Its keys are migrated
+    /// in memory either way; the repair writes them back.
+    pub upgrade: Option<ConfigIssue>,
+    /// The file with every issue repaired.
+    pub repaired: Map<String, Value>,
+}
+
+impl ConfigCheck {
+    /// Check the keys of a config file, migrating them to `CONFIG_VERSION` in memory
+    /// if the file is older; only a repair writes the migrated file.
+    pub fn of(file: Map<String, Value>) -> Self {
+        let defaults = to_map(&GameConfig::default());
+        let mut check = ConfigCheck { values: Map::new(), issues: Vec::new(), upgrade: None, repaired: Map::new() };
+        let version = file.get(VERSION_KEY).and_then(Value::as_u64).unwrap_or(0);
+        if version < CONFIG_VERSION as u64 {
+            check.upgrade = Some(ConfigIssue {
+                problem: format!("written by an older version of the game (config_version {})", version),
+                repair: Some(format!("update it to config_version {}", CONFIG_VERSION)),
+            });
+        }
+        let file = migrated(file);
+        // Keys this version doesn't know may be settings of the newer one, so they stay
+        let newer = version > CONFIG_VERSION as u64;
+        if newer {
+            check.issues.push(ConfigIssue {
+                problem: format!("written by a newer version of the game (config_version {}); settings this version doesn't know are ignored", version),
+                repair: None,
+            });
+        }
+
+        for (key, value) in &file {
+            let (key, value) = (key.clone(), value.clone());
+            if key == VERSION_KEY || (newer && !defaults.contains_key(&key)) {
+                check.repaired.insert(key, value);
+                continue;
+            }
+
+            let Some(default) = defaults.get(&key) else {
+                match closest_key(&key).filter(|known| !file.contains_key(known)) {
+                    Some(known) if check_value(&known, &value, &file).is_ok() => {
+                        check.issues.push(ConfigIssue {
+                            problem: format!("unknown key '{}'", key),
+                            repair: Some(format!("rename it to '{}'", known)),
+                        });
+                        check.repaired.insert(known, value);
+                    }
+                    _ => check.issues.push(ConfigIssue {
+                        problem: format!("unknown key '{}'", key),
+                        repair: Some("remove it".to_string()),
+                    }),
+                }
+                continue;
+            };
+
+            match check_value(&key, &value, &file) {
+                Ok(value) => {
+                    check.values.insert(key.clone(), value.clone());
+                    check.repaired.insert(key, value);
+                }
+                Err(e) => check.issues.push(ConfigIssue {
+                    problem: format!("invalid value for {}: {}", key, e),
+                    repair: Some(format!("remove it to use the default ({})", display_value(default))),
+                }),
+            }
+        }
+        check.repaired.entry(VERSION_KEY).or_insert(Value::from(CONFIG_VERSION));
+        check
+    }
+
+    /// The upgrade, if any, followed by the other issues, as `config doctor` lists them.
+    pub fn all_issues(&self) -> impl Iterator<Item = &ConfigIssue> {
+        self.upgrade.iter().chain(&self.issues)
+    }
+
+    /// Whether `config doctor --fix` would change the file.
+    pub fn needs_repair(&self) -> bool {
+        self.all_issues().any(|issue| issue.repair.is_some())
+    }
+
+    // A file that isn't a JSON object at all is replaced with one that leaves
+    // every key at its default
+    fn unreadable(error: String) -> Self {
+        let repaired = migrated(Map::new());
+        ConfigCheck {
+            values: Map::new(),
+            issues: vec![ConfigIssue { problem: error, repair: Some("replace it with the defaults".to_string()) }],
+            upgrade: None,
+            repaired,
+        }
+    }
+}
+
+/// Check the config file without changing it, as loading and `config doctor` do.
+/// A file that doesn't exist yet checks as an empty one.
+pub fn check_config_file() -> GameResult<ConfigCheck> {
+    match read_config_file() {
+        Ok(file) => Ok(ConfigCheck::of(file)),
+        Err(GameError::ValidationError(e)) => Ok(ConfigCheck::unreadable(e)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Write the repaired config file, keeping the file as it was next to it as `.bak`.
+/// Returns the backup's path.
+pub fn repair_config_file(check: &ConfigCheck) -> GameResult<PathBuf> {
+    let config_path = paths::config_path();
+    let mut backup = config_path.clone().into_os_string();
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+    fs::copy(&config_path, &backup)?;
+    write_config_file(&check.repaired)?;
+    Ok(backup)
+}
+
+/// Every config key, as written in the config file.
+pub fn config_keys() -> Vec<String> {
+    to_map(&GameConfig::default()).keys().cloned().collect()
+}
+
+/// The environment variable that overrides `key`.
+pub fn env_var(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.to_uppercase())
+}
+
+/// Parse `raw` as a value for `key`, following the type of the key's default:
+/// `true`/`false` (or yes/no, on/off), numbers, names, or JSON for anything else.
+pub fn parse_value(key: &str, raw: &str) -> GameResult<Value> {
+    let default = to_map(&GameConfig::default()).remove(key).ok_or_else(|| unknown_key(key))?;
+    let raw = raw.trim();
+    let invalid = |expected: &str| GameError::ValidationError(format!("Invalid value '{}' for {}: expected {}", raw, key, expected));
+    match default {
+        Value::Bool(_) => match raw.to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Ok(Value::Bool(true)),
+            "false" | "no" | "off" | "0" => Ok(Value::Bool(false)),
+            _ => Err(invalid("true or false")),
+        },
+        Value::Number(number) if number.is_u64() => raw.parse::<u64>().map(Value::from).map_err(|_| invalid("a whole number")),
+        Value::Number(number) if number.is_i64() => raw.parse::<i64>().map(Value::from).map_err(|_| invalid("a whole number")),
+        Value::Number(_) => raw
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| invalid("a number")),
+        Value::String(_) => Ok(Value::String(raw.to_string())),
+        _ => serde_json::from_str(raw).map_err(|_| invalid("JSON")),
+    }
+}
+
+/// Show a value the way it is typed on the command line.
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+// A value from the config file, as the config would store it. The other keys of
+// `file` that are valid on their own count too, e.g. the palette a theme names.
+fn check_value(key: &str, value: &Value, file: &Map<String, Value>) -> Result<Value, String> {
+    let defaults = to_map(&GameConfig::default());
+    let mut values = defaults.clone();
+    for (other, other_value) in file.iter().filter(|(other, _)| *other != key && defaults.contains_key(*other)) {
+        let mut alone = defaults.clone();
+        alone.insert(other.clone(), other_value.clone());
+        if from_map(&alone).is_ok() {
+            values.insert(other.clone(), other_value.clone());
+        }
+    }
+    values.insert(key.to_string(), value.clone());
+    let config = from_map(&values)?;
+    Ok(to_map(&config).remove(key).unwrap_or_default())
+}
+
+// The known key a mistyped one was most likely meant to be
+fn closest_key(key: &str) -> Option<String> {
+    config_keys()
+        .into_iter()
+        .map(|known| (edit_distance(key, &known), known))
+        .filter(|(distance, _)| *distance <= 3)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| known)
+}
+
+// Levenshtein distance
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn invalid_value(key: &str, raw: &str, reason: &str) -> GameError {
+    GameError::ValidationError(format!("Invalid value '{}' for {}: {}", raw, key, reason))
+}
+
+// The text of an error without the "Validation error" prefix, for warnings
+fn message(error: GameError) -> String {
+    match error {
+        GameError::ValidationError(message) => message,
+        other => other.to_string(),
+    }
+}
+
+fn unknown_key(key: &str) -> GameError {
+    GameError::ValidationError(format!("Unknown config key '{}'; known keys: {}", key, config_keys().join(", ")))
+}
+
+fn to_map(config: &GameConfig) -> Map<String, Value> {
+    match serde_json::to_value(config) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+fn from_map(values: &Map<String, Value>) -> Result<GameConfig, String> {
+    let config: GameConfig = serde_json::from_value(Value::Object(values.clone())).map_err(|e| e.to_string())?;
+    for name in config.palettes.keys() {
+        Theme::named(name, &config.palettes)?;
+    }
+    config.theme()?;
+    config.keymap()?;
+    Ok(config)
+}
+
+// Copy an old config into place, adding a note to `notes`, or else create a missing
+// file with only `config_version`, so every key keeps following its default
+fn create_config_file(notes: &mut Vec<String>) -> GameResult<()> {
+    notes.extend(paths::migrate_legacy_config()?);
+    if !paths::config_path().exists() {
+        write_config_file(&migrated(Map::new()))?;
+    }
+    Ok(())
+}
+
+// The keys the config file sets, as written
+fn read_config_file() -> GameResult<Map<String, Value>> {
+    let config_path = paths::config_path();
+    if !config_path.exists() {
+        return Ok(migrated(Map::new()));
+    }
+    let content = fs::read_to_string(&config_path)?;
+    serde_json::from_str(&content)
+        .map_err(|e| GameError::ValidationError(format!("{} isn't a valid config file: {}", config_path.display(), e)))
+}
+
+// The keys of a file brought up to `CONFIG_VERSION`, one version at a time. Version
+// 1 only started recording `config_version`; later steps go here, oldest first.
+fn migrated(mut file: Map<String, Value>) -> Map<String, Value> {
+    let version = file.get(VERSION_KEY).and_then(Value::as_u64).unwrap_or(0);
+    if version < CONFIG_VERSION as u64 {
+        file.insert(VERSION_KEY.to_string(), Value::from(CONFIG_VERSION));
+    }
+    file
+}
+
+fn write_config_file(values: &Map<String, Value>) -> GameResult<()> {
+    let mut values = values.clone();
+    values.entry(VERSION_KEY).or_insert(Value::from(CONFIG_VERSION));
+    let config_str = serde_json::to_string_pretty(&values)
+        .map_err(|e| GameError::ValidationError(format!("Failed to serialize config: {}", e)))?;
+    let config_path = paths::config_path();
+    if let Some(dir) = config_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(config_path, config_str)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::load_world_from_markdown_content;
+
+    // Point the config file at a fresh directory while the lock is held
+    fn config_in(name: &str) -> PathBuf {
+        let config_path = paths::scratch_dir(name).join("config.json");
+        std::env::set_var(paths::CONFIG_ENV, &config_path);
+        config_path
+    }
+
+    #[test]
+    fn layers_override_in_order() {
+        let _environment = paths::lock_environment();
+        let config_path = config_in("config-layers");
+        fs::write(&config_path, r#"{"config_version": 1, "typewriter_speed_ms": 10, "reveal": "Word", "auto_save": true, "theme": "sepia"}"#).unwrap();
+        std::env::set_var("RESTORATION_REVEAL", "sentence");
+        std::env::set_var("RESTORATION_AUTO_SAVE", "yes");
+        let world = load_world_from_markdown_content("---\ntheme: monochrome\nauto_save: no\n---\n# Layers\n\n@start hall\n\n## @room hall\nA hall.\n\n### Wait\n- \"Time passes.\"\n").unwrap();
+
+        let layers = ConfigLayers::load(Some(&world));
+        std::env::remove_var("RESTORATION_REVEAL");
+        std::env::remove_var("RESTORATION_AUTO_SAVE");
+        let mut layers = layers.unwrap();
+        layers.set("auto_save", "off", ConfigOrigin::Cli("--set".to_string())).unwrap();
+
+        let origin = |key: &str| layers.get(key).unwrap().1.clone();
+        assert_eq!(origin("enable_typewriter"), ConfigOrigin::Default);
+        assert_eq!(origin("typewriter_speed_ms"), ConfigOrigin::File);
+        assert_eq!(origin("theme"), ConfigOrigin::Story);
+        assert_eq!(origin("reveal"), ConfigOrigin::Env("RESTORATION_REVEAL".to_string()));
+        assert_eq!(origin("auto_save"), ConfigOrigin::Cli("--set".to_string()));
+
+        let config = layers.config().unwrap();
+        assert_eq!(config.typewriter_speed_ms, 10);
+        assert_eq!(config.theme, "monochrome");
+        assert_eq!(config.reveal, RevealMode::Sentence);
+        assert!(!config.auto_save);
+        assert!(layers.warnings.is_empty(), "{:?}", layers.warnings);
+        std::env::remove_var(paths::CONFIG_ENV);
+    }
+
+    #[test]
+    fn invalid_overrides_are_warnings_that_keep_the_value_before() {
+        let _environment = paths::lock_environment();
+        config_in("config-invalid-override");
+        std::env::set_var("RESTORATION_TYPEWRITER_SPEED_MS", "fast");
+        let layers = ConfigLayers::load(None);
+        std::env::remove_var("RESTORATION_TYPEWRITER_SPEED_MS");
+        let mut layers = layers.unwrap();
+
+        assert_eq!(layers.config().unwrap().typewriter_speed_ms, 30);
+        assert_eq!(layers.warnings, ["Ignoring RESTORATION_TYPEWRITER_SPEED_MS: Invalid value 'fast' for typewriter_speed_ms: expected a whole number"]);
+        assert!(layers.set("theme", "neon", ConfigOrigin::Cli("--set".to_string())).is_err());
+        assert_eq!(layers.get("theme").unwrap(), (&Value::from("default"), &ConfigOrigin::Default));
+        std::env::remove_var(paths::CONFIG_ENV);
+    }
+
+    #[test]
+    fn set_and_unset_round_trip_through_the_file() {
+        let _environment = paths::lock_environment();
+        let config_path = config_in("config-set-unset");
+
+        assert_eq!(GameConfig::set_in_file("ui_mode", "centered").unwrap(), Value::from("Centered"));
+        assert_eq!(GameConfig::set_in_file("keymap", r#"{"undo": ["z"]}"#).unwrap()["undo"], serde_json::json!(["z"]));
+        let file: Map<String, Value> = serde_json::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+        assert_eq!(file.keys().collect::<Vec<_>>(), ["config_version", "keymap", "ui_mode"]);
+        let layers = ConfigLayers::load(None).unwrap();
+        assert_eq!(layers.get("ui_mode").unwrap(), (&Value::from("Centered"), &ConfigOrigin::File));
+
+        assert!(GameConfig::set_in_file("ui_mode", "sideways").is_err());
+        assert!(GameConfig::set_in_file("colour", "red").is_err());
+        assert!(GameConfig::unset_in_file("ui_mode").unwrap());
+        assert!(!GameConfig::unset_in_file("ui_mode").unwrap());
+        assert!(GameConfig::unset_in_file("colour").is_err());
+        let layers = ConfigLayers::load(None).unwrap();
+        assert_eq!(layers.get("ui_mode").unwrap(), (&Value::from("Plain"), &ConfigOrigin::Default));
+        std::env::remove_var(paths::CONFIG_ENV);
+    }
+
+    #[test]
+    fn values_parse_by_the_type_of_their_key() {
+        assert_eq!(parse_value("auto_save", " Yes ").unwrap(), Value::Bool(true));
+        assert_eq!(parse_value("enable_typewriter", "off").unwrap(), Value::Bool(false));
+        assert_eq!(parse_value("typewriter_speed_ms", "45").unwrap(), Value::from(45));
+        assert_eq!(parse_value("theme", " sepia ").unwrap(), Value::from("sepia"));
+        assert_eq!(parse_value("keymap", r#"{"undo": ["z"]}"#).unwrap(), serde_json::json!({ "undo": ["z"] }));
+
+        let error = parse_value("typewriter_speed_ms", "-5").unwrap_err().to_string();
+        assert!(error.contains("expected a whole number"), "{}", error);
+        assert!(parse_value("auto_save", "maybe").unwrap_err().to_string().contains("expected true or false"));
+        assert!(parse_value("keymap", "undo=z").unwrap_err().to_string().contains("expected JSON"));
+        assert!(parse_value("colour", "red").unwrap_err().to_string().contains("Unknown config key 'colour'"));
+    }
+
+    fn file(json: &str) -> Map<String, Value> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn older_files_are_migrated_in_memory() {
+        let check = ConfigCheck::of(file(r#"{"theme": "sepia"}"#));
+        assert!(check.issues.is_empty());
+        assert_eq!(check.upgrade.as_ref().unwrap().problem, "written by an older version of the game (config_version 0)");
+        assert_eq!(check.values["theme"], "sepia");
+        assert_eq!(check.repaired, file(r#"{"config_version": 1, "theme": "sepia"}"#));
+        assert!(check.needs_repair());
+
+        assert!(ConfigCheck::of(file(r#"{"config_version": 1}"#)).upgrade.is_none());
+    }
+
+    #[test]
+    fn loading_an_older_file_neither_warns_nor_rewrites_it() {
+        let _environment = paths::lock_environment();
+        let config_path = config_in("config-older-file");
+        fs::write(&config_path, r#"{"theme": "sepia"}"#).unwrap();
+
+        let layers = ConfigLayers::load(None).unwrap();
+        assert!(layers.warnings.is_empty(), "{:?}", layers.warnings);
+        assert_eq!(layers.config().unwrap().theme, "sepia");
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), r#"{"theme": "sepia"}"#);
+        std::env::remove_var(paths::CONFIG_ENV);
+    }
+
+    #[test]
+    fn the_doctor_suggests_a_repair_for_each_problem() {
+        let check = ConfigCheck::of(file(r#"{"config_version": 1, "enable_typewritter": false, "colour": "red", "typewriter_speed_ms": "fast", "theme": "sepia"}"#));
+        let issues: Vec<(&str, Option<&str>)> = check.all_issues().map(|issue| (issue.problem.as_str(), issue.repair.as_deref())).collect();
+        assert_eq!(issues.len(), 3);
+        assert!(issues.contains(&("unknown key 'colour'", Some("remove it"))));
+        assert!(issues.contains(&("unknown key 'enable_typewritter'", Some("rename it to 'enable_typewriter'"))));
+        assert!(issues.iter().any(|(problem, repair)| problem.starts_with("invalid value for typewriter_speed_ms")
+            && *repair == Some("remove it to use the default (30)")));
+        assert_eq!(check.values, file(r#"{"theme": "sepia"}"#));
+        assert_eq!(check.repaired, file(r#"{"config_version": 1, "enable_typewriter": false, "theme": "sepia"}"#));
+    }
+
+    #[test]
+    fn the_doctor_leaves_settings_of_newer_versions_alone() {
+        let check = ConfigCheck::of(file(r#"{"config_version": 9, "sound": "loud", "theme": "sepia"}"#));
+        assert!(check.upgrade.is_none());
+        assert_eq!(check.issues.len(), 1);
+        assert!(check.issues[0].repair.is_none());
+        assert!(!check.needs_repair());
+        assert_eq!(check.repaired, file(r#"{"config_version": 9, "sound": "loud", "theme": "sepia"}"#));
+    }
+
+    #[test]
+    fn checking_reads_the_file_and_repairing_keeps_a_backup() {
+        let _environment = paths::lock_environment();
+        let config_path = config_in("config-doctor");
+
+        let check = check_config_file().unwrap();
+        assert!(check.all_issues().next().is_none());
+        assert!(!config_path.exists());
+
+        fs::write(&config_path, "not json").unwrap();
+        let check = check_config_file().unwrap();
+        assert!(check.issues[0].problem.contains("isn't a valid config file"), "{}", check.issues[0].problem);
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), "not json");
+
+        let backup = repair_config_file(&check).unwrap();
+        assert_eq!(fs::read_to_string(backup).unwrap(), "not json");
+        assert!(check_config_file().unwrap().all_issues().next().is_none());
+        std::env::remove_var(paths::CONFIG_ENV);
+    }
+}
@@ -0,0 +1,674 @@
+use serde::Serialize;
+use crate::world::{Action, ChoiceId, FlagId, World};
+use crate::game::{
+    GameState, PendingInput, check_single_condition, format_dialogue_line, get_available_choices,
+    get_dialogue_lines, get_room_description, interpolate_text, resolve_check,
+};
+use crate::errors::{GameError, GameResult};
+
+/// How many choices can be undone before the oldest snapshot is dropped.
+pub const MAX_UNDO_HISTORY: usize = 50;
+
+/// Roll message used unless the story sets `@check_message`.
+/// Placeholders: `{skill}`, `{dice}`, `{roll}`, `{total}`, `{target}`, `{result}`.
+pub const DEFAULT_CHECK_MESSAGE: &str = "[{skill} check: {total} vs {target} — {result}]";
+
+/// Something that happened while executing a choice.
+/// Front ends render these; they never change the game state themselves.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum GameEvent {
+    /// Narrative text from a display action, already interpolated.
+    Text(String),
+    CounterChanged {
+        counter: String,
+        old_value: i32,
+        new_value: i32,
+    },
+    FlagChanged {
+        flag: FlagId,
+        is_set: bool,
+    },
+    /// The story is waiting for free text; answer with `Engine::provide_input`.
+    InputRequested {
+        variable: String,
+        prompt: String,
+    },
+    /// The answer given to an `InputRequested`, as stored; first in the events of
+    /// `Engine::provide_input`.
+    InputProvided {
+        variable: String,
+        answer: String,
+    },
+    EndingReached {
+        id: String,
+        title: String,
+    },
+    /// A skill check was rolled; `message` is the story's formatted roll message.
+    CheckRolled {
+        skill: String,
+        roll: i32,
+        total: i32,
+        target: i32,
+        success: bool,
+        message: String,
+    },
+    /// Emitted at most once per playthrough for each achievement.
+    AchievementUnlocked {
+        id: String,
+        title: String,
+    },
+    /// A conversation started or jumped to `node`; its lines follow as `DialogueLine`s.
+    DialogueEntered {
+        node: String,
+    },
+    DialogueLine {
+        speaker: Option<String>,
+        text: String,
+    },
+    /// The conversation ended, by `exit` or by leaving the room.
+    DialogueEnded,
+    /// Emitted once per step, after all other text, when the player ends up in a new room.
+    RoomChanged {
+        from: String,
+        to: String,
+        description: String,
+    },
+    Quit,
+}
+
+impl GameEvent {
+    /// The line a front end should print for this event, if it has one.
+    pub fn display_text(&self) -> Option<String> {
+        match self {
+            GameEvent::Text(text) => Some(text.clone()),
+            GameEvent::CounterChanged { counter, old_value, new_value } => {
+                Some(format!("[{}: {} → {}]", counter, old_value, new_value))
+            }
+            GameEvent::EndingReached { title, .. } => Some(format!("[Ending: {}]", title)),
+            GameEvent::DialogueLine { speaker, text } => Some(format_dialogue_line(speaker.as_deref(), text)),
+            GameEvent::CheckRolled { message, .. } if !message.is_empty() => Some(message.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// The single implementation of action semantics shared by every front end.
+/// It only changes the game state it is given; to watch a game, play it through
+/// `observer::Observers`.
+pub struct Engine;
+
+impl Engine {
+    /// Whether `choice_id` can be made now; `choose` fails with the same error if not.
+    pub fn check_choice(world: &World, game_state: &GameState, choice_id: &ChoiceId) -> GameResult<()> {
+        if game_state.pending_input.is_some() {
+            return Err(GameError::ValidationError("The story is waiting for text input".to_string()));
+        }
+
+        let available = get_available_choices(world, game_state)?;
+        if !available.iter().any(|choice| choice.id == *choice_id) {
+            return Err(GameError::ValidationError(format!(
+                "Choice '{}' is not available in room '{}'",
+                choice_id.0, game_state.current_room_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Execute an available choice and return what happened, in order.
+    pub fn choose(world: &World, game_state: &mut GameState, choice_id: ChoiceId) -> GameResult<Vec<GameEvent>> {
+        Engine::check_choice(world, game_state, &choice_id)?;
+
+        record_history(world, game_state);
+        // Fresh one-shot rolls for conditions on every step
+        game_state.rng.next_u64();
+
+        let origin_room_id = game_state.current_room_id.clone();
+        let mut events = Vec::new();
+        let paused = run_actions(world, game_state, &choice_id, 0, &origin_room_id, &mut events)?;
+        if !paused {
+            finish_step(world, game_state, &origin_room_id, false, &mut events);
+        }
+        Ok(events)
+    }
+
+    /// Answer an `InputRequested` event and run the rest of the choice.
+    pub fn provide_input(world: &World, game_state: &mut GameState, answer: &str) -> GameResult<Vec<GameEvent>> {
+        let pending = game_state
+            .pending_input
+            .take()
+            .ok_or_else(|| GameError::ValidationError("The story is not waiting for text input".to_string()))?;
+
+        let answer = answer.trim().to_string();
+        game_state.strings.insert(pending.variable.clone(), answer.clone());
+
+        let choice_id = ChoiceId(pending.choice_id.clone());
+        let mut events = vec![GameEvent::InputProvided { variable: pending.variable.clone(), answer }];
+        let paused = run_actions(world, game_state, &choice_id, pending.next_action, &pending.origin_room_id, &mut events)?;
+        if !paused {
+            finish_step(world, game_state, &pending.origin_room_id, pending.entered_dialogue, &mut events);
+        }
+        Ok(events)
+    }
+
+    /// Restore the state from `steps` choices ago; returns how many were actually undone.
+    pub fn rewind(world: &World, game_state: &mut GameState, steps: usize) -> GameResult<usize> {
+        if world.no_undo {
+            return Err(GameError::ValidationError("Undo is disabled for this story".to_string()));
+        }
+        if steps == 0 || game_state.history.is_empty() {
+            return Err(GameError::ValidationError("Nothing to undo".to_string()));
+        }
+
+        let steps = steps.min(game_state.history.len());
+        let mut history = std::mem::take(&mut game_state.history);
+        history.truncate(history.len() - steps + 1);
+        if let Some(mut restored) = history.pop() {
+            restored.history = history;
+            *game_state = restored;
+        }
+        Ok(steps)
+    }
+
+    /// Description of the room the player is currently in, or the lines of the
+    /// conversation they are having there.
+    pub fn describe(world: &World, game_state: &GameState) -> GameResult<String> {
+        if let Some(node_id) = &game_state.dialogue {
+            let node = world
+                .dialogues
+                .get(node_id)
+                .ok_or_else(|| GameError::ValidationError(format!("Dialogue '{}' referenced but not defined", node_id)))?;
+            let lines: Vec<String> = get_dialogue_lines(node, game_state)
+                .iter()
+                .map(|(speaker, text)| format_dialogue_line(speaker.as_deref(), text))
+                .collect();
+            return Ok(lines.join("\n"));
+        }
+
+        let room = world
+            .rooms
+            .get(&game_state.current_room_id)
+            .ok_or_else(|| GameError::MissingRoom(game_state.current_room_id.clone()))?;
+        Ok(get_room_description(room, game_state))
+    }
+}
+
+// Runs actions from `start` onwards; returns true if execution paused for text input
+fn run_actions(
+    world: &World,
+    game_state: &mut GameState,
+    choice_id: &ChoiceId,
+    start: usize,
+    origin_room_id: &str,
+    events: &mut Vec<GameEvent>,
+) -> GameResult<bool> {
+    let choice = world
+        .choices
+        .get(&choice_id.0)
+        .ok_or_else(|| GameError::MissingChoice(choice_id.0.clone()))?;
+
+    for (index, action) in choice.actions.iter().enumerate().skip(start) {
+        if run_action(world, game_state, action, choice_id, index, origin_room_id, events)? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+// Applies one action; returns true if execution paused for text input
+fn run_action(
+    world: &World,
+    game_state: &mut GameState,
+    action: &Action,
+    choice_id: &ChoiceId,
+    index: usize,
+    origin_room_id: &str,
+    events: &mut Vec<GameEvent>,
+) -> GameResult<bool> {
+    match action {
+        Action::GoTo(room_id) => {
+            game_state.current_room_id = room_id.clone();
+            if game_state.dialogue.take().is_some() {
+                events.push(GameEvent::DialogueEnded);
+            }
+        }
+        Action::Dialogue(node_id) => {
+            game_state.dialogue = Some(node_id.clone());
+            events.push(GameEvent::DialogueEntered { node: node_id.clone() });
+        }
+        Action::ExitDialogue => {
+            if game_state.dialogue.take().is_some() {
+                events.push(GameEvent::DialogueEnded);
+            }
+        }
+        Action::SetFlag(flag_id) => {
+            if game_state.flags.insert(flag_id.clone()) {
+                events.push(GameEvent::FlagChanged { flag: flag_id.clone(), is_set: true });
+            }
+        }
+        Action::RemoveFlag(flag_id) => {
+            if game_state.flags.remove(flag_id) {
+                events.push(GameEvent::FlagChanged { flag: flag_id.clone(), is_set: false });
+            }
+        }
+        Action::Quit => game_state.has_quit = true,
+        Action::Ending(ending_id) => {
+            let title = world
+                .endings
+                .iter()
+                .find(|ending| &ending.id == ending_id)
+                .map(|ending| ending.title.clone())
+                .unwrap_or_else(|| ending_id.clone());
+            game_state.has_quit = true;
+            game_state.ending = Some(ending_id.clone());
+            events.push(GameEvent::EndingReached { id: ending_id.clone(), title });
+        }
+        Action::Achieve(achievement_id) => {
+            unlock_achievement(world, game_state, achievement_id, events);
+        }
+        Action::IncrementCounter(counter) => {
+            let old_value = *game_state.counters.get(counter).unwrap_or(&0);
+            set_counter(world, game_state, counter, old_value + 1, events);
+        }
+        Action::DecrementCounter(counter) => {
+            let old_value = *game_state.counters.get(counter).unwrap_or(&0);
+            set_counter(world, game_state, counter, old_value - 1, events);
+        }
+        Action::SetCounter(counter, value) => {
+            set_counter(world, game_state, counter, *value, events);
+        }
+        Action::DisplayText(text) => {
+            events.push(GameEvent::Text(interpolate_text(text, game_state)));
+        }
+        Action::DisplayTextConditional { condition, text_if_true, text_if_false } => {
+            let text = if check_single_condition(condition, game_state) {
+                text_if_true
+            } else {
+                text_if_false
+            };
+            events.push(GameEvent::Text(interpolate_text(text, game_state)));
+        }
+        Action::AskText { variable, prompt } => {
+            game_state.pending_input = Some(PendingInput {
+                choice_id: choice_id.0.clone(),
+                next_action: index + 1,
+                variable: variable.clone(),
+                origin_room_id: origin_room_id.to_string(),
+                entered_dialogue: events.iter().any(|event| matches!(event, GameEvent::DialogueEntered { .. })),
+            });
+            events.push(GameEvent::InputRequested {
+                variable: variable.clone(),
+                prompt: interpolate_text(prompt, game_state),
+            });
+            return Ok(true);
+        }
+        Action::SkillCheck { check, on_success, on_failure } => {
+            let roll = game_state.rng.roll(check.dice, check.sides);
+            let result = resolve_check(check, roll, game_state);
+            let template = world.check_message.as_deref().unwrap_or(DEFAULT_CHECK_MESSAGE);
+            let message = template
+                .replace("{skill}", &check.label())
+                .replace("{dice}", &check.dice_notation())
+                .replace("{roll}", &result.roll.to_string())
+                .replace("{total}", &result.total.to_string())
+                .replace("{target}", &check.target.to_string())
+                .replace("{result}", if result.success { "success" } else { "failure" });
+            events.push(GameEvent::CheckRolled {
+                skill: check.label(),
+                roll: result.roll,
+                total: result.total,
+                target: check.target,
+                success: result.success,
+                message,
+            });
+
+            let outcome = if result.success { Some(on_success) } else { on_failure.as_ref() };
+            if let Some(outcome) = outcome {
+                return run_action(world, game_state, outcome, choice_id, index, origin_room_id, events);
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+// Snapshot the state (without its own history) before a choice changes it
+fn record_history(world: &World, game_state: &mut GameState) {
+    if world.no_undo {
+        return;
+    }
+    let mut history = std::mem::take(&mut game_state.history);
+    history.push(game_state.clone());
+    if history.len() > MAX_UNDO_HISTORY {
+        history.remove(0);
+    }
+    game_state.history = history;
+}
+
+fn set_counter(world: &World, game_state: &mut GameState, counter: &str, new_value: i32, events: &mut Vec<GameEvent>) {
+    // Declared ranges hold however the story changes the counter
+    let new_value = match world.counter_range(counter) {
+        Some((min, max)) => new_value.clamp(min, max),
+        None => new_value,
+    };
+    let old_value = game_state.counters.insert(counter.to_string(), new_value).unwrap_or(0);
+    // A counter already at a bound doesn't move, so there is nothing to report
+    if old_value != new_value {
+        events.push(GameEvent::CounterChanged {
+            counter: counter.to_string(),
+            old_value,
+            new_value,
+        });
+    }
+}
+
+fn unlock_achievement(world: &World, game_state: &mut GameState, achievement_id: &str, events: &mut Vec<GameEvent>) {
+    if !game_state.achievements.insert(achievement_id.to_string()) {
+        return;
+    }
+    let title = world
+        .achievements
+        .iter()
+        .find(|achievement| achievement.id == achievement_id)
+        .map(|achievement| achievement.title.clone())
+        .unwrap_or_else(|| achievement_id.to_string());
+    events.push(GameEvent::AchievementUnlocked { id: achievement_id.to_string(), title });
+}
+
+// `entered_dialogue` covers a jump made before the step paused for text input
+fn finish_step(world: &World, game_state: &mut GameState, origin_room_id: &str, entered_dialogue: bool, events: &mut Vec<GameEvent>) {
+    // Achievements with a condition unlock themselves once it holds
+    for achievement in &world.achievements {
+        if let Some(condition) = &achievement.condition {
+            if !game_state.achievements.contains(&achievement.id) && check_single_condition(condition, game_state) {
+                unlock_achievement(world, game_state, &achievement.id, events);
+            }
+        }
+    }
+
+    // Show the node the conversation ended up at
+    if entered_dialogue || events.iter().any(|event| matches!(event, GameEvent::DialogueEntered { .. })) {
+        if let Some(node) = game_state.dialogue.as_ref().and_then(|node_id| world.dialogues.get(node_id)) {
+            for (speaker, text) in get_dialogue_lines(node, game_state) {
+                events.push(GameEvent::DialogueLine { speaker, text });
+            }
+        }
+    }
+
+    if game_state.current_room_id != origin_room_id {
+        let description = world
+            .rooms
+            .get(&game_state.current_room_id)
+            .map(|room| get_room_description(room, game_state))
+            .unwrap_or_default();
+        events.push(GameEvent::RoomChanged {
+            from: origin_room_id.to_string(),
+            to: game_state.current_room_id.clone(),
+            description,
+        });
+    }
+    if game_state.has_quit {
+        events.push(GameEvent::Quit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::load_world_from_markdown_content;
+
+    const STORY: &str = r#"# Engine test
+
+@start cellar
+@var courage 0..2 = 1
+
+## @room cellar
+A dusty cellar.
+
+### Look around
+- "You find a key."
+- flag+ has_key
+- count+ courage
+
+### Go north
+- @hallway
+
+### Talk to the cat
+- -> cat
+
+## @room hallway
+A narrow hallway.
+
+### Leave
+- quit
+
+## @dialogue cat
+Cat: "Mrow."
+
+### Say goodbye
+- exit
+"#;
+
+    fn world() -> World {
+        load_world_from_markdown_content(STORY).expect("the test story loads")
+    }
+
+    fn choose(world: &World, game_state: &mut GameState, text: &str) -> Vec<GameEvent> {
+        let choice_id = get_available_choices(world, game_state)
+            .unwrap()
+            .iter()
+            .find(|choice| choice.text == text)
+            .map(|choice| choice.id.clone())
+            .unwrap_or_else(|| panic!("'{}' is not available", text));
+        Engine::choose(world, game_state, choice_id).unwrap()
+    }
+
+    #[test]
+    fn choose_reports_text_flags_and_counters_in_order() {
+        let world = world();
+        let mut game_state = GameState::for_world(&world, 1);
+
+        let events = choose(&world, &mut game_state, "Look around");
+        assert_eq!(events, vec![
+            GameEvent::Text("\"You find a key.\"".to_string()),
+            GameEvent::FlagChanged { flag: FlagId("has_key".to_string()), is_set: true },
+            GameEvent::CounterChanged { counter: "courage".to_string(), old_value: 1, new_value: 2 },
+        ]);
+    }
+
+    #[test]
+    fn choose_skips_counters_held_at_a_bound() {
+        let world = world();
+        let mut game_state = GameState::for_world(&world, 1);
+        game_state.counters.insert("courage".to_string(), 2);
+
+        let events = choose(&world, &mut game_state, "Look around");
+        assert!(!events.iter().any(|event| matches!(event, GameEvent::CounterChanged { .. })));
+        assert_eq!(game_state.counters["courage"], 2);
+    }
+
+    #[test]
+    fn choose_reports_room_changes_and_quitting() {
+        let world = world();
+        let mut game_state = GameState::for_world(&world, 1);
+
+        let events = choose(&world, &mut game_state, "Go north");
+        assert_eq!(events, vec![GameEvent::RoomChanged {
+            from: "cellar".to_string(),
+            to: "hallway".to_string(),
+            description: Engine::describe(&world, &game_state).unwrap(),
+        }]);
+
+        assert_eq!(choose(&world, &mut game_state, "Leave"), vec![GameEvent::Quit]);
+        assert!(game_state.has_quit);
+    }
+
+    #[test]
+    fn choose_rejects_unavailable_choices() {
+        let world = world();
+        let mut game_state = GameState::for_world(&world, 1);
+
+        let result = Engine::choose(&world, &mut game_state, ChoiceId("hallway_choice_0".to_string()));
+        assert!(result.is_err());
+        assert!(game_state.history.is_empty());
+    }
+
+    #[test]
+    fn describe_shows_the_room_or_the_conversation() {
+        let world = world();
+        let mut game_state = GameState::for_world(&world, 1);
+        assert!(Engine::describe(&world, &game_state).unwrap().contains("A dusty cellar."));
+
+        let events = choose(&world, &mut game_state, "Talk to the cat");
+        assert_eq!(events, vec![
+            GameEvent::DialogueEntered { node: "cat".to_string() },
+            GameEvent::DialogueLine { speaker: Some("Cat".to_string()), text: "\"Mrow.\"".to_string() },
+        ]);
+        assert_eq!(Engine::describe(&world, &game_state).unwrap(), format_dialogue_line(Some("Cat"), "\"Mrow.\""));
+
+        assert_eq!(choose(&world, &mut game_state, "Say goodbye"), vec![GameEvent::DialogueEnded]);
+        assert!(Engine::describe(&world, &game_state).unwrap().contains("A dusty cellar."));
+    }
+
+    #[test]
+    fn rewind_restores_earlier_states() {
+        let world = world();
+        let mut game_state = GameState::for_world(&world, 1);
+        let start_hash = game_state.state_hash();
+
+        choose(&world, &mut game_state, "Look around");
+        let after_look = game_state.state_hash();
+        choose(&world, &mut game_state, "Go north");
+
+        assert_eq!(Engine::rewind(&world, &mut game_state, 1).unwrap(), 1);
+        assert_eq!(game_state.state_hash(), after_look);
+        assert_eq!(game_state.current_room_id, "cellar");
+
+        // Asking for more steps than were taken goes back to the start
+        assert_eq!(Engine::rewind(&world, &mut game_state, 5).unwrap(), 1);
+        assert_eq!(game_state.state_hash(), start_hash);
+        assert!(Engine::rewind(&world, &mut game_state, 1).is_err());
+    }
+
+    #[test]
+    fn choose_walks_through_a_dialogue() {
+        let world = load_world_from_markdown_content(
+            "# Talk\n\n@start home\n\n## @room home\nA cabin.\n\n### Talk to Alvin\n- -> alvin\n\n## @room yard\nA yard.\n\n### Wait\n- \"Nothing happens.\"\n\n## @dialogue alvin\nAlvin: \"Evening.\"\n[asked] Alvin: \"Anything else?\"\n\n### Ask about the preacher [!asked]\n- flag+ asked\n- -> preacher\n\n### Step outside\n- @yard\n\n### Say goodnight\n- exit\n\n## @dialogue preacher\nAlvin: \"He speaks tonight.\"\n\n### Go on\n- -> alvin\n",
+        )
+        .unwrap();
+        let mut game_state = GameState::for_world(&world, 1);
+        let texts = |world: &World, game_state: &GameState| -> Vec<String> {
+            get_available_choices(world, game_state).unwrap().iter().map(|choice| choice.text.clone()).collect()
+        };
+
+        choose(&world, &mut game_state, "Talk to Alvin");
+        assert_eq!(game_state.dialogue.as_deref(), Some("alvin"));
+        assert_eq!(texts(&world, &game_state), vec!["Ask about the preacher", "Step outside", "Say goodnight"]);
+
+        // Jumping to another node shows its lines and responses
+        let events = choose(&world, &mut game_state, "Ask about the preacher");
+        assert_eq!(events, vec![
+            GameEvent::FlagChanged { flag: FlagId("asked".to_string()), is_set: true },
+            GameEvent::DialogueEntered { node: "preacher".to_string() },
+            GameEvent::DialogueLine { speaker: Some("Alvin".to_string()), text: "\"He speaks tonight.\"".to_string() },
+        ]);
+        assert_eq!(texts(&world, &game_state), vec!["Go on"]);
+
+        // Coming back shows the conditional line and hides the response already taken
+        let events = choose(&world, &mut game_state, "Go on");
+        assert_eq!(events[1..], [
+            GameEvent::DialogueLine { speaker: Some("Alvin".to_string()), text: "\"Evening.\"".to_string() },
+            GameEvent::DialogueLine { speaker: Some("Alvin".to_string()), text: "\"Anything else?\"".to_string() },
+        ]);
+        assert_eq!(texts(&world, &game_state), vec!["Step outside", "Say goodnight"]);
+
+        // Going to another room also ends the conversation
+        let events = choose(&world, &mut game_state, "Step outside");
+        assert_eq!(events[0], GameEvent::DialogueEnded);
+        assert!(matches!(&events[1], GameEvent::RoomChanged { to, .. } if to == "yard"));
+        assert_eq!(game_state.dialogue, None);
+        assert_eq!(texts(&world, &game_state), vec!["Wait"]);
+    }
+
+    #[test]
+    fn history_keeps_only_the_latest_choices() {
+        let world = world();
+        let mut game_state = GameState::for_world(&world, 1);
+
+        for _ in 0..MAX_UNDO_HISTORY + 5 {
+            choose(&world, &mut game_state, "Look around");
+        }
+        assert_eq!(game_state.history.len(), MAX_UNDO_HISTORY);
+        assert!(game_state.history.iter().all(|snapshot| snapshot.history.is_empty()));
+        assert_eq!(Engine::rewind(&world, &mut game_state, MAX_UNDO_HISTORY + 5).unwrap(), MAX_UNDO_HISTORY);
+    }
+
+    #[test]
+    fn no_undo_stories_keep_no_history() {
+        let world = load_world_from_markdown_content(&STORY.replace("@start cellar", "@start cellar\n@no_undo")).unwrap();
+        assert!(world.no_undo);
+        let mut game_state = GameState::for_world(&world, 1);
+
+        choose(&world, &mut game_state, "Go north");
+        assert!(game_state.history.is_empty());
+        let error = Engine::rewind(&world, &mut game_state, 1).unwrap_err().to_string();
+        assert!(error.contains("Undo is disabled for this story"), "{}", error);
+        assert_eq!(game_state.current_room_id, "hallway");
+    }
+
+    #[test]
+    fn achievements_unlock_once_by_action_or_condition() {
+        let world = load_world_from_markdown_content(
+            "# Chores\n\n@start barn\n@achievement patient \"Baled all the hay\"\n@achievement strong \"Strong\" [hay > 1]\n\n## @room barn\nA barn.\n\n### Bale hay\n- count+ hay\n- achieve patient\n",
+        )
+        .unwrap();
+        let mut game_state = GameState::for_world(&world, 1);
+        let unlocked = |events: &[GameEvent]| -> Vec<String> {
+            events.iter().filter_map(|event| match event {
+                GameEvent::AchievementUnlocked { id, .. } => Some(id.clone()),
+                _ => None,
+            }).collect()
+        };
+
+        assert_eq!(unlocked(&choose(&world, &mut game_state, "Bale hay")), ["patient"]);
+        assert_eq!(unlocked(&choose(&world, &mut game_state, "Bale hay")), ["strong"]);
+        assert!(unlocked(&choose(&world, &mut game_state, "Bale hay")).is_empty());
+        assert_eq!(game_state.achievements.len(), 2);
+    }
+
+    const BRIDGE: &str = "# Bridge\n\n@start ledge\n@var courage = 3\n\n## @room ledge\nA ledge.\n\n### Jump\n- check d6 + courage >= TARGET: @far_side else: \"You fall short.\"\n\n### Peek [check d1 >= 2]\n- \"You see nothing.\"\n\n## @room far_side\nThe far side.\n\n### Rest\n- quit\n";
+
+    #[test]
+    fn checks_report_the_roll_and_run_one_outcome() {
+        let world = load_world_from_markdown_content(&BRIDGE.replace("TARGET", "4")).unwrap();
+        let mut game_state = GameState::for_world(&world, 9);
+        let events = choose(&world, &mut game_state, "Jump");
+        match &events[0] {
+            GameEvent::CheckRolled { skill, roll, total, target, success, message } => {
+                assert_eq!((skill.as_str(), *total, *target, *success), ("Courage", roll + 3, 4, true));
+                assert_eq!(message, &format!("[Courage check: {} vs 4 — success]", total));
+            }
+            other => panic!("expected a roll, got {:?}", other),
+        }
+        assert_eq!(game_state.current_room_id, "far_side");
+
+        let world = load_world_from_markdown_content(
+            &BRIDGE.replace("TARGET", "10").replace("@start ledge", "@start ledge\n@check_message \"({skill} {roll}+{dice})\""),
+        )
+        .unwrap();
+        let mut game_state = GameState::for_world(&world, 9);
+        let events = choose(&world, &mut game_state, "Jump");
+        assert!(matches!(&events[0], GameEvent::CheckRolled { success: false, message, .. } if message.starts_with("(Courage ") && message.ends_with("+d6)")));
+        assert_eq!(events[1], GameEvent::Text("You fall short.".to_string()));
+        assert_eq!(game_state.current_room_id, "ledge");
+    }
+
+    #[test]
+    fn checks_in_conditions_hide_choices_they_fail() {
+        let world = load_world_from_markdown_content(&BRIDGE.replace("TARGET", "4")).unwrap();
+        let game_state = GameState::for_world(&world, 9);
+        let texts: Vec<&str> = get_available_choices(&world, &game_state).unwrap().iter().map(|choice| choice.text.as_str()).collect();
+        assert_eq!(texts, ["Jump"]);
+    }
+}
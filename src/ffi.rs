@@ -0,0 +1,251 @@
+// C API over `Session`, for embedding stories in games written in other languages.
+// The header is committed as `include/restoration.h`; after changing this file, copy
+// the one the build script generates into OUT_DIR over it (tests/c_api.rs compares them).
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use serde_json::{json, Value};
+use crate::engine::GameEvent;
+use crate::errors::{GameError, GameResult};
+use crate::session::Session;
+
+/// A story being played. Create with `restoration_session_new`, release with
+/// `restoration_session_free`.
+pub struct RestorationSession {
+    session: Session,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+// Run `f`, turning errors and panics into `fallback` plus a last error message
+fn guard<T>(fallback: T, f: impl FnOnce() -> GameResult<T>) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(e)) => {
+            set_last_error(e.to_string());
+            fallback
+        }
+        Err(_) => {
+            set_last_error("Internal error".to_string());
+            fallback
+        }
+    }
+}
+
+unsafe fn read_str<'a>(text: *const c_char, what: &str) -> GameResult<&'a str> {
+    if text.is_null() {
+        return Err(GameError::ValidationError(format!("{} is NULL", what)));
+    }
+    CStr::from_ptr(text)
+        .to_str()
+        .map_err(|_| GameError::ValidationError(format!("{} is not valid UTF-8", what)))
+}
+
+unsafe fn session_ref<'a>(session: *const RestorationSession) -> GameResult<&'a Session> {
+    session
+        .as_ref()
+        .map(|handle| &handle.session)
+        .ok_or_else(|| GameError::ValidationError("Session is NULL".to_string()))
+}
+
+unsafe fn session_mut<'a>(session: *mut RestorationSession) -> GameResult<&'a mut Session> {
+    session
+        .as_mut()
+        .map(|handle| &mut handle.session)
+        .ok_or_else(|| GameError::ValidationError("Session is NULL".to_string()))
+}
+
+fn into_c_string(text: String) -> GameResult<*mut c_char> {
+    CString::new(text)
+        .map(CString::into_raw)
+        .map_err(|_| GameError::ValidationError("Text contains a NUL byte".to_string()))
+}
+
+// Each event as {"type", "data"} plus the line a front end would show, if any
+fn events_json(events: &[GameEvent]) -> GameResult<*mut c_char> {
+    let events: Vec<Value> = events
+        .iter()
+        .map(|event| {
+            let mut value = serde_json::to_value(event).unwrap_or(Value::Null);
+            if let (Value::Object(fields), Some(text)) = (&mut value, event.display_text()) {
+                fields.insert("text".to_string(), Value::String(text));
+            }
+            value
+        })
+        .collect();
+    into_c_string(Value::Array(events).to_string())
+}
+
+/// The message of the last failed call on this thread, or NULL. The pointer stays
+/// valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn restoration_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| last_error.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Free a string returned by this library.
+///
+/// # Safety
+/// `text` must be NULL or a string returned by this library that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn restoration_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+/// Load a story from Markdown and start it. Returns NULL if the story is invalid.
+///
+/// # Safety
+/// `story_markdown` must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn restoration_session_new(story_markdown: *const c_char, seed: u64) -> *mut RestorationSession {
+    guard(ptr::null_mut(), || {
+        let content = read_str(story_markdown, "Story")?;
+        let world = crate::world::load_world_from_markdown_content(content)?;
+        let session = Session::new(world, seed);
+        Ok(Box::into_raw(Box::new(RestorationSession { session })))
+    })
+}
+
+/// # Safety
+/// `session` must be NULL or a session from `restoration_session_new` that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn restoration_session_free(session: *mut RestorationSession) {
+    if !session.is_null() {
+        drop(Box::from_raw(session));
+    }
+}
+
+/// What the player sees now, as JSON:
+/// `{"description": "...", "is_over": false, "waiting_for_input": false}`.
+/// Free the result with `restoration_string_free`; NULL on error.
+///
+/// # Safety
+/// `session` must be a live session from `restoration_session_new`.
+#[no_mangle]
+pub unsafe extern "C" fn restoration_session_description(session: *const RestorationSession) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let session = session_ref(session)?;
+        into_c_string(
+            json!({
+                "description": session.description()?,
+                "is_over": session.is_over(),
+                "waiting_for_input": session.is_waiting_for_input(),
+            })
+            .to_string(),
+        )
+    })
+}
+
+/// The available choices as a JSON array of `{"index", "id", "text"}`.
+/// Free the result with `restoration_string_free`; NULL on error.
+///
+/// # Safety
+/// `session` must be a live session from `restoration_session_new`.
+#[no_mangle]
+pub unsafe extern "C" fn restoration_session_choices(session: *const RestorationSession) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let choices: Vec<Value> = session_ref(session)?
+            .choices()?
+            .iter()
+            .enumerate()
+            .map(|(index, choice)| json!({ "index": index, "id": choice.id.0, "text": choice.text }))
+            .collect();
+        into_c_string(Value::Array(choices).to_string())
+    })
+}
+
+/// Make the choice at zero-based `index` and return the resulting events as a JSON
+/// array. Free the result with `restoration_string_free`; NULL on error.
+///
+/// # Safety
+/// `session` must be a live session from `restoration_session_new`.
+#[no_mangle]
+pub unsafe extern "C" fn restoration_session_choose(session: *mut RestorationSession, index: usize) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let events = session_mut(session)?.choose(index)?;
+        events_json(&events)
+    })
+}
+
+/// Like `restoration_session_choose`, selecting the choice by its ID.
+///
+/// # Safety
+/// `session` must be a live session and `choice_id` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn restoration_session_choose_id(session: *mut RestorationSession, choice_id: *const c_char) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let choice_id = read_str(choice_id, "Choice ID")?;
+        let events = session_mut(session)?.choose(choice_id)?;
+        events_json(&events)
+    })
+}
+
+/// Answer a pending text prompt and return the resulting events as a JSON array.
+///
+/// # Safety
+/// `session` must be a live session and `answer` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn restoration_session_answer(session: *mut RestorationSession, answer: *const c_char) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let answer = read_str(answer, "Answer")?;
+        let events = session_mut(session)?.answer(answer)?;
+        events_json(&events)
+    })
+}
+
+/// # Safety
+/// `session` must be NULL or a live session from `restoration_session_new`.
+#[no_mangle]
+pub unsafe extern "C" fn restoration_session_is_over(session: *const RestorationSession) -> bool {
+    guard(true, || Ok(session_ref(session)?.is_over()))
+}
+
+/// Write the game state into `buffer` as NUL-terminated JSON. Returns the length of
+/// the save without the NUL; if that is not less than `capacity`, nothing is written
+/// and the call should be repeated with a larger buffer. Returns 0 on error.
+///
+/// # Safety
+/// `session` must be a live session and `buffer` NULL or valid for `capacity` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn restoration_session_save(session: *const RestorationSession, buffer: *mut c_char, capacity: usize) -> usize {
+    guard(0, || {
+        let save = session_ref(session)?.save()?;
+        if !buffer.is_null() && save.len() < capacity {
+            ptr::copy_nonoverlapping(save.as_ptr(), buffer.cast::<u8>(), save.len());
+            *buffer.add(save.len()) = 0;
+        }
+        Ok(save.len())
+    })
+}
+
+/// Continue from `length` bytes of JSON written by `restoration_session_save`.
+/// Returns a JSON array of notes about anything changed to fit the current story,
+/// to show the player. Free it with `restoration_string_free`; NULL, leaving the
+/// session unchanged, if the save can't be used.
+///
+/// # Safety
+/// `session` must be a live session and `save` valid for `length` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn restoration_session_restore(session: *mut RestorationSession, save: *const c_char, length: usize) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        if save.is_null() {
+            return Err(GameError::ValidationError("Save is NULL".to_string()));
+        }
+        let bytes = std::slice::from_raw_parts(save.cast::<u8>(), length);
+        let save = std::str::from_utf8(bytes)
+            .map_err(|_| GameError::ValidationError("Save is not valid UTF-8".to_string()))?;
+        let notes = session_mut(session)?.restore(save)?;
+        into_c_string(Value::from(notes).to_string())
+    })
+}
@@ -1,11 +1,10 @@
-use std::collections::{HashSet, HashMap};
-use std::fs;
+use std::collections::{BTreeMap, HashSet, HashMap};
 use serde::{Serialize, Deserialize};
-use crate::world::{FlagId, Choice, Action, Condition, World, Room};
-#[cfg(not(target_arch = "wasm32"))]
-use crate::ui::print_typewriter_effect;
+use crate::world::{FlagId, Choice, Condition, World, Room, SkillCheck, DialogueNode, Variable, DescriptionMode};
+use crate::engine::GameEvent;
+use crate::observer::Observers;
+use crate::ui_trait::{GameUI, WaitForInput};
 use crate::errors::{GameError, GameResult};
-use crate::config::GameConfig;
 
 // --- Dynamic Game State ---
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,7 +12,100 @@ pub struct GameState {
     pub current_room_id: String,
     pub flags: HashSet<FlagId>,
     pub counters: HashMap<String, i32>,
+    #[serde(default)]
+    pub strings: HashMap<String, String>,
     pub has_quit: bool,
+    /// The named ending reached, if the story finished through one.
+    #[serde(default)]
+    pub ending: Option<String>,
+    /// Achievements already announced in this playthrough.
+    #[serde(default)]
+    pub achievements: HashSet<String>,
+    #[serde(default)]
+    pub pending_input: Option<PendingInput>,
+    /// The dialogue node being shown; the player stays in `current_room_id` meanwhile.
+    #[serde(default)]
+    pub dialogue: Option<String>,
+    /// Source of dice rolls; saved with the game so reloading doesn't reroll.
+    #[serde(default)]
+    pub rng: DiceRng,
+    /// Snapshots taken before each choice, oldest first, used by undo.
+    #[serde(default)]
+    pub history: Vec<GameState>,
+}
+
+/// Small deterministic random number generator (SplitMix64) for skill checks.
+/// It lives in the game state so the same seed and choices always give the same rolls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiceRng {
+    state: u64,
+}
+
+impl DiceRng {
+    pub fn seeded(seed: u64) -> Self {
+        DiceRng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Roll `dice` dice with `sides` sides each and return the sum.
+    pub fn roll(&mut self, dice: u32, sides: u32) -> i32 {
+        (0..dice)
+            .map(|_| (self.next_u64() % sides.max(1) as u64) as i32 + 1)
+            .sum()
+    }
+
+    /// Roll without advancing the generator. The same check gives the same result
+    /// until the state moves on, so a one-shot roll in a condition stays put while
+    /// the player reads the choices.
+    pub fn peek_roll(&self, salt: &str, dice: u32, sides: u32) -> i32 {
+        // Hashing the salt keeps different checks from sharing one result
+        DiceRng::seeded(self.state ^ fnv1a(salt.as_bytes())).roll(dice, sides)
+    }
+}
+
+// FNV-1a: tiny, stable across platforms and releases, unlike std's hasher
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF2_9CE4_8422_2325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+/// The outcome of a skill check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckResult {
+    pub roll: i32,
+    pub total: i32,
+    pub success: bool,
+}
+
+/// Add the check's modifiers to a dice roll and compare with the target.
+pub fn resolve_check(check: &SkillCheck, roll: i32, game_state: &GameState) -> CheckResult {
+    let modifiers: i32 = check
+        .modifiers
+        .iter()
+        .map(|counter| *game_state.counters.get(counter).unwrap_or(&0))
+        .sum();
+    let total = roll + modifiers + check.bonus;
+    CheckResult { roll, total, success: total >= check.target }
+}
+
+/// A choice paused at an `ask` action, waiting for the player's answer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingInput {
+    pub choice_id: String,
+    pub next_action: usize,
+    pub variable: String,
+    pub origin_room_id: String,
+    /// Whether the choice jumped to a dialogue node before pausing.
+    #[serde(default)]
+    pub entered_dialogue: bool,
 }
 
 impl GameState {
@@ -22,26 +114,63 @@ impl GameState {
             current_room_id: starting_room_id,
             flags: HashSet::new(),
             counters: HashMap::new(),
+            strings: HashMap::new(),
             has_quit: false,
+            ending: None,
+            achievements: HashSet::new(),
+            pending_input: None,
+            dialogue: None,
+            rng: DiceRng::default(),
+            history: Vec::new(),
         }
     }
     
-    pub fn save_to_file(&self, filename: &str) -> GameResult<()> {
-        let json = serde_json::to_string_pretty(self)
-            .map_err(|e| GameError::ValidationError(format!("Failed to serialize save data: {}", e)))?;
-        fs::write(filename, json)?;
-        Ok(())
+    /// A new game whose dice rolls all follow from `seed`.
+    pub fn with_seed(starting_room_id: String, seed: u64) -> Self {
+        GameState {
+            rng: DiceRng::seeded(seed),
+            ..GameState::new(starting_room_id)
+        }
     }
     
-    pub fn load_from_file(filename: &str) -> GameResult<Self> {
-        let content = fs::read_to_string(filename)?;
-        let game_state: GameState = serde_json::from_str(&content)
-            .map_err(|e| GameError::ValidationError(format!("Failed to deserialize save data: {}", e)))?;
-        Ok(game_state)
+    /// A new game at the story's start, with its declared flags and counters set.
+    pub fn for_world(world: &World, seed: u64) -> Self {
+        let mut game_state = GameState::with_seed(world.starting_room_id.clone(), seed);
+        for variable in &world.variables {
+            match variable {
+                Variable::Flag { name, initial } => {
+                    if *initial {
+                        game_state.flags.insert(FlagId(name.clone()));
+                    }
+                }
+                Variable::Counter { name, initial, .. } => {
+                    game_state.counters.insert(name.clone(), *initial);
+                }
+            }
+        }
+        game_state
     }
     
-    pub fn has_save_file(filename: &str) -> bool {
-        std::path::Path::new(filename).exists()
+    /// Fingerprint of everything choices can change, for comparing two runs.
+    /// Undo history is left out; collections are sorted so the hash is stable.
+    pub fn state_hash(&self) -> String {
+        let mut flags: Vec<&str> = self.flags.iter().map(|flag| flag.0.as_str()).collect();
+        flags.sort();
+        let mut achievements: Vec<&String> = self.achievements.iter().collect();
+        achievements.sort();
+        let canonical = serde_json::json!({
+            "room": self.current_room_id,
+            "flags": flags,
+            "counters": self.counters.iter().collect::<BTreeMap<_, _>>(),
+            "strings": self.strings.iter().collect::<BTreeMap<_, _>>(),
+            "has_quit": self.has_quit,
+            "ending": self.ending,
+            "achievements": achievements,
+            "dialogue": self.dialogue,
+            "rng": self.rng,
+            "waiting_for_input": self.pending_input.is_some(),
+        });
+        format!("{:016x}", fnv1a(canonical.to_string().as_bytes()))
     }
 }
 
@@ -67,6 +196,16 @@ pub fn check_single_condition(condition: &Condition, game_state: &GameState) ->
         Condition::CounterEquals(counter, value) => {
             game_state.counters.get(counter).unwrap_or(&0) == value
         }
+        Condition::StringEquals(variable, value) => {
+            string_matches(game_state, variable, value)
+        }
+        Condition::StringNotEquals(variable, value) => {
+            !string_matches(game_state, variable, value)
+        }
+        Condition::SkillCheck(check) => {
+            let roll = game_state.rng.peek_roll(&check.to_string(), check.dice, check.sides);
+            resolve_check(check, roll, game_state).success
+        }
         Condition::And(left, right) => {
             check_single_condition(left, game_state) && check_single_condition(right, game_state)
         }
@@ -76,152 +215,120 @@ pub fn check_single_condition(condition: &Condition, game_state: &GameState) ->
     }
 }
 
-/// Execute a choice using the unified UI trait approach.
-/// This handles both immediate actions (flags, counters, room changes) 
-/// and text display actions with proper user-controlled pacing.
-pub fn execute_choice<T: crate::ui_trait::GameUI + crate::ui_trait::WaitForInput>(
-    choice: &Choice, 
-    game_state: &mut GameState, 
-    ui: &mut T,
-    _config: &GameConfig
-) -> Result<(), Box<dyn std::error::Error>> {
-    // Store the original room ID to detect room changes
-    let original_room_id = game_state.current_room_id.clone();
-    
-    // First, execute all immediate actions (flags, counters, room changes, quit)
-    let mut counter_messages = Vec::new();
-    let mut room_changed = false;
-    
-    for action in &choice.actions {
-        match action {
-            Action::GoTo(room_id) => {
-                game_state.current_room_id = room_id.clone();
-                room_changed = true;
-            }
-            Action::SetFlag(flag_id) => {
-                game_state.flags.insert(flag_id.clone());
-            }
-            Action::RemoveFlag(flag_id) => {
-                game_state.flags.remove(flag_id);
-            }
-            Action::Quit => game_state.has_quit = true,
-            Action::IncrementCounter(counter) => {
-                let old_value = *game_state.counters.get(counter).unwrap_or(&0);
-                *game_state.counters.entry(counter.clone()).or_insert(0) += 1;
-                let new_value = *game_state.counters.get(counter).unwrap();
-                counter_messages.push(format!("[{}: {} → {}]", counter, old_value, new_value));
-            }
-            Action::DecrementCounter(counter) => {
-                let old_value = *game_state.counters.get(counter).unwrap_or(&0);
-                *game_state.counters.entry(counter.clone()).or_insert(0) -= 1;
-                let new_value = *game_state.counters.get(counter).unwrap();
-                counter_messages.push(format!("[{}: {} → {}]", counter, old_value, new_value));
-            }
-            Action::SetCounter(counter, value) => {
-                let old_value = *game_state.counters.get(counter).unwrap_or(&0);
-                game_state.counters.insert(counter.clone(), *value);
-                counter_messages.push(format!("[{}: {} → {}]", counter, old_value, value));
-            }
-            // Text display actions are handled separately
-            Action::DisplayText(_) | Action::DisplayTextConditional { .. } => {}
-        }
-    }
-    
-    // Collect all text display actions
-    let mut text_actions = Vec::new();
-    
-    // Add any counter messages first
-    text_actions.extend(counter_messages);
-    
-    // Then add display text actions
-    for action in &choice.actions {
-        match action {
-            Action::DisplayText(text) => {
-                text_actions.push(text.clone());
+// Player-entered text is compared case-insensitively so "joseph" matches "Joseph";
+// spaces around either side don't count
+fn string_matches(game_state: &GameState, variable: &str, value: &str) -> bool {
+    game_state
+        .strings
+        .get(variable)
+        .map(|current| current.trim().to_lowercase() == value.trim().to_lowercase())
+        .unwrap_or(false)
+}
+
+/// Replace `{variable}` placeholders with the player's stored text input.
+/// Unknown placeholders are left untouched so typos stay visible while testing.
+pub fn interpolate_text(text: &str, game_state: &GameState) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after_brace = &rest[start + 1..];
+        match after_brace.find('}') {
+            Some(end) => {
+                let name = &after_brace[..end];
+                match game_state.strings.get(name) {
+                    Some(value) => result.push_str(value),
+                    None => {
+                        result.push('{');
+                        result.push_str(name);
+                        result.push('}');
+                    }
+                }
+                rest = &after_brace[end + 1..];
             }
-            Action::DisplayTextConditional { condition, text_if_true, text_if_false } => {
-                let text = if check_single_condition(condition, game_state) {
-                    text_if_true.clone()
-                } else {
-                    text_if_false.clone()
-                };
-                text_actions.push(text);
+            None => {
+                result.push_str(&rest[start..]);
+                rest = "";
             }
-            _ => {}
         }
     }
-    
-    // Display all texts with proper pacing using the UI trait
-    if !text_actions.is_empty() {
-        ui.display_texts(&text_actions)?;
-    }
-    
-    // If room changed, add a pause before continuing to the new room
-    if room_changed && original_room_id != game_state.current_room_id {
-        ui.wait_for_continue()?;
-        ui.add_separator();
-    }
-    
-    Ok(())
+
+    result.push_str(rest);
+    result
 }
 
-// Keep the old execute_actions function for backwards compatibility with existing code
-pub fn execute_actions(choice: &Choice, game_state: &mut GameState, config: &GameConfig) {
-    for action in &choice.actions {
-        match action {
-            Action::GoTo(room_id) => game_state.current_room_id = room_id.clone(),
-            Action::SetFlag(flag_id) => {
-                game_state.flags.insert(flag_id.clone());
-            }
-            Action::RemoveFlag(flag_id) => {
-                game_state.flags.remove(flag_id);
-            }
-            Action::Quit => game_state.has_quit = true,
-            Action::DisplayText(text) => {
-                #[cfg(not(target_arch = "wasm32"))]
-                print_typewriter_effect(&format!("\n{}", text), config);
-            }
-            Action::DisplayTextConditional { condition, text_if_true, text_if_false } => {
-                let text = if check_single_condition(condition, game_state) {
-                    text_if_true
-                } else {
-                    text_if_false
-                };
-                #[cfg(not(target_arch = "wasm32"))]
-                print_typewriter_effect(&format!("\n{}", text), config);
-            }
-            Action::IncrementCounter(counter) => {
-                let old_value = *game_state.counters.get(counter).unwrap_or(&0);
-                *game_state.counters.entry(counter.clone()).or_insert(0) += 1;
-                let new_value = *game_state.counters.get(counter).unwrap();
-                #[cfg(not(target_arch = "wasm32"))]
-                print_typewriter_effect(&format!("\n[{}: {} → {}]", counter, old_value, new_value), config);
+/// Execute a choice through the engine and render the resulting events on any UI.
+/// Texts are shown with user-controlled pacing, questions are asked as they come up,
+/// and a room change pauses before showing the new room's description.
+/// Returns every event that happened so callers can update what outlives the game.
+pub fn execute_choice<T: GameUI + WaitForInput>(
+    world: &World,
+    choice: &Choice, 
+    game_state: &mut GameState, 
+    ui: &mut T,
+    observers: &mut Observers,
+) -> Result<Vec<GameEvent>, Box<dyn std::error::Error>> {
+    let mut events = observers.choose(world, game_state, choice.id.clone())?;
+    let mut all_events = Vec::new();
+    
+    loop {
+        let mut text_actions = Vec::new();
+        let mut input_prompt = None;
+        all_events.extend(events.iter().cloned());
+        
+        for event in events {
+            if let Some(text) = event.display_text() {
+                text_actions.push(text);
+                continue;
             }
-            Action::DecrementCounter(counter) => {
-                let old_value = *game_state.counters.get(counter).unwrap_or(&0);
-                *game_state.counters.entry(counter.clone()).or_insert(0) -= 1;
-                let new_value = *game_state.counters.get(counter).unwrap();
-                #[cfg(not(target_arch = "wasm32"))]
-                print_typewriter_effect(&format!("\n[{}: {} → {}]", counter, old_value, new_value), config);
+            match event {
+                GameEvent::InputRequested { prompt, .. } => input_prompt = Some(prompt),
+                GameEvent::RoomChanged { description, .. } => {
+                    if !text_actions.is_empty() {
+                        ui.display_texts(&text_actions)?;
+                        text_actions.clear();
+                    }
+                    ui.wait_for_continue()?;
+                    ui.add_separator();
+                    ui.display_text(&format!("\n{}", description))?;
+                }
+                _ => {}
             }
-            Action::SetCounter(counter, value) => {
-                let old_value = *game_state.counters.get(counter).unwrap_or(&0);
-                game_state.counters.insert(counter.clone(), *value);
-                #[cfg(not(target_arch = "wasm32"))]
-                print_typewriter_effect(&format!("\n[{}: {} → {}]", counter, old_value, value), config);
+        }
+        
+        // Display all texts with proper pacing using the UI trait
+        if !text_actions.is_empty() {
+            ui.display_texts(&text_actions)?;
+        }
+        
+        match input_prompt {
+            Some(prompt) => {
+                let answer = ui.get_text_input(&prompt)?;
+                events = observers.provide_input(world, game_state, &answer)?;
             }
+            None => return Ok(all_events),
         }
     }
 }
 
 pub fn get_available_choices<'a>(world: &'a World, game_state: &GameState) -> GameResult<Vec<&'a Choice>> {
-    let current_room = world
-        .rooms
-        .get(&game_state.current_room_id)
-        .ok_or_else(|| GameError::MissingRoom(game_state.current_room_id.clone()))?;
+    // During a conversation the player picks from the node's responses instead
+    let choice_ids = match &game_state.dialogue {
+        Some(node_id) => &world
+            .dialogues
+            .get(node_id)
+            .ok_or_else(|| GameError::ValidationError(format!("Dialogue '{}' referenced but not defined", node_id)))?
+            .choices,
+        None => &world
+            .rooms
+            .get(&game_state.current_room_id)
+            .ok_or_else(|| GameError::MissingRoom(game_state.current_room_id.clone()))?
+            .choices,
+    };
 
     let mut choices = Vec::new();
-    for choice_id in &current_room.choices {
+    for choice_id in choice_ids {
         let choice = world
             .choices
             .get(choice_id)
@@ -235,19 +342,154 @@ pub fn get_available_choices<'a>(world: &'a World, game_state: &GameState) -> Ga
     Ok(choices)
 }
 
+/// The lines of a dialogue node whose conditions hold, as `(speaker, text)` pairs.
+pub fn get_dialogue_lines(node: &DialogueNode, game_state: &GameState) -> Vec<(Option<String>, String)> {
+    node.lines
+        .iter()
+        .filter(|line| line.condition.as_ref().is_none_or(|condition| check_single_condition(condition, game_state)))
+        .map(|line| (line.speaker.clone(), interpolate_text(&line.text, game_state)))
+        .collect()
+}
+
+/// How a spoken line appears in the transcript: `Alvin: "Hello."`.
+pub fn format_dialogue_line(speaker: Option<&str>, text: &str) -> String {
+    match speaker {
+        Some(speaker) => format!("{}: {}", speaker, text),
+        None => text.to_string(),
+    }
+}
+
 pub fn get_room_description(room: &Room, game_state: &GameState) -> String {
-    // Find the first matching conditional description
+    let mut result = String::new();
+    // Whether a block, shown or not, has ended its paragraph since the last text
+    let mut new_paragraph = false;
+    // An [else] block applies when the block just before it didn't
+    let mut previous_applied = false;
+    
     for description in &room.descriptions {
-        if let Some(condition) = &description.condition {
-            if check_single_condition(condition, game_state) {
-                return description.text.clone();
+        let applies = match &description.condition {
+            Some(condition) => check_single_condition(condition, game_state),
+            None if description.is_else => !previous_applied,
+            None => true,
+        };
+        previous_applied = applies;
+        if applies {
+            let text = interpolate_text(&description.text, game_state);
+            if room.description_mode == DescriptionMode::FirstMatch {
+                return text;
+            }
+            if !text.is_empty() {
+                if !result.is_empty() {
+                    result.push_str(if new_paragraph { "\n\n" } else { " " });
+                }
+                result.push_str(&text);
+                new_paragraph = false;
             }
-        } else {
-            // Unconditional description - use as fallback
-            return description.text.clone();
         }
+        new_paragraph |= description.ends_paragraph;
     }
     
-    // No matching description found
-    String::new()
-}
\ No newline at end of file
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::FlagId;
+
+    fn answered(variable: &str, answer: &str) -> GameState {
+        let mut game_state = GameState::new("hall".to_string());
+        game_state.strings.insert(variable.to_string(), answer.to_string());
+        game_state
+    }
+
+    #[test]
+    fn placeholders_take_the_stored_answers() {
+        let game_state = answered("name", "Ada");
+        assert_eq!(interpolate_text("Hello, {name}! {name}?", &game_state), "Hello, Ada! Ada?");
+        assert_eq!(interpolate_text("Café {name}", &game_state), "Café Ada");
+    }
+
+    #[test]
+    fn unknown_and_unclosed_placeholders_stay_as_written() {
+        let game_state = answered("name", "Ada");
+        assert_eq!(interpolate_text("Hi {nmae}, {name}", &game_state), "Hi {nmae}, Ada");
+        assert_eq!(interpolate_text("Open {name", &game_state), "Open {name");
+        assert_eq!(interpolate_text("{}", &game_state), "{}");
+    }
+
+    #[test]
+    fn empty_answers_fill_placeholders_with_nothing() {
+        let game_state = answered("name", "");
+        assert_eq!(interpolate_text("Hello, {name}!", &game_state), "Hello, !");
+        assert!(check_single_condition(&Condition::StringEquals("name".to_string(), String::new()), &game_state));
+    }
+
+    #[test]
+    fn string_conditions_ignore_case_and_surrounding_spaces() {
+        let game_state = answered("name", "  joseph ");
+        assert!(check_single_condition(&Condition::StringEquals("name".to_string(), "Joseph".to_string()), &game_state));
+        assert!(check_single_condition(&Condition::StringEquals("name".to_string(), " JOSEPH ".to_string()), &game_state));
+        assert!(!check_single_condition(&Condition::StringNotEquals("name".to_string(), "Joseph".to_string()), &game_state));
+        assert!(!check_single_condition(&Condition::StringEquals("name".to_string(), "Jo seph".to_string()), &game_state));
+    }
+
+    #[test]
+    fn unanswered_variables_match_nothing() {
+        let game_state = GameState::new("hall".to_string());
+        assert!(!check_single_condition(&Condition::StringEquals("name".to_string(), String::new()), &game_state));
+        assert!(check_single_condition(&Condition::StringNotEquals("name".to_string(), "Joseph".to_string()), &game_state));
+        assert!(!check_single_condition(&Condition::HasFlag(FlagId("name".to_string())), &game_state));
+    }
+
+    const ROOMS: &str = "# Rooms\n\n@start cellar\n\n## @room cellar\nA dusty cellar.\n[door_open]\nThe door is open.\n[else]\nThe door is shut.\n\n[has_key]\nA key glints in your hand.\n\n### Wait\n- \"Time passes.\"\n";
+
+    fn cellar_with(mode: &str, flags: &[&str]) -> String {
+        let world = crate::world::load_world_from_markdown_content(&ROOMS.replace("@start cellar", &format!("@start cellar\n@descriptions {}", mode))).unwrap();
+        let mut game_state = GameState::for_world(&world, 0);
+        game_state.flags.extend(flags.iter().map(|flag| FlagId(flag.to_string())));
+        get_room_description(&world.rooms["cellar"], &game_state)
+    }
+
+    #[test]
+    fn additive_descriptions_show_every_block_that_applies() {
+        assert_eq!(cellar_with("additive", &[]), "A dusty cellar. The door is shut.");
+        assert_eq!(cellar_with("additive", &["door_open"]), "A dusty cellar. The door is open.");
+        assert_eq!(
+            cellar_with("additive", &["door_open", "has_key"]),
+            "A dusty cellar. The door is open.\n\nA key glints in your hand."
+        );
+    }
+
+    #[test]
+    fn else_blocks_follow_the_block_before_them() {
+        assert_eq!(cellar_with("additive", &["has_key"]), "A dusty cellar. The door is shut.\n\nA key glints in your hand.");
+        assert_eq!(cellar_with("first_match", &["door_open"]), "A dusty cellar.");
+    }
+
+    #[test]
+    fn checks_add_counters_and_bonus_to_the_roll() {
+        let check = SkillCheck { dice: 1, sides: 20, modifiers: vec!["sleight_of_hand".to_string(), "luck".to_string()], bonus: -1, target: 12 };
+        let mut game_state = GameState::new("hall".to_string());
+        game_state.counters.insert("sleight_of_hand".to_string(), 3);
+        game_state.counters.insert("luck".to_string(), 2);
+
+        assert_eq!(resolve_check(&check, 8, &game_state), CheckResult { roll: 8, total: 12, success: true });
+        assert!(!resolve_check(&check, 7, &game_state).success);
+        assert_eq!(check.label(), "Sleight of hand");
+        assert_eq!(SkillCheck { modifiers: Vec::new(), dice: 2, ..check }.label(), "2d20");
+    }
+
+    #[test]
+    fn seeded_rolls_repeat_and_peeking_leaves_the_generator_alone() {
+        let mut first = DiceRng::seeded(42);
+        let mut second = DiceRng::seeded(42);
+        let rolls: Vec<i32> = (0..20).map(|_| first.roll(3, 6)).collect();
+        assert_eq!(rolls, (0..20).map(|_| second.roll(3, 6)).collect::<Vec<_>>());
+        assert!(rolls.iter().all(|roll| (3..=18).contains(roll)));
+
+        let rng = DiceRng::seeded(7);
+        assert_eq!(rng.peek_roll("d20 >= 10", 1, 20), rng.peek_roll("d20 >= 10", 1, 20));
+        assert_eq!(rng, DiceRng::seeded(7));
+    }
+}
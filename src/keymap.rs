@@ -0,0 +1,396 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use crate::world::World;
+
+/// A key as the front ends see it, independent of crossterm and ratzilla.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// Letters are kept in lower case, so bindings ignore Shift.
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Esc,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Backspace,
+    Tab,
+}
+
+impl Key {
+    pub fn from_char(c: char) -> Key {
+        Key::Char(c.to_ascii_lowercase())
+    }
+
+    /// The key as shown in hints and the help overlay.
+    pub fn label(&self) -> String {
+        match self {
+            Key::Up => "↑".to_string(),
+            Key::Down => "↓".to_string(),
+            Key::Left => "←".to_string(),
+            Key::Right => "→".to_string(),
+            other => other.to_string(),
+        }
+    }
+}
+
+/// Key names as written in the config file: `Up`, `PageDown`, `Space`, `Esc`, or a
+/// single character such as `k` or `?`.
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(Key::from_char(c));
+        }
+        let key = match name.to_ascii_lowercase().as_str() {
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "left" => Key::Left,
+            "right" => Key::Right,
+            "enter" | "return" => Key::Enter,
+            "esc" | "escape" => Key::Esc,
+            "pageup" => Key::PageUp,
+            "pagedown" => Key::PageDown,
+            "home" => Key::Home,
+            "end" => Key::End,
+            "backspace" => Key::Backspace,
+            "tab" => Key::Tab,
+            "space" => Key::Char(' '),
+            _ => return Err(format!("unknown key '{}'", name)),
+        };
+        Ok(key)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Char(' ') => write!(f, "Space"),
+            Key::Char(c) => write!(f, "{}", c),
+            Key::Up => write!(f, "Up"),
+            Key::Down => write!(f, "Down"),
+            Key::Left => write!(f, "Left"),
+            Key::Right => write!(f, "Right"),
+            Key::Enter => write!(f, "Enter"),
+            Key::Esc => write!(f, "Esc"),
+            Key::PageUp => write!(f, "PageUp"),
+            Key::PageDown => write!(f, "PageDown"),
+            Key::Home => write!(f, "Home"),
+            Key::End => write!(f, "End"),
+            Key::Backspace => write!(f, "Backspace"),
+            Key::Tab => write!(f, "Tab"),
+        }
+    }
+}
+
+/// Something the player can bind keys to in the full-screen and web UIs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    SelectPrev,
+    SelectNext,
+    Confirm,
+    ScrollUp,
+    ScrollDown,
+    PageUp,
+    PageDown,
+    ScrollTop,
+    ScrollBottom,
+    /// Finish the text being typed out, or continue after a pause.
+    SkipText,
+    Undo,
+    Save,
+    Load,
+    ExportSave,
+    ImportSave,
+    Achievements,
+    Endings,
+    /// Show the help overlay with every binding.
+    Menu,
+    Quit,
+}
+
+impl KeyAction {
+    /// Actions `world` has nothing for: undo under `@no_undo`, and the endings or
+    /// achievements of a story that declares none.
+    pub fn unavailable_in(world: &World) -> Vec<KeyAction> {
+        let mut unavailable = Vec::new();
+        if world.no_undo {
+            unavailable.push(KeyAction::Undo);
+        }
+        if world.endings.is_empty() {
+            unavailable.push(KeyAction::Endings);
+        }
+        if world.achievements.is_empty() {
+            unavailable.push(KeyAction::Achievements);
+        }
+        unavailable
+    }
+
+    pub const ALL: [KeyAction; 19] = [
+        KeyAction::SelectPrev,
+        KeyAction::SelectNext,
+        KeyAction::Confirm,
+        KeyAction::ScrollUp,
+        KeyAction::ScrollDown,
+        KeyAction::PageUp,
+        KeyAction::PageDown,
+        KeyAction::ScrollTop,
+        KeyAction::ScrollBottom,
+        KeyAction::SkipText,
+        KeyAction::Undo,
+        KeyAction::Save,
+        KeyAction::Load,
+        KeyAction::ExportSave,
+        KeyAction::ImportSave,
+        KeyAction::Achievements,
+        KeyAction::Endings,
+        KeyAction::Menu,
+        KeyAction::Quit,
+    ];
+
+    /// The action's name in the config file's `keymap`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyAction::SelectPrev => "select_prev",
+            KeyAction::SelectNext => "select_next",
+            KeyAction::Confirm => "confirm",
+            KeyAction::ScrollUp => "scroll_up",
+            KeyAction::ScrollDown => "scroll_down",
+            KeyAction::PageUp => "page_up",
+            KeyAction::PageDown => "page_down",
+            KeyAction::ScrollTop => "scroll_top",
+            KeyAction::ScrollBottom => "scroll_bottom",
+            KeyAction::SkipText => "skip_text",
+            KeyAction::Undo => "undo",
+            KeyAction::Save => "save",
+            KeyAction::Load => "load",
+            KeyAction::ExportSave => "export_save",
+            KeyAction::ImportSave => "import_save",
+            KeyAction::Achievements => "achievements",
+            KeyAction::Endings => "endings",
+            KeyAction::Menu => "menu",
+            KeyAction::Quit => "quit",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            KeyAction::SelectPrev => "Previous choice",
+            KeyAction::SelectNext => "Next choice",
+            KeyAction::Confirm => "Make the selected choice",
+            KeyAction::ScrollUp => "Scroll up",
+            KeyAction::ScrollDown => "Scroll down",
+            KeyAction::PageUp => "Scroll up a page",
+            KeyAction::PageDown => "Scroll down a page",
+            KeyAction::ScrollTop => "Jump to the start",
+            KeyAction::ScrollBottom => "Jump to the latest text",
+            KeyAction::SkipText => "Finish the text / continue",
+            KeyAction::Undo => "Undo the last choice",
+            KeyAction::Save => "Save",
+            KeyAction::Load => "Load a save",
+            KeyAction::ExportSave => "Export a save code",
+            KeyAction::ImportSave => "Import a save code",
+            KeyAction::Achievements => "Achievements",
+            KeyAction::Endings => "Endings found",
+            KeyAction::Menu => "Show or hide this help",
+            KeyAction::Quit => "Quit",
+        }
+    }
+
+    fn default_keys(&self) -> &'static [Key] {
+        match self {
+            KeyAction::SelectPrev => &[Key::Up],
+            KeyAction::SelectNext => &[Key::Down],
+            KeyAction::Confirm => &[Key::Enter],
+            KeyAction::ScrollUp => &[Key::Char('k')],
+            KeyAction::ScrollDown => &[Key::Char('j')],
+            KeyAction::PageUp => &[Key::PageUp],
+            KeyAction::PageDown => &[Key::PageDown],
+            KeyAction::ScrollTop => &[Key::Home],
+            KeyAction::ScrollBottom => &[Key::End, Key::Char(' ')],
+            KeyAction::SkipText => &[Key::Enter, Key::Char(' ')],
+            KeyAction::Undo => &[Key::Char('u')],
+            KeyAction::Save => &[Key::Char('s')],
+            KeyAction::Load => &[Key::Char('l')],
+            KeyAction::ExportSave => &[Key::Char('x')],
+            KeyAction::ImportSave => &[Key::Char('i')],
+            KeyAction::Achievements => &[Key::Char('a')],
+            KeyAction::Endings => &[Key::Char('e')],
+            KeyAction::Menu => &[Key::Char('?')],
+            KeyAction::Quit => &[Key::Esc],
+        }
+    }
+}
+
+/// The keys bound to each action: the defaults, with the config's `keymap`
+/// replacing the keys of the actions it names.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    bindings: Vec<(KeyAction, Vec<Key>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            bindings: KeyAction::ALL.iter().map(|action| (*action, action.default_keys().to_vec())).collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// Apply `overrides` (action name to key names) to the default bindings.
+    pub fn new(overrides: &BTreeMap<String, Vec<String>>) -> Result<Keymap, String> {
+        let mut keymap = Keymap::default();
+        for (name, key_names) in overrides {
+            let action = KeyAction::ALL.iter().find(|action| action.name() == name).ok_or_else(|| {
+                let known: Vec<&str> = KeyAction::ALL.iter().map(KeyAction::name).collect();
+                format!("unknown action '{}' (expected one of: {})", name, known.join(", "))
+            })?;
+            let keys = key_names.iter().map(|key| key.parse()).collect::<Result<Vec<Key>, String>>()?;
+            if let Some(key) = keys.iter().find(|key| matches!(key, Key::Char('1'..='9'))) {
+                return Err(format!("key '{}' is kept for making the choice with that number", key));
+            }
+            if let Some((_, bound)) = keymap.bindings.iter_mut().find(|(bound, _)| bound == action) {
+                *bound = keys;
+            }
+        }
+
+        // Skipping text only happens while text is being shown, so its keys may do
+        // something else the rest of the time
+        let mut seen: Vec<(Key, KeyAction)> = Vec::new();
+        for (action, keys) in keymap.bindings.iter().filter(|(action, _)| *action != KeyAction::SkipText) {
+            for key in keys {
+                if let Some((_, other)) = seen.iter().find(|(seen_key, _)| seen_key == key) {
+                    return Err(format!("key '{}' is bound to both {} and {}", key, other.name(), action.name()));
+                }
+                seen.push((*key, *action));
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// The action bound to `key`, leaving out `skip_text`.
+    pub fn action(&self, key: Key) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .find(|(action, keys)| *action != KeyAction::SkipText && keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    /// Whether `key` is bound to `action`.
+    pub fn is(&self, key: Key, action: KeyAction) -> bool {
+        self.keys(action).contains(&key)
+    }
+
+    pub fn keys(&self, action: KeyAction) -> &[Key] {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    /// The keys of `action` for hints, e.g. `u` or `End/Space`.
+    pub fn label(&self, action: KeyAction) -> String {
+        let labels: Vec<String> = self.keys(action).iter().map(Key::label).collect();
+        if labels.is_empty() { "(unbound)".to_string() } else { labels.join("/") }
+    }
+
+    /// One line per binding for the help overlay, leaving out `hidden` actions the
+    /// front end doesn't offer.
+    pub fn help_lines(&self, hidden: &[KeyAction]) -> Vec<String> {
+        let mut rows: Vec<(String, &str)> = self
+            .bindings
+            .iter()
+            .filter(|(action, _)| !hidden.contains(action))
+            .map(|(action, _)| (self.label(*action), action.description()))
+            .collect();
+        rows.push(("1-9".to_string(), "Make the choice with that number"));
+        let width = rows.iter().map(|(keys, _)| keys.chars().count()).max().unwrap_or(0);
+        rows.iter()
+            .map(|(keys, description)| format!("{:width$}  {}", keys, description, width = width))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(entries: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        entries
+            .iter()
+            .map(|(action, keys)| (action.to_string(), keys.iter().map(|key| key.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn default_bindings_have_no_conflicts() {
+        let keymap = Keymap::new(&BTreeMap::new()).unwrap();
+        assert_eq!(keymap, Keymap::default());
+        assert_eq!(keymap.action(Key::Up), Some(KeyAction::SelectPrev));
+        // Space scrolls to the bottom outside of typing, where it skips text instead
+        assert_eq!(keymap.action(Key::Char(' ')), Some(KeyAction::ScrollBottom));
+        assert!(keymap.is(Key::Char(' '), KeyAction::SkipText));
+    }
+
+    #[test]
+    fn overrides_replace_an_actions_keys() {
+        let keymap = Keymap::new(&overrides(&[("undo", &["Z", "Backspace"]), ("select_next", &["down", "j"]), ("scroll_down", &["n"])])).unwrap();
+        assert_eq!(keymap.keys(KeyAction::Undo), [Key::Char('z'), Key::Backspace]);
+        assert_eq!(keymap.action(Key::Char('u')), None);
+        assert_eq!(keymap.action(Key::Char('j')), Some(KeyAction::SelectNext));
+        assert_eq!(keymap.label(KeyAction::SelectNext), "↓/j");
+        assert_eq!(Keymap::new(&overrides(&[("menu", &[])])).unwrap().label(KeyAction::Menu), "(unbound)");
+    }
+
+    #[test]
+    fn keys_bound_to_two_actions_are_rejected() {
+        let error = Keymap::new(&overrides(&[("undo", &["s"])])).unwrap_err();
+        assert_eq!(error, "key 's' is bound to both undo and save");
+        let error = Keymap::new(&overrides(&[("select_next", &["j"])])).unwrap_err();
+        assert_eq!(error, "key 'j' is bound to both select_next and scroll_down");
+
+        // Skipping text may share keys with anything
+        assert!(Keymap::new(&overrides(&[("skip_text", &["u", "Esc"])])).is_ok());
+    }
+
+    #[test]
+    fn digits_one_to_nine_stay_for_choices() {
+        for digit in ["1", "5", "9"] {
+            let error = Keymap::new(&overrides(&[("save", &[digit])])).unwrap_err();
+            assert_eq!(error, format!("key '{}' is kept for making the choice with that number", digit));
+        }
+        assert!(Keymap::new(&overrides(&[("save", &["0"])])).is_ok());
+    }
+
+    #[test]
+    fn unknown_actions_and_keys_are_rejected() {
+        assert!(Keymap::new(&overrides(&[("jump", &["j"])])).unwrap_err().starts_with("unknown action 'jump' (expected one of: select_prev, "));
+        assert_eq!(Keymap::new(&overrides(&[("undo", &["Ctrl-z"])])).unwrap_err(), "unknown key 'Ctrl-z'");
+        assert_eq!("PAGEDOWN".parse::<Key>(), Ok(Key::PageDown));
+        assert_eq!("Space".parse::<Key>().map(|key| key.to_string()), Ok("Space".to_string()));
+    }
+
+    #[test]
+    fn help_lists_bindings_except_hidden_ones() {
+        let world = crate::world::load_world_from_markdown_content(
+            "# Help\n\n@start hall\n@no_undo\n@ending out \"Out\"\n\n## @room hall\nA hall.\n\n### Leave\n- end: out\n",
+        )
+        .unwrap();
+        let hidden = KeyAction::unavailable_in(&world);
+        assert_eq!(hidden, [KeyAction::Undo, KeyAction::Achievements]);
+
+        let lines = Keymap::default().help_lines(&hidden);
+        assert_eq!(lines.len(), KeyAction::ALL.len() - 2 + 1);
+        assert!(lines.iter().any(|line| line.starts_with("End/Space  ") && line.ends_with("Jump to the latest text")));
+        assert!(!lines.iter().any(|line| line.ends_with("Undo the last choice")));
+        assert!(lines.last().unwrap().starts_with("1-9"));
+    }
+}
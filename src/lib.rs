@@ -1,7 +1,19 @@
 pub mod world;
 pub mod game;
+pub mod engine;
+pub mod observer;
+pub mod replay;
+pub mod session;
+pub mod saves;
+pub mod paths;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ffi;
 pub mod ui;
 pub mod ui_trait;
 pub mod errors;
 pub mod config;
-pub mod markdown_parser;
\ No newline at end of file
+pub mod theme;
+pub mod keymap;
+pub mod profile;
+pub mod markdown_parser;
+pub mod pacing;
\ No newline at end of file
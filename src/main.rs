@@ -1,10 +1,4 @@
-mod world;
-mod game;
-mod ui;
-mod ui_trait;
-mod errors;
-mod config;
-mod markdown_parser;
+use restoration_project::{world, game, engine, ui_trait, config, profile, pacing, saves, theme, keymap, observer};
 
 #[cfg(not(target_arch = "wasm32"))]
 mod terminal_ui;
@@ -12,22 +6,31 @@ mod terminal_ui;
 #[cfg(target_arch = "wasm32")]
 mod web_ui;
 
-use game::{GameState, get_room_description};
+use game::GameState;
 use config::GameConfig;
 
 #[cfg(not(target_arch = "wasm32"))]
 use {
     clap::{Parser, Subcommand},
-    ui::{print_typewriter_effect, get_user_input, display_choices, parse_user_choice, print_game_text},
+    restoration_project::ui::{print_typewriter_effect, get_user_input, parse_user_choice, print_game_text, PlainUi},
+    ui_trait::{GameUI, WaitForInput},
     terminal_ui::TerminalUi,
     world::load_world_from_markdown,
-    game::{get_available_choices, execute_actions},
-    config::UiMode,
+    game::get_available_choices,
+    engine::{Engine, GameEvent},
+    observer::{JsonLinesLogger, Observers},
+    restoration_project::replay::{Recorder, RecordedStep, load_recording, replay},
+    restoration_project::paths,
+    saves::{SaveStore, SlotInfo, PlayClock, AUTO_SAVE, QUICK_SAVE, format_age, format_playtime},
+    profile::PlayerProfile,
+    config::{ConfigLayers, ConfigOrigin, UiMode},
 };
 
 #[cfg(target_arch = "wasm32")]
 use {
     web_ui::run_web_game,
+    config::ConfigLayers,
+    observer::Observers,
     world::load_world_from_markdown_content,
     wasm_bindgen::prelude::*,
 };
@@ -38,6 +41,10 @@ use {
 #[command(about = "A text adventure game engine with Markdown story format")]
 #[command(version)]
 struct Cli {
+    /// Config file to use instead of the one in the platform config directory
+    #[arg(long, global = true, value_name = "FILE", env = "RESTORATION_CONFIG")]
+    config: Option<std::path::PathBuf>,
+    
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -58,6 +65,41 @@ enum Commands {
         /// Disable text commands (numbers only)
         #[arg(long, env = "RESTORATION_NO_TEXT")]
         no_text_commands: bool,
+        
+        /// Seed for dice rolls, to replay the same luck
+        #[arg(long, env = "RESTORATION_SEED")]
+        seed: Option<u64>,
+        
+        /// Log choices and state changes to this file as JSON lines
+        #[arg(long, value_name = "FILE", env = "RESTORATION_LOG")]
+        log: Option<String>,
+        
+        /// Record every input and resulting state to this file for `replay`
+        #[arg(long, value_name = "FILE")]
+        record: Option<String>,
+        
+        /// Autosave on every room change for this session
+        #[arg(long, conflicts_with = "no_auto_save")]
+        auto_save: bool,
+        
+        /// Don't autosave this session, whatever the config says
+        #[arg(long)]
+        no_auto_save: bool,
+        
+        /// Override a config key for this session (repeatable)
+        #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_setting)]
+        settings: Vec<(String, String)>,
+    },
+    
+    /// Re-run a recorded playthrough without a UI and report where it diverges
+    Replay {
+        /// Recording made with `play --record`
+        #[arg(value_name = "RECORDING")]
+        recording: String,
+        
+        /// Story file to replay against
+        #[arg(value_name = "STORY")]
+        story: String,
     },
     
     /// Validate a story file
@@ -67,6 +109,12 @@ enum Commands {
         story: String,
     },
     
+    /// List, inspect or delete a story's saves
+    Saves {
+        #[command(subcommand)]
+        action: SavesAction,
+    },
+    
     /// Manage configuration
     Config {
         #[command(subcommand)]
@@ -74,41 +122,119 @@ enum Commands {
     },
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Subcommand)]
+enum SavesAction {
+    /// List saves, most recent first
+    List {
+        #[arg(value_name = "STORY")]
+        story: String,
+    },
+    /// Show what a save contains
+    Show {
+        #[arg(value_name = "STORY")]
+        story: String,
+        #[arg(value_name = "SLOT")]
+        slot: String,
+    },
+    /// Delete a save
+    Delete {
+        #[arg(value_name = "STORY")]
+        story: String,
+        #[arg(value_name = "SLOT")]
+        slot: String,
+    },
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 #[derive(Subcommand)]
 enum ConfigAction {
     /// Show current configuration
-    Show,
+    Show {
+        /// Show where each value comes from
+        #[arg(long)]
+        origin: bool,
+        /// Include the overrides in this story's front matter
+        #[arg(long, value_name = "STORY")]
+        story: Option<String>,
+    },
+    /// Print the value of a key
+    Get {
+        key: String,
+        /// Include the overrides in this story's front matter
+        #[arg(long, value_name = "STORY")]
+        story: Option<String>,
+    },
+    /// Set a key in the config file
+    Set { key: String, value: String },
+    /// Remove a key from the config file so its default applies
+    Unset { key: String },
     /// Reset configuration to defaults
     Reset,
-    /// Set typewriter speed (0 = instant, higher = slower)
+    /// Check the config file and show how to repair it
+    Doctor {
+        /// Apply the repairs, keeping the old file as config.json.bak
+        #[arg(long)]
+        fix: bool,
+    },
+    // Deprecated shortcuts for `config set`, kept so old scripts still work
+    #[command(hide = true)]
     SetSpeed { speed: u64 },
-    /// Enable typewriter effect
+    #[command(hide = true)]
     EnableTypewriter,
-    /// Disable typewriter effect  
+    #[command(hide = true)]
     DisableTypewriter,
-    /// Enable text commands
+    #[command(hide = true)]
     EnableTextCommands,
-    /// Disable text commands
+    #[command(hide = true)]
     DisableTextCommands,
-    /// Enable centered UI mode
+    #[command(hide = true)]
     EnableUiMode,
-    /// Disable centered UI mode (plain mode)
+    #[command(hide = true)]
     DisableUiMode,
+    #[command(hide = true)]
+    EnableAutoSave {
+        #[arg(long)]
+        every_choice: bool,
+    },
+    #[command(hide = true)]
+    DisableAutoSave,
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 fn main() {
     let cli = Cli::parse();
+    if let Some(config_file) = cli.config {
+        paths::set_config_path(config_file);
+    }
     
     match cli.command {
-        Some(Commands::Play { story, fast, no_text_commands }) => {
+        Some(Commands::Play { story, fast, no_text_commands, seed, log, record, auto_save, no_auto_save, settings }) => {
             let story_file = story.as_deref().unwrap_or("the_cellar.md");
-            play_story(story_file, fast, no_text_commands);
+            let mut cli_settings = Vec::new();
+            let flags = [
+                (fast, "--fast", "enable_typewriter", "false"),
+                (no_text_commands, "--no-text-commands", "allow_text_commands", "false"),
+                (auto_save, "--auto-save", "auto_save", "true"),
+                (no_auto_save, "--no-auto-save", "auto_save", "false"),
+            ];
+            for (_, flag, key, value) in flags.into_iter().filter(|(set, ..)| *set) {
+                cli_settings.push(CliSetting { flag: flag.to_string(), key: key.to_string(), value: value.to_string() });
+            }
+            for (key, value) in settings {
+                cli_settings.push(CliSetting { flag: format!("--set {}", key), key, value });
+            }
+            play_story(story_file, &cli_settings, seed, log.as_deref(), record.as_deref());
+        }
+        Some(Commands::Replay { recording, story }) => {
+            replay_recording(&recording, &story);
         }
         Some(Commands::Validate { story }) => {
             validate_story(&story);
         }
+        Some(Commands::Saves { action }) => {
+            handle_saves(action);
+        }
         Some(Commands::Config { action }) => {
             handle_config(action);
         }
@@ -116,7 +242,7 @@ fn main() {
             // Default: play with first argument as story file
             let story_arg = std::env::args().nth(1);
             let story_file = story_arg.as_deref().unwrap_or("the_cellar.md");
-            play_story(story_file, false, false);
+            play_story(story_file, &[], None, None, None);
         }
     }
 }
@@ -134,13 +260,11 @@ pub fn wasm_main() {
     
     // For web, we'll start with the default story
     let story_content = include_str!("../first-vision.md");
-    play_story_web(story_content);
+    play_story_web("first-vision", story_content);
 }
 
 #[cfg(target_arch = "wasm32")]
-fn play_story_web(story_content: &str) {
-    let config = GameConfig::default();
-    
+fn play_story_web(story_name: &str, story_content: &str) {
     let world = match load_world_from_markdown_content(story_content) {
         Ok(world) => world,
         Err(e) => {
@@ -149,102 +273,146 @@ fn play_story_web(story_content: &str) {
         }
     };
 
-    let game_state = GameState::new(world.starting_room_id.clone());
+    // The browser has no config file or environment, only the story's front matter;
+    // saves live in localStorage and the autosave is on unless the story says otherwise
+    let mut layers = ConfigLayers::new(&GameConfig { auto_save: true, ..GameConfig::default() });
+    layers.apply_story(&world);
+    for warning in &layers.warnings {
+        web_sys::console::warn_1(&warning.into());
+    }
+    let config = layers.config().unwrap_or_default();
+
+    let game_state = GameState::for_world(&world, js_sys::Date::now() as u64);
     
     // Start the web game using the new Ratzilla pattern
-    run_web_game(world, game_state, config);
+    run_web_game(story_name, world, game_state, config, Observers::new());
 }
 
 
 #[cfg(not(target_arch = "wasm32"))]
-fn play_story(story_file: &str, fast: bool, no_text_commands: bool) {
-    let mut config = match GameConfig::load_or_create() {
-        Ok(config) => config,
+fn play_story(story_file: &str, cli_settings: &[CliSetting], seed: Option<u64>, log: Option<&str>, record: Option<&str>) {
+    let world = match load_world_from_markdown(story_file) {
+        Ok(world) => world,
+        Err(e) => {
+            eprintln!("Error loading story: {}", e);
+            return;
+        }
+    };
+
+    // Defaults, config file, the story's front matter and the environment, then flags
+    let mut layers = match ConfigLayers::load(Some(&world)) {
+        Ok(layers) => layers,
         Err(e) => {
             eprintln!("Error loading config: {}", e);
             return;
         }
     };
-    
-    // Override config with command line options
-    if fast {
-        config.enable_typewriter = false;
+    for setting in cli_settings {
+        if let Err(e) = layers.set(&setting.key, &setting.value, ConfigOrigin::Cli(setting.flag.clone())) {
+            eprintln!("Error in {}: {}", setting.flag, e);
+            return;
+        }
     }
-    if no_text_commands {
-        config.allow_text_commands = false;
+    for warning in &layers.warnings {
+        eprintln!("⚠️  {}", warning);
     }
+    let config = match layers.config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error loading config: {}", e);
+            return;
+        }
+    };
+    migrate_story_files(story_file);
 
-    let world = match load_world_from_markdown(story_file) {
-        Ok(world) => world,
+    let mut observers = Observers::new();
+    if let Some(log_file) = log {
+        match JsonLinesLogger::create(log_file) {
+            Ok(logger) => observers.add(Box::new(logger)),
+            Err(e) => eprintln!("Failed to open log file: {}", e),
+        }
+    }
+
+    let recorder = match record.map(Recorder::create).transpose() {
+        Ok(recorder) => recorder,
         Err(e) => {
-            eprintln!("Error loading story: {}", e);
+            eprintln!("Failed to create recording: {}", e);
             return;
         }
     };
 
+    let seed = seed.unwrap_or_else(clock_seed);
+
     match config.ui_mode {
-        UiMode::Centered => play_story_terminal_ui(story_file, world, config),
-        UiMode::Plain => play_story_plain(story_file, world, config),
+        UiMode::Centered => play_story_terminal_ui(story_file, world, config, seed, recorder, observers),
+        UiMode::Plain => play_story_plain(story_file, world, config, seed, recorder, observers),
     }
 }
 
+// A config key set by a command-line flag
 #[cfg(not(target_arch = "wasm32"))]
-fn play_story_plain(story_file: &str, world: world::World, config: GameConfig) {
-    let save_filename = format!("{}.save", story_file.replace(".md", ""));
+struct CliSetting {
+    flag: String,
+    key: String,
+    value: String,
+}
+
+// "key=value" for --set
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_setting(setting: &str) -> Result<(String, String), String> {
+    setting
+        .split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .ok_or_else(|| format!("'{}' should look like key=value", setting))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn play_story_plain(story_file: &str, world: world::World, config: GameConfig, seed: u64, mut recorder: Option<Recorder>, mut observers: Observers) {
+    let saves = SaveStore::for_story(story_file);
+    let profile_filename = PlayerProfile::path_for_story(story_file);
+    let mut plain_ui = PlainUi::new(config.clone());
+    let mut profile = PlayerProfile::load_or_default(&profile_filename).unwrap_or_else(|e| {
+        eprintln!("Failed to load profile: {}", e);
+        PlayerProfile::default()
+    });
     
-    let mut game_state = if GameState::has_save_file(&save_filename) {
-        print_game_text("Found save file. Load it? (y/n)", &config);
+    let mut clock = PlayClock::start();
+    let mut game_state = GameState::for_world(&world, seed);
+    let offers = [(AUTO_SAVE, "Continue from autosave?"), (QUICK_SAVE, "Found save file. Load it?")];
+    for (slot, question) in offers.into_iter().filter(|(slot, _)| saves.exists(slot)) {
+        print_game_text(&format!("{} (y/n)", question), &config);
         let mut input = String::new();
-        if std::io::stdin().read_line(&mut input).is_ok() && input.trim().eq_ignore_ascii_case("y") {
-            match GameState::load_from_file(&save_filename) {
-                Ok(state) => {
-                    print_game_text("Game loaded successfully!", &config);
-                    state
-                }
-                Err(e) => {
-                    eprintln!("Failed to load save file: {}", e);
-                    print_game_text("Starting new game...", &config);
-                    GameState::new(world.starting_room_id.clone())
+        if std::io::stdin().read_line(&mut input).is_err() || !input.trim().eq_ignore_ascii_case("y") {
+            continue;
+        }
+        match saves.restore(slot, &world) {
+            Ok((save, notes)) => {
+                print_game_text("Game loaded successfully!", &config);
+                for note in notes {
+                    print_game_text(&note, &config);
                 }
+                clock = PlayClock::resume(save.playtime_secs());
+                observers.load(&mut game_state, save.state);
+                break;
             }
-        } else {
-            GameState::new(world.starting_room_id.clone())
+            Err(e) => eprintln!("Failed to load save file: {}", e),
         }
-    } else {
-        GameState::new(world.starting_room_id.clone())
-    };
-    
-    let mut last_room_id = game_state.current_room_id.clone(); // Track the last room
+    }
+    record_step(&mut recorder, RecordedStep::start(&world, &game_state, seed), &mut plain_ui);
 
     //print_game_line("--- Welcome to the Restoration Project ---", &config);
     //print_game_text("Special commands: 'save' to save game, 'load' to load game, 'quit' to exit", &config);
 
     // Initial room description
-    let current_room = match world.rooms.get(&game_state.current_room_id) {
-        Some(room) => room,
-        None => {
-            eprintln!("Error: Starting room '{}' not found!", game_state.current_room_id);
+    match Engine::describe(&world, &game_state) {
+        Ok(room_desc) => print_typewriter_effect(&format!("\n{}", room_desc), &config),
+        Err(e) => {
+            eprintln!("Error: {}", e);
             return;
         }
-    };
-    let room_desc = get_room_description(current_room, &game_state);
-    print_typewriter_effect(&format!("\n{}", room_desc), &config);
+    }
 
     while !game_state.has_quit {
-        // Only print room description if we have changed rooms
-        if last_room_id != game_state.current_room_id {
-            let current_room = match world.rooms.get(&game_state.current_room_id) {
-                Some(room) => room,
-                None => {
-                    eprintln!("Error: Room '{}' not found!", game_state.current_room_id);
-                    return;
-                }
-            };
-            let room_desc = get_room_description(current_room, &game_state);
-            print_typewriter_effect(&format!("\n{}", room_desc), &config);
-            last_room_id = game_state.current_room_id.clone();
-        }
-
         let available_choices = match get_available_choices(&world, &game_state) {
             Ok(choices) => choices,
             Err(e) => {
@@ -255,11 +423,11 @@ fn play_story_plain(story_file: &str, world: world::World, config: GameConfig) {
 
         if available_choices.is_empty() {
             print_game_text("There is nothing you can do here.", &config);
-            game_state.has_quit = true;
+            observers.quit(&mut game_state);
             continue;
         }
 
-        display_choices(&available_choices, &config);
+        plain_ui.display_choices(&available_choices);
 
         let input = match get_user_input(&config) {
             Ok(input) => input,
@@ -270,27 +438,105 @@ fn play_story_plain(story_file: &str, world: world::World, config: GameConfig) {
         };
 
         // Handle special commands first
-        match input.trim().to_lowercase().as_str() {
-            "save" => {
-                match game_state.save_to_file(&save_filename) {
-                    Ok(()) => print_game_text("Game saved successfully!", &config),
+        if let Some(steps) = parse_rewind_command(&input) {
+            match observers.rewind(&world, &mut game_state, steps) {
+                Ok(undone) => {
+                    record_step(&mut recorder, RecordedStep::rewind(&input, undone, &game_state), &mut plain_ui);
+                    print_game_text(&format!("Rewound {} choice(s).", undone), &config);
+                    if let Ok(room_desc) = Engine::describe(&world, &game_state) {
+                        print_typewriter_effect(&format!("\n{}", room_desc), &config);
+                    }
+                }
+                Err(e) => eprintln!("Failed to undo: {}", e),
+            }
+            continue;
+        }
+        // "save the cat" is a choice before it is a save slot
+        let slot_command = parse_slot_command(&input)
+            .filter(|_| parse_user_choice(&input, &available_choices, &config).is_none());
+        match slot_command {
+            Some(SlotCommand::Save(slot)) => {
+                match saves.save(slot, &world, &game_state, clock.elapsed()) {
+                    Ok(()) => print_game_text(&format!("Game saved to '{}'.", slot), &config),
                     Err(e) => eprintln!("Failed to save game: {}", e),
                 }
                 continue;
             }
-            "load" => {
-                match GameState::load_from_file(&save_filename) {
-                    Ok(loaded_state) => {
-                        game_state = loaded_state;
-                        last_room_id = "".to_string(); // Force room description to show
+            Some(SlotCommand::Load(slot)) => {
+                match saves.restore(slot, &world) {
+                    Ok((save, notes)) => {
+                        clock = PlayClock::resume(save.playtime_secs());
+                        observers.load(&mut game_state, save.state);
+                        record_step(&mut recorder, RecordedStep::load(&input, &game_state), &mut plain_ui);
                         print_game_text("Game loaded successfully!", &config);
+                        for note in notes {
+                            print_game_text(&note, &config);
+                        }
+                        if let Ok(room_desc) = Engine::describe(&world, &game_state) {
+                            print_typewriter_effect(&format!("\n{}", room_desc), &config);
+                        }
                     }
                     Err(e) => eprintln!("Failed to load game: {}", e),
                 }
                 continue;
             }
+            None => {}
+        }
+        if let Some(code) = parse_import_command(&input) {
+            match saves::import_code(&world, code) {
+                Ok((state, notes)) => {
+                    observers.load(&mut game_state, state);
+                    record_step(&mut recorder, RecordedStep::load(&input, &game_state), &mut plain_ui);
+                    print_game_text("Save code imported.", &config);
+                    for note in notes {
+                        print_game_text(&note, &config);
+                    }
+                    if let Ok(room_desc) = Engine::describe(&world, &game_state) {
+                        print_typewriter_effect(&format!("\n{}", room_desc), &config);
+                    }
+                }
+                Err(e) => eprintln!("Failed to import save code: {}", e),
+            }
+            continue;
+        }
+        match input.trim().to_lowercase().as_str() {
+            "export-save" => {
+                match saves::export_code(&world, &game_state) {
+                    // Printed as it is so it can be copied in one piece
+                    Ok(code) => {
+                        print_game_text("Your save code (use 'import-save CODE' to load it anywhere):", &config);
+                        println!("{}", code);
+                    }
+                    Err(e) => eprintln!("Failed to export save: {}", e),
+                }
+                continue;
+            }
+            "saves" => {
+                match saves.list() {
+                    Ok(slots) if slots.is_empty() => print_game_text("No saves yet.", &config),
+                    Ok(slots) => {
+                        for line in slots.iter().flat_map(SlotInfo::lines) {
+                            print_game_text(&line, &config);
+                        }
+                    }
+                    Err(e) => eprintln!("Failed to list saves: {}", e),
+                }
+                continue;
+            }
+            "endings" if !world.endings.is_empty() => {
+                for line in profile.endings_gallery(&world) {
+                    print_game_text(&line, &config);
+                }
+                continue;
+            }
+            "achievements" if !world.achievements.is_empty() => {
+                for line in profile.achievements_list(&world) {
+                    print_game_text(&line, &config);
+                }
+                continue;
+            }
             "quit" | "exit" => {
-                game_state.has_quit = true;
+                observers.quit(&mut game_state);
                 continue;
             }
             _ => {}
@@ -299,7 +545,19 @@ fn play_story_plain(story_file: &str, world: world::World, config: GameConfig) {
         match parse_user_choice(&input, &available_choices, &config) {
             Some(choice_index) => {
                 let choice = available_choices[choice_index];
-                execute_actions(choice, &mut game_state, &config);
+                let previous_room = game_state.current_room_id.clone();
+                match game::execute_choice(&world, choice, &mut game_state, &mut plain_ui, &mut observers) {
+                    Ok(events) => {
+                        record_step(&mut recorder, RecordedStep::choice(&input, &choice.id, &events, &game_state), &mut plain_ui);
+                        record_progress(&events, &mut profile, &profile_filename, &mut plain_ui);
+                        if config.auto_save {
+                            if let Err(e) = saves.auto_save(&world, &game_state, &previous_room, config.auto_save_every_choice, clock.elapsed()) {
+                                eprintln!("Failed to autosave: {}", e);
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("Error executing choice: {}", e),
+                }
             }
             None => {
                 if config.allow_text_commands {
@@ -312,10 +570,16 @@ fn play_story_plain(story_file: &str, world: world::World, config: GameConfig) {
     }
 
     print_game_text("\nThank you for playing!", &config);
+
+    if !world.endings.is_empty() {
+        for line in profile.endings_gallery(&world) {
+            print_game_text(&line, &config);
+        }
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn play_story_terminal_ui(story_file: &str, world: world::World, config: GameConfig) {
+fn play_story_terminal_ui(story_file: &str, world: world::World, config: GameConfig, seed: u64, mut recorder: Option<Recorder>, mut observers: Observers) {
     let mut terminal_ui = match TerminalUi::new(config.clone()) {
         Ok(ui) => ui,
         Err(e) => {
@@ -323,64 +587,72 @@ fn play_story_terminal_ui(story_file: &str, world: world::World, config: GameCon
             return;
         }
     };
+    terminal_ui.set_speakers(world.speakers());
+    terminal_ui.set_unavailable_actions(keymap::KeyAction::unavailable_in(&world));
 
-    let save_filename = format!("{}.save", story_file.replace(".md", ""));
+    let saves = SaveStore::for_story(story_file);
+    let profile_filename = PlayerProfile::path_for_story(story_file);
+    let mut profile = PlayerProfile::load_or_default(&profile_filename).unwrap_or_else(|e| {
+        terminal_ui.display_text(&format!("Failed to load profile: {}", e));
+        PlayerProfile::default()
+    });
     
-    let mut game_state = if GameState::has_save_file(&save_filename) {
-        terminal_ui.display_text("Found save file. Load it? (y/n)");
-        match terminal_ui.get_input() {
-            Ok(input) if input.trim().eq_ignore_ascii_case("y") => {
-                match GameState::load_from_file(&save_filename) {
-                    Ok(state) => {
+    let mut clock = PlayClock::start();
+    let mut game_state = GameState::for_world(&world, seed);
+    // Offer the autosave and quick save, most recent first, ahead of a new game
+    let slots: Vec<SlotInfo> = saves
+        .list()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|slot| slot.name == AUTO_SAVE || slot.name == QUICK_SAVE)
+        .collect();
+    if !slots.is_empty() {
+        let mut labels: Vec<String> = slots
+            .iter()
+            .map(|slot| {
+                let action = if slot.name == AUTO_SAVE { "Continue from autosave" } else { "Load quick save" };
+                match &slot.metadata {
+                    Some(metadata) => format!("{} — {}", action, metadata.summary()),
+                    None => action.to_string(),
+                }
+            })
+            .collect();
+        labels.push("New game".to_string());
+        if let Ok(Some(index)) = terminal_ui.pick_slot("Welcome back", &labels) {
+            if let Some(slot) = slots.get(index) {
+                match saves.restore(&slot.name, &world) {
+                    Ok((save, notes)) => {
                         terminal_ui.display_text("Game loaded successfully!");
-                        state
+                        for note in notes {
+                            terminal_ui.display_text(&note);
+                        }
+                        clock = PlayClock::resume(save.playtime_secs());
+                        observers.load(&mut game_state, save.state);
                     }
                     Err(e) => {
                         terminal_ui.display_text(&format!("Failed to load save file: {}", e));
                         terminal_ui.display_text("Starting new game...");
-                        GameState::new(world.starting_room_id.clone())
                     }
                 }
             }
-            _ => GameState::new(world.starting_room_id.clone()),
         }
-    } else {
-        GameState::new(world.starting_room_id.clone())
-    };
-    
-    let mut last_room_id = game_state.current_room_id.clone();
+    }
+    record_step(&mut recorder, RecordedStep::start(&world, &game_state, seed), &mut terminal_ui);
 
     //terminal_ui.display_text("--- Welcome to the Restoration Project ---");
     //terminal_ui.display_text("Special commands: 'save' to save game, 'load' to load game, 'quit' to exit");
 
     // Initial room description
-    let current_room = match world.rooms.get(&game_state.current_room_id) {
-        Some(room) => room,
-        None => {
-            terminal_ui.display_text(&format!("Error: Starting room '{}' not found!", game_state.current_room_id));
+    match Engine::describe(&world, &game_state) {
+        Ok(room_desc) => terminal_ui.display_text(&format!("\n{}", room_desc)),
+        Err(e) => {
+            terminal_ui.display_text(&format!("Error: {}", e));
             let _ = terminal_ui.cleanup();
             return;
         }
-    };
-    let room_desc = get_room_description(current_room, &game_state);
-    terminal_ui.display_text(&format!("\n{}", room_desc));
+    }
 
     while !game_state.has_quit {
-        // Only display room description if we have changed rooms
-        if last_room_id != game_state.current_room_id {
-            let current_room = match world.rooms.get(&game_state.current_room_id) {
-                Some(room) => room,
-                None => {
-                    terminal_ui.display_text(&format!("Error: Room '{}' not found!", game_state.current_room_id));
-                    break;
-                }
-            };
-            let room_desc = get_room_description(current_room, &game_state);
-            terminal_ui.clear_text();
-            terminal_ui.display_text(&format!("\n{}", room_desc));
-            last_room_id = game_state.current_room_id.clone();
-        }
-
         let available_choices = match get_available_choices(&world, &game_state) {
             Ok(choices) => choices,
             Err(e) => {
@@ -391,7 +663,7 @@ fn play_story_terminal_ui(story_file: &str, world: world::World, config: GameCon
 
         if available_choices.is_empty() {
             terminal_ui.display_text("There is nothing you can do here.");
-            game_state.has_quit = true;
+            observers.quit(&mut game_state);
             continue;
         }
 
@@ -406,27 +678,123 @@ fn play_story_terminal_ui(story_file: &str, world: world::World, config: GameCon
         };
 
         // Handle special commands first
+        if let Some(steps) = parse_rewind_command(&input) {
+            match observers.rewind(&world, &mut game_state, steps) {
+                Ok(undone) => {
+                    record_step(&mut recorder, RecordedStep::rewind(&input, undone, &game_state), &mut terminal_ui);
+                    terminal_ui.display_text(&format!("Rewound {} choice(s).", undone));
+                    if let Ok(room_desc) = Engine::describe(&world, &game_state) {
+                        terminal_ui.clear_text();
+                        terminal_ui.display_text(&format!("\n{}", room_desc));
+                    }
+                }
+                Err(e) => terminal_ui.display_text(&format!("Failed to undo: {}", e)),
+            }
+            continue;
+        }
         match input.trim().to_lowercase().as_str() {
             "save" => {
-                match game_state.save_to_file(&save_filename) {
-                    Ok(()) => terminal_ui.display_text("Game saved successfully!"),
+                let slot = match terminal_ui.get_text_input_or_cancel(&format!("Name this save (letters, numbers, - and _; '{}' for the quick save):", QUICK_SAVE)) {
+                    Ok(Some(slot)) => slot,
+                    Ok(None) => {
+                        terminal_ui.display_text("Save cancelled.");
+                        continue;
+                    }
+                    Err(_) => continue,
+                };
+                match saves.save(&slot, &world, &game_state, clock.elapsed()) {
+                    Ok(()) => terminal_ui.display_text(&format!("Game saved to '{}'.", slot)),
                     Err(e) => terminal_ui.display_text(&format!("Failed to save game: {}", e)),
                 }
                 continue;
             }
-            "load" => {
-                match GameState::load_from_file(&save_filename) {
-                    Ok(loaded_state) => {
-                        game_state = loaded_state;
-                        last_room_id = "".to_string(); // Force room description to show
+            "load" | "saves" => {
+                let slots = match saves.list() {
+                    Ok(slots) if slots.is_empty() => {
+                        terminal_ui.display_text("No saves yet.");
+                        continue;
+                    }
+                    Ok(slots) => slots,
+                    Err(e) => {
+                        terminal_ui.display_text(&format!("Failed to list saves: {}", e));
+                        continue;
+                    }
+                };
+                let labels: Vec<String> = slots.iter().map(|slot| slot.lines().join("\n")).collect();
+                let slot = match terminal_ui.pick_slot("Load a save", &labels) {
+                    Ok(Some(index)) => &slots[index].name,
+                    _ => continue,
+                };
+                match saves.restore(slot, &world) {
+                    Ok((save, notes)) => {
+                        clock = PlayClock::resume(save.playtime_secs());
+                        observers.load(&mut game_state, save.state);
+                        record_step(&mut recorder, RecordedStep::load(&format!("load {}", slot), &game_state), &mut terminal_ui);
                         terminal_ui.display_text("Game loaded successfully!");
+                        for note in notes {
+                            terminal_ui.display_text(&note);
+                        }
+                        if let Ok(room_desc) = Engine::describe(&world, &game_state) {
+                            terminal_ui.clear_text();
+                            terminal_ui.display_text(&format!("\n{}", room_desc));
+                        }
                     }
                     Err(e) => terminal_ui.display_text(&format!("Failed to load game: {}", e)),
                 }
                 continue;
             }
+            "export-save" => {
+                match saves::export_code(&world, &game_state) {
+                    Ok(code) => {
+                        terminal_ui.display_text("Your save code (press i to import it here or use 'import-save CODE' anywhere):");
+                        // Long codes are split over lines; import ignores the breaks
+                        let lines: Vec<&str> = code.as_bytes().chunks(60).filter_map(|chunk| std::str::from_utf8(chunk).ok()).collect();
+                        terminal_ui.display_verbatim(&lines);
+                    }
+                    Err(e) => terminal_ui.display_text(&format!("Failed to export save: {}", e)),
+                }
+                continue;
+            }
+            "import-save" => {
+                let code = match terminal_ui.get_text_input_or_cancel("Paste a save code:") {
+                    Ok(Some(code)) => code,
+                    Ok(None) => {
+                        terminal_ui.display_text("Import cancelled.");
+                        continue;
+                    }
+                    Err(_) => continue,
+                };
+                match saves::import_code(&world, &code) {
+                    Ok((state, notes)) => {
+                        observers.load(&mut game_state, state);
+                        record_step(&mut recorder, RecordedStep::load("import-save", &game_state), &mut terminal_ui);
+                        terminal_ui.display_text("Save code imported.");
+                        for note in notes {
+                            terminal_ui.display_text(&note);
+                        }
+                        if let Ok(room_desc) = Engine::describe(&world, &game_state) {
+                            terminal_ui.clear_text();
+                            terminal_ui.display_text(&format!("\n{}", room_desc));
+                        }
+                    }
+                    Err(e) => terminal_ui.display_text(&format!("Failed to import save code: {}", e)),
+                }
+                continue;
+            }
+            "endings" if !world.endings.is_empty() => {
+                for line in profile.endings_gallery(&world) {
+                    terminal_ui.display_text(&line);
+                }
+                continue;
+            }
+            "achievements" if !world.achievements.is_empty() => {
+                for line in profile.achievements_list(&world) {
+                    terminal_ui.display_text(&line);
+                }
+                continue;
+            }
             "quit" | "exit" => {
-                game_state.has_quit = true;
+                observers.quit(&mut game_state);
                 continue;
             }
             _ => {}
@@ -435,9 +803,19 @@ fn play_story_terminal_ui(story_file: &str, world: world::World, config: GameCon
         match parse_user_choice(&input, &available_choices, &config) {
             Some(choice_index) => {
                 let choice = available_choices[choice_index];
+                let previous_room = game_state.current_room_id.clone();
                 // Use the new unified UI approach for proper text pacing
-                if let Err(e) = game::execute_choice(choice, &mut game_state, &mut terminal_ui, &config) {
-                    terminal_ui.display_text(&format!("Error executing choice: {}", e));
+                match game::execute_choice(&world, choice, &mut game_state, &mut terminal_ui, &mut observers) {
+                    Ok(events) => {
+                        record_step(&mut recorder, RecordedStep::choice(&input, &choice.id, &events, &game_state), &mut terminal_ui);
+                        record_progress(&events, &mut profile, &profile_filename, &mut terminal_ui);
+                        if config.auto_save {
+                            if let Err(e) = saves.auto_save(&world, &game_state, &previous_room, config.auto_save_every_choice, clock.elapsed()) {
+                                terminal_ui.show_toast(&format!("Failed to autosave: {}", e));
+                            }
+                        }
+                    }
+                    Err(e) => terminal_ui.display_text(&format!("Error executing choice: {}", e)),
                 }
             }
             None => {
@@ -451,10 +829,95 @@ fn play_story_terminal_ui(story_file: &str, world: world::World, config: GameCon
     }
 
     terminal_ui.display_text("\nThank you for playing!");
+
+    if !world.endings.is_empty() {
+        for line in profile.endings_gallery(&world) {
+            terminal_ui.display_text(&line);
+        }
+        // Leave the gallery on screen until the player is done with it
+        let _ = terminal_ui.wait_for_continue();
+    }
     let _ = terminal_ui.cleanup();
 }
 
 
+// Dice seed for games started without --seed
+#[cfg(not(target_arch = "wasm32"))]
+fn clock_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+// Recording problems are reported but never stop the game
+#[cfg(not(target_arch = "wasm32"))]
+fn record_step<T: GameUI>(recorder: &mut Option<Recorder>, step: RecordedStep, ui: &mut T) {
+    if let Some(recorder) = recorder {
+        if let Err(e) = recorder.record(&step) {
+            let _ = ui.display_text(&format!("Failed to record step: {}", e));
+        }
+    }
+}
+
+// Save endings and achievements to the profile as soon as they happen, and announce the achievements
+#[cfg(not(target_arch = "wasm32"))]
+fn record_progress<T: GameUI>(events: &[GameEvent], profile: &mut PlayerProfile, profile_filename: &str, ui: &mut T) {
+    let unlocked = profile.record_achievements(events);
+    let found_ending = profile.record_endings(events);
+    if unlocked.is_empty() && !found_ending {
+        return;
+    }
+    if let Err(e) = profile.save_to_file(profile_filename) {
+        let _ = ui.display_text(&format!("Failed to save profile: {}", e));
+    }
+    for title in unlocked {
+        ui.show_toast(&format!("🏆 Achievement unlocked: {}", title));
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+enum SlotCommand<'a> {
+    Save(&'a str),
+    Load(&'a str),
+}
+
+// "save" or "load" with an optional slot name, e.g. "save chapter2"
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_slot_command(input: &str) -> Option<SlotCommand<'_>> {
+    let mut words = input.split_whitespace();
+    let command = words.next()?.to_lowercase();
+    let slot = words.next().unwrap_or(QUICK_SAVE);
+    if words.next().is_some() {
+        return None;
+    }
+    match command.as_str() {
+        "save" => Some(SlotCommand::Save(slot)),
+        "load" => Some(SlotCommand::Load(slot)),
+        _ => None,
+    }
+}
+
+// "import-save CODE"; the code is case-sensitive, so it isn't lowercased
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_import_command(input: &str) -> Option<&str> {
+    let input = input.trim();
+    let (command, code) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    command.eq_ignore_ascii_case("import-save").then(|| code.trim())
+}
+
+// "undo" steps back one choice; "rewind N" steps back N
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_rewind_command(input: &str) -> Option<usize> {
+    let input = input.trim().to_lowercase();
+    let mut parts = input.split_whitespace();
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some("undo"), None, None) | (Some("rewind"), None, None) => Some(1),
+        (Some("rewind"), Some(steps), None) => steps.parse().ok(),
+        _ => None,
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 fn validate_story(story_file: &str) {
     match load_world_from_markdown(story_file) {
@@ -462,7 +925,7 @@ fn validate_story(story_file: &str) {
             println!("✅ Story '{}' is valid!", story_file);
             // Run the detailed validation from story_validator
             std::process::Command::new("cargo")
-                .args(&["run", "--bin", "story_validator", story_file])
+                .args(["run", "--bin", "story_validator", story_file])
                 .status()
                 .expect("Failed to run story validator");
         }
@@ -474,112 +937,297 @@ fn validate_story(story_file: &str) {
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn handle_config(action: ConfigAction) {
-    match action {
-        ConfigAction::Show => {
-            match GameConfig::load_or_create() {
-                Ok(config) => {
-                    println!("Current Configuration:");
-                    println!("  Typewriter enabled: {}", config.enable_typewriter);
-                    println!("  Typewriter speed: {} ms", config.typewriter_speed_ms);
-                    println!("  Text commands: {}", config.allow_text_commands);
-                    println!("  Auto save: {}", config.auto_save);
-                    println!("  UI mode: {:?}", config.ui_mode);
-                }
-                Err(e) => eprintln!("Error loading config: {}", e),
+fn replay_recording(recording_file: &str, story_file: &str) {
+    let world = match load_world_from_markdown(story_file) {
+        Ok(world) => world,
+        Err(e) => {
+            eprintln!("❌ Story '{}' is invalid: {}", story_file, e);
+            std::process::exit(1);
+        }
+    };
+    let recording = match load_recording(recording_file) {
+        Ok(recording) => recording,
+        Err(e) => {
+            eprintln!("❌ Failed to read recording '{}': {}", recording_file, e);
+            std::process::exit(1);
+        }
+    };
+
+    match replay(&world, &recording) {
+        Ok(game_state) => {
+            println!("✅ Replayed {} step(s) of '{}' against '{}'", recording.len().saturating_sub(1), recording_file, story_file);
+            match &game_state.ending {
+                Some(ending_id) => println!("  Finished at ending '{}' in room '{}'", ending_id, game_state.current_room_id),
+                None => println!("  Finished in room '{}'", game_state.current_room_id),
             }
         }
-        ConfigAction::Reset => {
-            let default_config = GameConfig::default();
-            match default_config.save() {
-                Ok(()) => println!("✅ Configuration reset to defaults"),
-                Err(e) => eprintln!("❌ Failed to reset config: {}", e),
+        Err(divergence) => {
+            eprintln!("❌ Replay diverged at {}", divergence);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Bring saves and the profile kept next to the story by older versions into the data directory
+#[cfg(not(target_arch = "wasm32"))]
+fn migrate_story_files(story_file: &str) {
+    match paths::migrate_legacy_story_files(story_file) {
+        Ok(notes) => {
+            for note in notes {
+                eprintln!("{}", note);
             }
         }
-        ConfigAction::SetSpeed { speed } => {
-            match GameConfig::load_or_create() {
-                Ok(mut config) => {
-                    config.typewriter_speed_ms = speed;
-                    match config.save() {
-                        Ok(()) => println!("✅ Typewriter speed set to {} ms", speed),
-                        Err(e) => eprintln!("❌ Failed to save config: {}", e),
+        Err(e) => eprintln!("Failed to copy old saves: {}", e),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn handle_saves(action: SavesAction) {
+    let (SavesAction::List { story } | SavesAction::Show { story, .. } | SavesAction::Delete { story, .. }) = &action;
+    migrate_story_files(story);
+    match action {
+        SavesAction::List { story } => {
+            match SaveStore::for_story(&story).list() {
+                Ok(slots) if slots.is_empty() => println!("No saves for {}", story),
+                Ok(slots) => {
+                    println!("💾 Saves for {}:", story);
+                    for line in slots.iter().flat_map(SlotInfo::lines) {
+                        println!("  {}", line);
                     }
                 }
-                Err(e) => eprintln!("Error loading config: {}", e),
+                Err(e) => {
+                    eprintln!("❌ Failed to list saves: {}", e);
+                    std::process::exit(1);
+                }
             }
         }
-        ConfigAction::EnableTypewriter => {
-            match GameConfig::load_or_create() {
-                Ok(mut config) => {
-                    config.enable_typewriter = true;
-                    match config.save() {
-                        Ok(()) => println!("✅ Typewriter effect enabled"),
-                        Err(e) => eprintln!("❌ Failed to save config: {}", e),
-                    }
+        SavesAction::Show { story, slot } => {
+            let save = match SaveStore::for_story(&story).load(&slot) {
+                Ok(save) => save,
+                Err(e) => {
+                    eprintln!("❌ {}", e);
+                    std::process::exit(1);
                 }
-                Err(e) => eprintln!("Error loading config: {}", e),
+            };
+            println!("💾 Save '{}':", slot);
+            if let Some(metadata) = &save.metadata {
+                if !metadata.story_title.is_empty() {
+                    println!("  Story: {}", metadata.story_title);
+                }
+                println!("  Saved: {}", format_age(metadata.saved_at));
+                println!("  Playtime: {}", format_playtime(metadata.playtime_secs));
+                println!("  Excerpt: {}", metadata.excerpt);
+            }
+            println!("  Room: {}", save.state.current_room_id);
+            if save.format_version == 0 {
+                println!("  Format: unversioned (older than save slots)");
+            } else {
+                println!("  Format: v{} (engine {})", save.format_version, save.engine_version);
             }
+            if let Ok(world) = load_world_from_markdown(&story) {
+                let matches = save.story_hash == world.structure_hash();
+                println!("  Story changes: {}", if matches { "none since this save" } else { "rooms, choices or variables changed; the save is migrated on load" });
+            }
+            let mut flags: Vec<&str> = save.state.flags.iter().map(|flag| flag.0.as_str()).collect();
+            flags.sort();
+            println!("  Flags: {}", if flags.is_empty() { "none".to_string() } else { flags.join(", ") });
+            let mut counters: Vec<String> = save.state.counters.iter().map(|(name, value)| format!("{} = {}", name, value)).collect();
+            counters.sort();
+            println!("  Counters: {}", if counters.is_empty() { "none".to_string() } else { counters.join(", ") });
         }
-        ConfigAction::DisableTypewriter => {
-            match GameConfig::load_or_create() {
-                Ok(mut config) => {
-                    config.enable_typewriter = false;
-                    match config.save() {
-                        Ok(()) => println!("✅ Typewriter effect disabled"),
-                        Err(e) => eprintln!("❌ Failed to save config: {}", e),
-                    }
+        SavesAction::Delete { story, slot } => {
+            match SaveStore::for_story(&story).delete(&slot) {
+                Ok(()) => println!("✅ Deleted save '{}'", slot),
+                Err(e) => {
+                    eprintln!("❌ Failed to delete save: {}", e);
+                    std::process::exit(1);
                 }
-                Err(e) => eprintln!("Error loading config: {}", e),
             }
         }
-        ConfigAction::EnableTextCommands => {
-            match GameConfig::load_or_create() {
-                Ok(mut config) => {
-                    config.allow_text_commands = true;
-                    match config.save() {
-                        Ok(()) => println!("✅ Text commands enabled"),
-                        Err(e) => eprintln!("❌ Failed to save config: {}", e),
-                    }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn handle_config(action: ConfigAction) {
+    match action {
+        ConfigAction::Show { origin, story } => {
+            let layers = match load_config_layers(story.as_deref()) {
+                Some(layers) => layers,
+                None => return,
+            };
+            println!("Current Configuration:");
+            let entries = layers.entries();
+            let width = entries.iter().map(|(key, _, _)| key.len()).max().unwrap_or(0);
+            for (key, value, value_origin) in entries {
+                if origin {
+                    println!("  {:width$} = {:10} ({})", key, config::display_value(value), value_origin, width = width);
+                } else {
+                    println!("  {:width$} = {}", key, config::display_value(value), width = width);
                 }
-                Err(e) => eprintln!("Error loading config: {}", e),
             }
+            println!("  Config file: {}", paths::config_path().display());
+            println!("  Data directory: {}", paths::data_dir().display());
         }
-        ConfigAction::DisableTextCommands => {
-            match GameConfig::load_or_create() {
-                Ok(mut config) => {
-                    config.allow_text_commands = false;
-                    match config.save() {
-                        Ok(()) => println!("✅ Text commands disabled"),
-                        Err(e) => eprintln!("❌ Failed to save config: {}", e),
-                    }
-                }
-                Err(e) => eprintln!("Error loading config: {}", e),
+        ConfigAction::Get { key, story } => {
+            let layers = match load_config_layers(story.as_deref()) {
+                Some(layers) => layers,
+                None => return,
+            };
+            match layers.get(&key) {
+                Ok((value, _)) => println!("{}", config::display_value(value)),
+                Err(e) => eprintln!("❌ {}", e),
             }
         }
-        ConfigAction::EnableUiMode => {
-            match GameConfig::load_or_create() {
-                Ok(mut config) => {
-                    config.ui_mode = UiMode::Centered;
-                    match config.save() {
-                        Ok(()) => println!("✅ Centered UI mode enabled"),
-                        Err(e) => eprintln!("❌ Failed to save config: {}", e),
-                    }
-                }
-                Err(e) => eprintln!("Error loading config: {}", e),
+        ConfigAction::Set { key, value } => set_config(&[(key.as_str(), value.as_str())]),
+        ConfigAction::Unset { key } => match GameConfig::unset_in_file(&key) {
+            Ok(true) => println!("✅ {} now uses its default", key),
+            Ok(false) => println!("{} isn't set in the config file", key),
+            Err(e) => eprintln!("❌ {}", e),
+        },
+        ConfigAction::Reset => {
+            match GameConfig::reset_file() {
+                Ok(()) => println!("✅ Configuration reset to defaults"),
+                Err(e) => eprintln!("❌ Failed to reset config: {}", e),
             }
         }
-        ConfigAction::DisableUiMode => {
-            match GameConfig::load_or_create() {
-                Ok(mut config) => {
-                    config.ui_mode = UiMode::Plain;
-                    match config.save() {
-                        Ok(()) => println!("✅ Plain UI mode enabled"),
-                        Err(e) => eprintln!("❌ Failed to save config: {}", e),
-                    }
-                }
-                Err(e) => eprintln!("Error loading config: {}", e),
+        ConfigAction::Doctor { fix } => config_doctor(fix),
+        ConfigAction::SetSpeed { speed } => deprecated_set_config(&[("typewriter_speed_ms", &speed.to_string())]),
+        ConfigAction::EnableTypewriter => deprecated_set_config(&[("enable_typewriter", "true")]),
+        ConfigAction::DisableTypewriter => deprecated_set_config(&[("enable_typewriter", "false")]),
+        ConfigAction::EnableTextCommands => deprecated_set_config(&[("allow_text_commands", "true")]),
+        ConfigAction::DisableTextCommands => deprecated_set_config(&[("allow_text_commands", "false")]),
+        ConfigAction::EnableUiMode => deprecated_set_config(&[("ui_mode", "Centered")]),
+        ConfigAction::DisableUiMode => deprecated_set_config(&[("ui_mode", "Plain")]),
+        ConfigAction::EnableAutoSave { every_choice } => {
+            deprecated_set_config(&[("auto_save", "true"), ("auto_save_every_choice", if every_choice { "true" } else { "false" })])
+        }
+        ConfigAction::DisableAutoSave => deprecated_set_config(&[("auto_save", "false")]),
+    }
+}
+
+// The old shortcut subcommands, pointing at the `config set` commands they stand for
+#[cfg(not(target_arch = "wasm32"))]
+fn deprecated_set_config(settings: &[(&str, &str)]) {
+    let commands: Vec<String> = settings.iter().map(|(key, raw)| format!("`restoration config set {} {}`", key, raw)).collect();
+    eprintln!("⚠️  This command is deprecated; use {} instead", commands.join(" and "));
+    set_config(settings);
+}
+
+// The config as a story would be played with it, without command-line flags
+#[cfg(not(target_arch = "wasm32"))]
+fn load_config_layers(story: Option<&str>) -> Option<ConfigLayers> {
+    let world = match story.map(load_world_from_markdown).transpose() {
+        Ok(world) => world,
+        Err(e) => {
+            eprintln!("Error loading story: {}", e);
+            return None;
+        }
+    };
+    match ConfigLayers::load(world.as_ref()) {
+        Ok(layers) => {
+            for warning in &layers.warnings {
+                eprintln!("⚠️  {}", warning);
             }
+            Some(layers)
+        }
+        Err(e) => {
+            eprintln!("Error loading config: {}", e);
+            None
         }
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+fn config_doctor(fix: bool) {
+    println!("Checking {}", paths::config_path().display());
+    let check = match config::check_config_file() {
+        Ok(check) => check,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
+    if check.all_issues().next().is_none() {
+        println!("✅ No problems found");
+        return;
+    }
+    for issue in check.all_issues() {
+        println!("  ⚠️  {}", issue.problem);
+        if let Some(repair) = &issue.repair {
+            println!("      → {}", repair);
+        }
+    }
+    if !check.needs_repair() {
+        return;
+    }
+    if !fix {
+        println!("Run `restoration config doctor --fix` to apply these repairs");
+        return;
+    }
+    match config::repair_config_file(&check) {
+        Ok(backup) => println!("✅ Config file repaired; the old one is at {}", backup.display()),
+        Err(e) => eprintln!("❌ Failed to repair config: {}", e),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn set_config(settings: &[(&str, &str)]) {
+    for (key, raw) in settings {
+        match GameConfig::set_in_file(key, raw) {
+            Ok(value) => println!("✅ {} = {}", key, config::display_value(&value)),
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                return;
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use world::load_world_from_markdown_content;
+
+    #[test]
+    fn endings_reach_the_profile_file_as_soon_as_they_happen() {
+        let world = load_world_from_markdown_content(
+            "# Endings\n\n@start home\n@ending grove \"The Grove\"\n\n## @room home\nA cabin.\n\n### Walk out\n- end: grove\n",
+        )
+        .unwrap();
+        let dir = std::env::temp_dir().join(format!("restoration-main-{}-endings", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let profile_filename = dir.join("story.json").to_string_lossy().into_owned();
+        let mut profile = PlayerProfile::default();
+        let mut game_state = GameState::for_world(&world, 1);
+
+        let events = Engine::choose(&world, &mut game_state, world::ChoiceId("home_choice_0".to_string())).unwrap();
+        record_progress(&events, &mut profile, &profile_filename, &mut PlainUi::new(GameConfig::default()));
+
+        let saved = PlayerProfile::load_or_default(&profile_filename).unwrap();
+        assert_eq!(saved.endings_found, ["grove".to_string()].into_iter().collect());
+    }
+
+    #[test]
+    fn undo_and_rewind_commands_take_a_step_count() {
+        assert_eq!(parse_rewind_command("undo"), Some(1));
+        assert_eq!(parse_rewind_command(" Rewind "), Some(1));
+        assert_eq!(parse_rewind_command("rewind 3"), Some(3));
+        assert_eq!(parse_rewind_command("rewind three"), None);
+        assert_eq!(parse_rewind_command("undo 2"), None);
+        assert_eq!(parse_rewind_command("rewind 1 2"), None);
+    }
+
+    #[test]
+    fn auto_save_flags_override_the_config_for_one_session() {
+        assert!(Cli::try_parse_from(["restoration", "play", "story.md", "--auto-save", "--no-auto-save"]).is_err());
+        match Cli::try_parse_from(["restoration", "play", "story.md", "--no-auto-save"]).unwrap().command {
+            Some(Commands::Play { auto_save, no_auto_save, .. }) => assert!(!auto_save && no_auto_save),
+            _ => panic!("expected the play command"),
+        }
+
+        let mut layers = ConfigLayers::new(&GameConfig { auto_save: true, ..GameConfig::default() });
+        layers.set("auto_save", "false", ConfigOrigin::Cli("--no-auto-save".to_string())).unwrap();
+        assert!(!layers.config().unwrap().auto_save);
+    }
+}
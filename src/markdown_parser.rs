@@ -1,12 +1,22 @@
-use std::collections::HashMap;
-use crate::world::{World, Room, Choice, Action, Condition, FlagId, ConditionalDescription};
+use std::collections::{BTreeMap, HashMap};
+use crate::world::{World, Room, Choice, ChoiceId, Action, Condition, FlagId, ConditionalDescription, Ending, Achievement, SkillCheck, DialogueNode, DialogueLine, Variable, DescriptionMode};
 use crate::errors::{GameError, GameResult};
+use crate::pacing::is_pacing_marker;
 
 #[derive(Debug)]
 struct MarkdownStory {
     title: Option<String>,
     starting_room_id: String,
+    no_undo: bool,
+    description_mode: DescriptionMode,
+    endings: Vec<Ending>,
+    achievements: Vec<Achievement>,
+    check_message: Option<String>,
+    variables: Vec<Variable>,
+    renamed_rooms: HashMap<String, String>,
+    config: BTreeMap<String, String>,
     rooms: HashMap<String, MarkdownRoom>,
+    dialogues: HashMap<String, MarkdownDialogue>,
 }
 
 #[derive(Debug)]
@@ -16,10 +26,19 @@ struct MarkdownRoom {
     choices: Vec<MarkdownChoice>,
 }
 
+#[derive(Debug)]
+struct MarkdownDialogue {
+    id: String,
+    lines: Vec<DialogueLine>,
+    choices: Vec<MarkdownChoice>,
+}
+
 #[derive(Debug)]
 struct MarkdownDescription {
     condition: Option<Condition>,
     text: String,
+    is_else: bool,
+    ends_paragraph: bool,
 }
 
 #[derive(Debug)]
@@ -39,12 +58,22 @@ fn parse_markdown(content: &str) -> GameResult<MarkdownStory> {
     let mut story = MarkdownStory {
         title: None,
         starting_room_id: String::new(),
+        no_undo: false,
+        description_mode: DescriptionMode::FirstMatch,
+        endings: Vec::new(),
+        achievements: Vec::new(),
+        check_message: None,
+        variables: Vec::new(),
+        renamed_rooms: HashMap::new(),
+        config: BTreeMap::new(),
         rooms: HashMap::new(),
+        dialogues: HashMap::new(),
     };
     
     let mut current_room: Option<MarkdownRoom> = None;
+    let mut current_dialogue: Option<MarkdownDialogue> = None;
     let mut current_choice: Option<MarkdownChoice> = None;
-    let mut i = 0;
+    let mut i = parse_front_matter(&lines, &mut story.config)?;
     
     while i < lines.len() {
         let line = lines[i].trim();
@@ -63,19 +92,51 @@ fn parse_markdown(content: &str) -> GameResult<MarkdownStory> {
         else if let Some(start_id) = parse_start_directive(line) {
             story.starting_room_id = start_id;
         }
+        // Disable undo for puzzle stories: @no_undo
+        else if line == "@no_undo" {
+            story.no_undo = true;
+        }
+        // How rooms combine descriptions: @descriptions additive
+        else if let Some(mode) = line.strip_prefix("@descriptions ") {
+            story.description_mode = match mode.trim() {
+                "additive" => DescriptionMode::Additive,
+                "first_match" => DescriptionMode::FirstMatch,
+                other => return Err(GameError::ValidationError(format!("Unknown description mode '{}'; use additive or first_match", other))),
+            };
+        }
+        // Ending declaration: @ending ending_id "Title"
+        else if let Some((ending_id, title)) = parse_ending_directive(line) {
+            declare_ending(&mut story.endings, ending_id, title);
+        }
+        // Skill check message: @check_message "[{skill}: {total} vs {target}]"
+        else if let Some(message) = line.strip_prefix("@check_message ") {
+            story.check_message = Some(unquote(message.trim()).to_string());
+        }
+        // Counter declaration: @var gold = 10, @var faith 0..10
+        else if let Some(rest) = line.strip_prefix("@var ") {
+            story.variables.push(parse_counter_declaration(rest)?);
+        }
+        // Flag declaration: @flag lantern_lit = true
+        else if let Some(rest) = line.strip_prefix("@flag ") {
+            story.variables.push(parse_flag_declaration(rest)?);
+        }
+        // Room rename, for saves made before it: @renamed old_id -> new_id
+        else if let Some(rest) = line.strip_prefix("@renamed ") {
+            let (old_id, new_id) = rest
+                .split_once("->")
+                .map(|(old_id, new_id)| (old_id.trim(), new_id.trim()))
+                .filter(|(old_id, new_id)| !old_id.is_empty() && !new_id.is_empty())
+                .ok_or_else(|| GameError::ValidationError(format!("Invalid rename '{}'; use @renamed old_id -> new_id", rest.trim())))?;
+            story.renamed_rooms.insert(old_id.to_string(), new_id.to_string());
+        }
+        // Achievement declaration: @achievement achievement_id "Title" [condition]
+        else if let Some(rest) = line.strip_prefix("@achievement ") {
+            story.achievements.push(parse_achievement_directive(rest)?);
+        }
         // Room definition: ## @room room_id
         else if let Some(room_id) = parse_room_header(line) {
-            // Save previous room if exists
-            if let Some(room) = current_room.take() {
-                // Save current choice if exists
-                if let Some(choice) = current_choice.take() {
-                    let mut room = room;
-                    room.choices.push(choice);
-                    story.rooms.insert(room.id.clone(), room);
-                } else {
-                    story.rooms.insert(room.id.clone(), room);
-                }
-            }
+            // Save previous room or dialogue node and its last choice
+            finish_section(&mut story, &mut current_room, &mut current_dialogue, &mut current_choice);
             
             // Start new room - parse conditional descriptions
             i += 1;
@@ -88,12 +149,28 @@ fn parse_markdown(content: &str) -> GameResult<MarkdownStory> {
             });
             continue; // Don't increment i again
         }
+        // Dialogue node: ## @dialogue node_id
+        else if let Some(node_id) = parse_dialogue_header(line) {
+            finish_section(&mut story, &mut current_room, &mut current_dialogue, &mut current_choice);
+            
+            i += 1;
+            let lines = parse_dialogue_lines(&lines, &mut i);
+            
+            current_dialogue = Some(MarkdownDialogue {
+                id: node_id,
+                lines,
+                choices: Vec::new(),
+            });
+            continue; // Don't increment i again
+        }
         // Choice definition: ### Choice text [condition]
         else if let Some((choice_text, condition)) = parse_choice_header(line) {
             // Save previous choice if exists
             if let Some(choice) = current_choice.take() {
                 if let Some(ref mut room) = current_room {
                     room.choices.push(choice);
+                } else if let Some(ref mut dialogue) = current_dialogue {
+                    dialogue.choices.push(choice);
                 }
             }
             
@@ -106,6 +183,10 @@ fn parse_markdown(content: &str) -> GameResult<MarkdownStory> {
         // Action line: tab-indented action
         else if let Some(action_str) = parse_action_line(line) {
             if let Some(ref mut choice) = current_choice {
+                // Endings named in an action count as declared
+                if let Some((ending_id, Some(title))) = parse_ending_action(&action_str) {
+                    declare_ending(&mut story.endings, ending_id, Some(title));
+                }
                 match parse_action(&action_str) {
                     Ok(action) => choice.actions.push(action),
                     Err(e) => eprintln!("Warning: Failed to parse action '{}': {}", action_str, e),
@@ -116,15 +197,8 @@ fn parse_markdown(content: &str) -> GameResult<MarkdownStory> {
         i += 1;
     }
     
-    // Save final room and choice
-    if let Some(choice) = current_choice {
-        if let Some(ref mut room) = current_room {
-            room.choices.push(choice);
-        }
-    }
-    if let Some(room) = current_room {
-        story.rooms.insert(room.id.clone(), room);
-    }
+    // Save final room or dialogue node and choice
+    finish_section(&mut story, &mut current_room, &mut current_dialogue, &mut current_choice);
     
     // Validate required fields
     if story.starting_room_id.is_empty() {
@@ -137,13 +211,17 @@ fn parse_markdown(content: &str) -> GameResult<MarkdownStory> {
 fn parse_room_descriptions(lines: &[&str], i: &mut usize) -> GameResult<Vec<MarkdownDescription>> {
     let mut descriptions = Vec::new();
     let mut current_condition: Option<Condition> = None;
+    let mut current_is_else = false;
     let mut current_text_lines = Vec::new();
+    // A blank line after a block's text ends its paragraph
+    let mut blank_after_text = false;
     
     while *i < lines.len() {
         let line = lines[*i].trim();
         
         // Empty line
         if line.is_empty() {
+            blank_after_text = !current_text_lines.is_empty();
             *i += 1;
             continue;
         }
@@ -153,23 +231,28 @@ fn parse_room_descriptions(lines: &[&str], i: &mut usize) -> GameResult<Vec<Mark
             break;
         }
         
-        // Check for conditional block: [condition]
-        if line.starts_with('[') && line.ends_with(']') {
+        // Check for conditional block: [condition] (pacing markers like [wait] are text)
+        if line.starts_with('[') && line.ends_with(']') && !is_pacing_marker(line) {
             // Save previous description if exists
             if !current_text_lines.is_empty() {
                 descriptions.push(MarkdownDescription {
                     condition: current_condition.take(),
                     text: current_text_lines.join(" "),
+                    is_else: current_is_else,
+                    ends_paragraph: blank_after_text,
                 });
                 current_text_lines.clear();
+                blank_after_text = false;
             }
             
-            // Parse new condition from line like "[condition]"
-            let condition_str = &line[1..line.len()-1];
-            current_condition = parse_condition(condition_str).ok();
+            // Parse new condition from line like "[condition]", or an [else] block
+            let condition_str = line[1..line.len()-1].trim();
+            current_is_else = condition_str == "else";
+            current_condition = if current_is_else { None } else { parse_condition(condition_str).ok() };
         } else {
             // Regular description text
             current_text_lines.push(line);
+            blank_after_text = false;
         }
         
         *i += 1;
@@ -180,6 +263,8 @@ fn parse_room_descriptions(lines: &[&str], i: &mut usize) -> GameResult<Vec<Mark
         descriptions.push(MarkdownDescription {
             condition: current_condition,
             text: current_text_lines.join(" "),
+            is_else: current_is_else,
+            ends_paragraph: blank_after_text,
         });
     }
     
@@ -188,42 +273,243 @@ fn parse_room_descriptions(lines: &[&str], i: &mut usize) -> GameResult<Vec<Mark
         return Ok(vec![MarkdownDescription {
             condition: None,
             text: String::new(),
+            is_else: false,
+            ends_paragraph: false,
         }]);
     }
     
     Ok(descriptions)
 }
 
-fn parse_title(line: &str) -> Option<String> {
-    if line.starts_with("# ") {
-        Some(line[2..].trim().to_string())
-    } else {
-        None
+// Store the open room or dialogue node along with its last choice
+fn finish_section(
+    story: &mut MarkdownStory,
+    current_room: &mut Option<MarkdownRoom>,
+    current_dialogue: &mut Option<MarkdownDialogue>,
+    current_choice: &mut Option<MarkdownChoice>,
+) {
+    let choice = current_choice.take();
+    if let Some(mut room) = current_room.take() {
+        room.choices.extend(choice);
+        story.rooms.insert(room.id.clone(), room);
+    } else if let Some(mut dialogue) = current_dialogue.take() {
+        dialogue.choices.extend(choice);
+        story.dialogues.insert(dialogue.id.clone(), dialogue);
     }
 }
 
-fn parse_start_directive(line: &str) -> Option<String> {
-    if line.starts_with("@start ") {
-        Some(line[7..].trim().to_string())
-    } else {
-        None
+// Lines before the first response: `Alvin: "Hello."`, narration, or `[condition] Alvin: ...`
+fn parse_dialogue_lines(lines: &[&str], i: &mut usize) -> Vec<DialogueLine> {
+    let mut dialogue_lines = Vec::new();
+    
+    while *i < lines.len() {
+        let line = lines[*i].trim();
+        
+        if line.starts_with('#') {
+            break;
+        }
+        if line.is_empty() || line.starts_with("<!--") {
+            *i += 1;
+            continue;
+        }
+        
+        // Optional condition in brackets at the start of the line
+        let (condition, rest) = match line.strip_prefix('[').and_then(|after| after.split_once(']')) {
+            Some((condition_str, rest)) if !is_pacing_marker(&format!("[{}]", condition_str)) => {
+                (parse_condition(condition_str).ok(), rest.trim())
+            }
+            _ => (None, line),
+        };
+        let (speaker, text) = split_speaker(rest);
+        dialogue_lines.push(DialogueLine {
+            condition,
+            speaker: speaker.map(str::to_string),
+            text: text.to_string(),
+        });
+        
+        *i += 1;
     }
+    
+    dialogue_lines
+}
+
+// A speaker is a short capitalised name before ": "; anything else is narration
+fn split_speaker(line: &str) -> (Option<&str>, &str) {
+    if let Some((speaker, text)) = line.split_once(": ") {
+        let is_name = speaker.len() <= 30
+            && speaker.chars().next().is_some_and(char::is_uppercase)
+            && speaker.split_whitespace().count() <= 3
+            && speaker.chars().all(|c| c.is_alphanumeric() || c == ' ' || c == '\'' || c == '-' || c == '.');
+        if is_name {
+            return (Some(speaker), text.trim());
+        }
+    }
+    (None, line)
+}
+
+// Config overrides between `---` lines at the very top of the story, one `key: value`
+// per line. Returns the index of the first line after the block.
+fn parse_front_matter(lines: &[&str], config: &mut BTreeMap<String, String>) -> GameResult<usize> {
+    let start = match lines.iter().position(|line| !line.trim().is_empty()) {
+        Some(start) if lines[start].trim() == "---" => start,
+        _ => return Ok(0),
+    };
+    for (index, line) in lines.iter().enumerate().skip(start + 1) {
+        let line = line.trim();
+        if line == "---" {
+            return Ok(index + 1);
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once(':')
+            .map(|(key, value)| (key.trim(), unquote(value.trim())))
+            .filter(|(key, _)| !key.is_empty())
+            .ok_or_else(|| GameError::ValidationError(format!("Invalid front matter line {}: '{}'; use key: value", index + 1, line)))?;
+        config.insert(key.to_string(), value.to_string());
+    }
+    Err(GameError::ValidationError(format!("The front matter starting on line {} is never closed with ---", start + 1)))
+}
+
+fn parse_title(line: &str) -> Option<String> {
+    line.strip_prefix("# ").map(|title| title.trim().to_string())
+}
+
+fn parse_start_directive(line: &str) -> Option<String> {
+    line.strip_prefix("@start ").map(|id| id.trim().to_string())
 }
 
 fn parse_room_header(line: &str) -> Option<String> {
-    if line.starts_with("## @room ") {
-        Some(line[9..].trim().to_string())
-    } else {
+    line.strip_prefix("## @room ").map(|id| id.trim().to_string())
+}
+
+fn parse_dialogue_header(line: &str) -> Option<String> {
+    line.strip_prefix("## @dialogue ").map(|id| id.trim().to_string())
+}
+
+fn parse_ending_directive(line: &str) -> Option<(String, Option<String>)> {
+    let rest = line.strip_prefix("@ending ")?;
+    split_ending(rest)
+}
+
+fn parse_ending_action(action_str: &str) -> Option<(String, Option<String>)> {
+    let rest = action_str.trim().strip_prefix("end: ")?;
+    split_ending(rest)
+}
+
+// "ending_id" or "ending_id \"Title\""
+fn split_ending(rest: &str) -> Option<(String, Option<String>)> {
+    let rest = rest.trim();
+    let (ending_id, title) = match rest.find(char::is_whitespace) {
+        Some(pos) => (&rest[..pos], Some(unquote(rest[pos..].trim()).to_string())),
+        None => (rest, None),
+    };
+    if ending_id.is_empty() {
         None
+    } else {
+        Some((ending_id.to_string(), title.filter(|title| !title.is_empty())))
     }
 }
 
-fn parse_choice_header(line: &str) -> Option<(String, Option<Condition>)> {
-    if !line.starts_with("### ") {
-        return None;
+fn declare_ending(endings: &mut Vec<Ending>, ending_id: String, title: Option<String>) {
+    match endings.iter_mut().find(|ending| ending.id == ending_id) {
+        Some(existing) => {
+            // Only fill in a title that was never given
+            if let Some(title) = title.filter(|_| existing.title == existing.id) {
+                existing.title = title;
+            }
+        }
+        None => {
+            let title = title.unwrap_or_else(|| ending_id.clone());
+            endings.push(Ending { id: ending_id, title });
+        }
     }
-    
-    let content = &line[4..];
+}
+
+// "achievement_id \"Title\" [condition]", after `@achievement `
+fn parse_achievement_directive(rest: &str) -> GameResult<Achievement> {
+    let rest = rest.trim();
+    let (achievement_id, rest) = match rest.find(char::is_whitespace) {
+        Some(pos) => (&rest[..pos], rest[pos..].trim()),
+        None => (rest, ""),
+    };
+    if achievement_id.is_empty() {
+        return Err(GameError::ValidationError("Achievement without an ID; use @achievement achievement_id \"Title\"".to_string()));
+    }
+
+    // Optional automatic unlock condition in brackets at the end
+    let (title, condition) = match rest.rfind('[') {
+        Some(bracket_start) if rest.ends_with(']') => {
+            let condition_str = &rest[bracket_start + 1..rest.len() - 1];
+            let condition = parse_condition(condition_str).map_err(|e| match e {
+                GameError::ValidationError(reason) => {
+                    GameError::ValidationError(format!("Invalid condition for achievement '{}': {}", achievement_id, reason))
+                }
+                other => other,
+            })?;
+            (rest[..bracket_start].trim(), Some(condition))
+        }
+        _ => (rest, None),
+    };
+    let title = unquote(title);
+
+    Ok(Achievement {
+        id: achievement_id.to_string(),
+        title: if title.is_empty() { achievement_id.to_string() } else { title.to_string() },
+        condition,
+    })
+}
+
+// "gold", "gold = 10", "faith 0..10" or "faith 0..10 = 5"
+fn parse_counter_declaration(rest: &str) -> GameResult<Variable> {
+    let invalid = || GameError::ValidationError(format!("Invalid counter declaration: @var {}", rest.trim()));
+
+    let (declaration, initial) = match rest.split_once('=') {
+        Some((declaration, value)) => (declaration, Some(value.trim().parse::<i32>().map_err(|_| invalid())?)),
+        None => (rest, None),
+    };
+    let mut parts = declaration.split_whitespace();
+    let name = parts.next().ok_or_else(invalid)?;
+    let range = match parts.next() {
+        Some(range) => {
+            let (min, max) = range.split_once("..").ok_or_else(invalid)?;
+            Some((min.parse::<i32>().map_err(|_| invalid())?, max.parse::<i32>().map_err(|_| invalid())?))
+        }
+        None => None,
+    };
+    if parts.next().is_some() {
+        return Err(invalid());
+    }
+
+    // Without a value, start as close to zero as the range allows
+    let initial = initial.unwrap_or_else(|| match range {
+        Some((min, max)) if min <= max => 0.clamp(min, max),
+        _ => 0,
+    });
+    Ok(Variable::Counter { name: name.to_string(), initial, range })
+}
+
+// "lantern_lit" or "lantern_lit = true"
+fn parse_flag_declaration(rest: &str) -> GameResult<Variable> {
+    let invalid = || GameError::ValidationError(format!("Invalid flag declaration: @flag {}", rest.trim()));
+
+    let (name, initial) = match rest.split_once('=') {
+        Some((name, value)) => match value.trim() {
+            "true" => (name.trim(), true),
+            "false" => (name.trim(), false),
+            _ => return Err(invalid()),
+        },
+        None => (rest.trim(), false),
+    };
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(invalid());
+    }
+    Ok(Variable::Flag { name: name.to_string(), initial })
+}
+
+fn parse_choice_header(line: &str) -> Option<(String, Option<Condition>)> {
+    let content = line.strip_prefix("### ")?;
     
     // Check for condition in brackets at the end
     if let Some(bracket_start) = content.rfind('[') {
@@ -242,51 +528,55 @@ fn parse_choice_header(line: &str) -> Option<(String, Option<Condition>)> {
 fn parse_action_line(line: &str) -> Option<String> {
     let trimmed = line.trim();
     // Check for dash-prefixed action (Markdown list style)
-    if trimmed.starts_with("- ") {
-        let action = trimmed[2..].trim().to_string();
-        Some(action)
-    } else {
-        None
-    }
+    trimmed.strip_prefix("- ").map(|action| action.trim().to_string())
 }
 
 fn parse_action(action_str: &str) -> GameResult<Action> {
     let action_str = action_str.trim();
     
     // Display text with say: prefix
-    if action_str.starts_with("say: ") {
-        return Ok(Action::DisplayText(action_str[5..].to_string()));
+    if let Some(text) = action_str.strip_prefix("say: ") {
+        return Ok(Action::DisplayText(text.to_string()));
     }
     
     // Movement: goto: room_id or @room_id
-    if action_str.starts_with("goto: ") {
-        return Ok(Action::GoTo(action_str[6..].to_string()));
+    if let Some(room_id) = action_str.strip_prefix("goto: ") {
+        return Ok(Action::GoTo(room_id.to_string()));
+    }
+    
+    if let Some(room_id) = action_str.strip_prefix('@') {
+        return Ok(Action::GoTo(room_id.to_string()));
+    }
+    
+    // Dialogue: -> node_id starts or continues a conversation, exit leaves it
+    if let Some(node_id) = action_str.strip_prefix("-> ") {
+        return Ok(Action::Dialogue(node_id.trim().to_string()));
     }
     
-    if action_str.starts_with('@') {
-        return Ok(Action::GoTo(action_str[1..].to_string()));
+    if action_str == "exit" {
+        return Ok(Action::ExitDialogue);
     }
     
     // Flags: flag+ name, flag- name
-    if action_str.starts_with("flag+ ") {
-        return Ok(Action::SetFlag(FlagId(action_str[6..].to_string())));
+    if let Some(flag) = action_str.strip_prefix("flag+ ") {
+        return Ok(Action::SetFlag(FlagId(flag.to_string())));
     }
     
-    if action_str.starts_with("flag- ") {
-        return Ok(Action::RemoveFlag(FlagId(action_str[6..].to_string())));
+    if let Some(flag) = action_str.strip_prefix("flag- ") {
+        return Ok(Action::RemoveFlag(FlagId(flag.to_string())));
     }
     
     // Counters: count+ name, count- name, count= name value
-    if action_str.starts_with("count+ ") {
-        return Ok(Action::IncrementCounter(action_str[7..].to_string()));
+    if let Some(counter) = action_str.strip_prefix("count+ ") {
+        return Ok(Action::IncrementCounter(counter.to_string()));
     }
     
-    if action_str.starts_with("count- ") {
-        return Ok(Action::DecrementCounter(action_str[7..].to_string()));
+    if let Some(counter) = action_str.strip_prefix("count- ") {
+        return Ok(Action::DecrementCounter(counter.to_string()));
     }
     
-    if action_str.starts_with("count= ") {
-        let parts: Vec<&str> = action_str[7..].split_whitespace().collect();
+    if let Some(rest) = action_str.strip_prefix("count= ") {
+        let parts: Vec<&str> = rest.split_whitespace().collect();
         if parts.len() == 2 {
             if let Ok(value) = parts[1].parse::<i32>() {
                 return Ok(Action::SetCounter(parts[0].to_string(), value));
@@ -294,6 +584,52 @@ fn parse_action(action_str: &str) -> GameResult<Action> {
         }
     }
     
+    // Text input: ask variable "Prompt text"
+    if let Some(rest) = action_str.strip_prefix("ask ") {
+        let rest = rest.trim();
+        let (variable, prompt) = match rest.find(char::is_whitespace) {
+            Some(pos) => (&rest[..pos], rest[pos..].trim()),
+            None => (rest, ""),
+        };
+        let is_identifier = !variable.is_empty()
+            && variable.chars().all(|c| c.is_alphanumeric() || c == '_');
+        let is_quoted = prompt.is_empty() || (prompt.len() >= 2 && prompt.starts_with('"') && prompt.ends_with('"'));
+        if is_identifier && is_quoted {
+            return Ok(Action::AskText {
+                variable: variable.to_string(),
+                prompt: unquote(prompt).to_string(),
+            });
+        }
+    }
+    
+    // Named ending: end: ending_id "Title"
+    if let Some((ending_id, _)) = parse_ending_action(action_str) {
+        return Ok(Action::Ending(ending_id));
+    }
+    
+    // Skill check: check d20 + courage >= 12: @success else: @failure
+    if let Some(rest) = action_str.strip_prefix("check ") {
+        let (check_str, outcomes) = rest.split_once(": ").ok_or_else(|| {
+            GameError::ValidationError(format!("Skill check needs an outcome after ':': {}", action_str))
+        })?;
+        let check = parse_skill_check(check_str)?;
+        let (success_str, failure_str) = match outcomes.split_once(" else: ") {
+            Some((success, failure)) => (success, Some(failure)),
+            None => (outcomes, None),
+        };
+        let on_success = Box::new(parse_check_outcome(success_str)?);
+        let on_failure = match failure_str {
+            Some(failure) => Some(Box::new(parse_check_outcome(failure)?)),
+            None => None,
+        };
+        return Ok(Action::SkillCheck { check, on_success, on_failure });
+    }
+    
+    // Achievement: achieve achievement_id
+    if let Some(achievement_id) = action_str.strip_prefix("achieve ") {
+        return Ok(Action::Achieve(achievement_id.trim().to_string()));
+    }
+    
     // Quit: quit, end
     if action_str == "quit" || action_str == "end" {
         return Ok(Action::Quit);
@@ -336,9 +672,29 @@ fn parse_condition(condition_str: &str) -> GameResult<Condition> {
 fn parse_single_condition(condition_str: &str) -> GameResult<Condition> {
     let condition_str = condition_str.trim();
     
+    // One-shot dice roll: check d20 + agility >= 15
+    if let Some(check_str) = condition_str.strip_prefix("check ") {
+        return Ok(Condition::SkillCheck(parse_skill_check(check_str)?));
+    }
+    
     // Simple flag conditions
-    if condition_str.starts_with('!') {
-        return Ok(Condition::NotHasFlag(FlagId(condition_str[1..].to_string())));
+    if let Some(flag) = condition_str.strip_prefix('!') {
+        return Ok(Condition::NotHasFlag(FlagId(flag.to_string())));
+    }
+    
+    // String conditions compare against a quoted value: name = "Joseph"
+    for op in &[" != ", " = "] {
+        if let Some(pos) = condition_str.find(op) {
+            let variable = condition_str[..pos].trim();
+            let value_str = condition_str[pos + op.len()..].trim();
+            if value_str.len() >= 2 && value_str.starts_with('"') && value_str.ends_with('"') {
+                let value = unquote(value_str).to_string();
+                return match op.trim() {
+                    "=" => Ok(Condition::StringEquals(variable.to_string(), value)),
+                    _ => Ok(Condition::StringNotEquals(variable.to_string(), value)),
+                };
+            }
+        }
     }
     
     // Check for counter conditions
@@ -351,7 +707,7 @@ fn parse_single_condition(condition_str: &str) -> GameResult<Condition> {
                     ">" => Ok(Condition::CounterGreaterThan(counter.to_string(), value)),
                     "<" => Ok(Condition::CounterLessThan(counter.to_string(), value)),
                     "=" => Ok(Condition::CounterEquals(counter.to_string(), value)),
-                    _ => Err(GameError::ValidationError(format!("Unsupported operator: {}", op))),
+                    _ => Err(GameError::ValidationError(format!("Unsupported operator: {}", op.trim()))),
                 };
             }
         }
@@ -361,19 +717,64 @@ fn parse_single_condition(condition_str: &str) -> GameResult<Condition> {
     Ok(Condition::HasFlag(FlagId(condition_str.to_string())))
 }
 
+// Outcomes are single actions; quoted text is shown without its quotes
+fn parse_check_outcome(outcome_str: &str) -> GameResult<Action> {
+    let outcome_str = outcome_str.trim();
+    if outcome_str.len() >= 2 && outcome_str.starts_with('"') && outcome_str.ends_with('"') {
+        return Ok(Action::DisplayText(unquote(outcome_str).to_string()));
+    }
+    parse_action(outcome_str)
+}
+
+// "d20 + courage >= 12", "2d6 + strength - 1 >= 9"
+fn parse_skill_check(check_str: &str) -> GameResult<SkillCheck> {
+    let invalid = || GameError::ValidationError(format!("Invalid skill check: {}", check_str));
+
+    let (roll_str, target_str) = check_str.split_once(" >= ").ok_or_else(invalid)?;
+    let target = target_str.trim().parse::<i32>().map_err(|_| invalid())?;
+
+    let mut terms = roll_str.split_whitespace();
+    let (dice_str, sides_str) = terms.next().and_then(|dice| dice.split_once('d')).ok_or_else(invalid)?;
+    let dice = if dice_str.is_empty() { 1 } else { dice_str.parse::<u32>().map_err(|_| invalid())? };
+    let sides = sides_str.parse::<u32>().map_err(|_| invalid())?;
+    if dice == 0 || sides == 0 {
+        return Err(invalid());
+    }
+
+    let mut modifiers = Vec::new();
+    let mut bonus = 0;
+    while let Some(sign) = terms.next() {
+        let term = terms.next().ok_or_else(invalid)?;
+        match (sign, term.parse::<i32>()) {
+            ("+", Ok(value)) => bonus += value,
+            ("-", Ok(value)) => bonus -= value,
+            ("+", Err(_)) => modifiers.push(term.to_string()),
+            _ => return Err(invalid()),
+        }
+    }
+
+    Ok(SkillCheck { dice, sides, modifiers, bonus, target })
+}
+
+fn unquote(text: &str) -> &str {
+    text.strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+        .unwrap_or(text)
+}
+
 fn convert_to_world(story: MarkdownStory) -> GameResult<World> {
     let mut rooms = HashMap::new();
     let mut choices = HashMap::new();
-    let mut choice_counter = 0;
     
     for (room_id, md_room) in story.rooms {
         let mut room_choices = Vec::new();
         
-        for md_choice in md_room.choices {
-            let choice_id = format!("choice_{}", choice_counter);
-            choice_counter += 1;
+        for (index, md_choice) in md_room.choices.into_iter().enumerate() {
+            // Derived from the room so IDs stay stable between runs
+            let choice_id = format!("{}_choice_{}", room_id, index);
             
             let choice = Choice {
+                id: ChoiceId(choice_id.clone()),
                 text: md_choice.text,
                 condition: md_choice.condition,
                 actions: md_choice.actions,
@@ -387,20 +788,211 @@ fn convert_to_world(story: MarkdownStory) -> GameResult<World> {
             ConditionalDescription {
                 condition: md_desc.condition,
                 text: md_desc.text,
+                is_else: md_desc.is_else,
+                ends_paragraph: md_desc.ends_paragraph,
             }
         }).collect();
         
         let room = Room {
             descriptions,
             choices: room_choices,
+            description_mode: story.description_mode,
         };
         
         rooms.insert(room_id, room);
     }
     
+    let mut dialogues = HashMap::new();
+    for (node_id, md_dialogue) in story.dialogues {
+        let mut node_choices = Vec::new();
+        
+        for (index, md_choice) in md_dialogue.choices.into_iter().enumerate() {
+            let choice_id = format!("{}_response_{}", node_id, index);
+            
+            let choice = Choice {
+                id: ChoiceId(choice_id.clone()),
+                text: md_choice.text,
+                condition: md_choice.condition,
+                actions: md_choice.actions,
+            };
+            
+            node_choices.push(choice_id.clone());
+            choices.insert(choice_id, choice);
+        }
+        
+        dialogues.insert(node_id, DialogueNode {
+            lines: md_dialogue.lines,
+            choices: node_choices,
+        });
+    }
+    
     Ok(World {
         rooms,
         choices,
         starting_room_id: story.starting_room_id,
+        title: story.title,
+        no_undo: story.no_undo,
+        endings: story.endings,
+        achievements: story.achievements,
+        check_message: story.check_message,
+        dialogues,
+        variables: story.variables,
+        renamed_rooms: story.renamed_rooms,
+        config: story.config,
     })
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ask_takes_a_variable_and_an_optional_prompt() {
+        match parse_action("ask name \"What is your name?\"").unwrap() {
+            Action::AskText { variable, prompt } => assert_eq!((variable.as_str(), prompt.as_str()), ("name", "What is your name?")),
+            other => panic!("expected a question, got {:?}", other),
+        }
+        match parse_action("ask name").unwrap() {
+            Action::AskText { variable, prompt } => assert_eq!((variable.as_str(), prompt.as_str()), ("name", "")),
+            other => panic!("expected a question, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn quoted_values_make_string_conditions() {
+        match parse_single_condition("name = \"Joseph\"").unwrap() {
+            Condition::StringEquals(variable, value) => assert_eq!((variable.as_str(), value.as_str()), ("name", "Joseph")),
+            other => panic!("expected a string comparison, got {:?}", other),
+        }
+        match parse_single_condition("name != \" Joseph \"").unwrap() {
+            Condition::StringNotEquals(variable, value) => assert_eq!((variable.as_str(), value.as_str()), ("name", " Joseph ")),
+            other => panic!("expected a string comparison, got {:?}", other),
+        }
+        match parse_single_condition("name = \"\"").unwrap() {
+            Condition::StringEquals(_, value) => assert!(value.is_empty()),
+            other => panic!("expected a string comparison, got {:?}", other),
+        }
+        // Unquoted numbers still compare counters
+        assert!(matches!(parse_single_condition("gold = 3").unwrap(), Condition::CounterEquals(_, 3)));
+    }
+
+    #[test]
+    fn dialogue_nodes_keep_lines_and_responses() {
+        let world = crate::world::load_world_from_markdown_content(
+            "# Talk\n\n@start home\n\n## @room home\nA cabin.\n\n### Talk to Alvin\n- -> alvin\n\n## @dialogue alvin\nAlvin: \"Joseph, do you have a minute?\"\nHe looks toward the fire.\n[hay_bales > 2] Alvin: \"You did good work today.\"\n\n### Ask about the preacher [!asked]\n- flag+ asked\n- -> alvin\n\n### Say goodnight\n- exit\n",
+        )
+        .unwrap();
+
+        let node = &world.dialogues["alvin"];
+        let lines: Vec<(Option<&str>, &str)> = node.lines.iter()
+            .map(|line| (line.speaker.as_deref(), line.text.as_str()))
+            .collect();
+        assert_eq!(lines, vec![
+            (Some("Alvin"), "\"Joseph, do you have a minute?\""),
+            (None, "He looks toward the fire."),
+            (Some("Alvin"), "\"You did good work today.\""),
+        ]);
+        assert!(node.lines[0].condition.is_none());
+        assert!(matches!(node.lines[2].condition, Some(Condition::CounterGreaterThan(ref counter, 2)) if counter == "hay_bales"));
+
+        assert_eq!(node.choices, vec!["alvin_response_0".to_string(), "alvin_response_1".to_string()]);
+        let ask = &world.choices["alvin_response_0"];
+        assert_eq!(ask.text, "Ask about the preacher");
+        assert!(matches!(ask.condition, Some(Condition::NotHasFlag(_))));
+        assert!(matches!(ask.actions.last(), Some(Action::Dialogue(node_id)) if node_id == "alvin"));
+        assert!(matches!(world.choices["alvin_response_1"].actions[..], [Action::ExitDialogue]));
+        assert!(matches!(world.choices["home_choice_0"].actions[..], [Action::Dialogue(ref node_id)] if node_id == "alvin"));
+    }
+
+    #[test]
+    fn dialogue_nodes_need_a_response() {
+        let result = crate::world::load_world_from_markdown_content(
+            "# Talk\n\n@start home\n\n## @room home\nA cabin.\n\n### Talk to Alvin\n- -> alvin\n\n## @dialogue alvin\nAlvin: \"Goodnight.\"\n",
+        );
+        match result {
+            Err(GameError::ValidationError(message)) => assert_eq!(message, "Dialogue 'alvin' has no responses to leave it by"),
+            other => panic!("expected a validation error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn endings_are_declared_by_directives_and_titled_actions() {
+        let world = crate::world::load_world_from_markdown_content(
+            "# Endings\n\n@start home\n@ending grove \"The Grove\"\n@ending cabin\n\n## @room home\nA cabin.\n\n### Walk out\n- end: grove \"Ignored\"\n\n### Stay in\n- end: cabin \"The Cabin\"\n\n### Sleep\n- end: dream \"A Dream\"\n",
+        )
+        .unwrap();
+
+        let endings: Vec<(&str, &str)> = world.endings.iter().map(|ending| (ending.id.as_str(), ending.title.as_str())).collect();
+        assert_eq!(endings, [("grove", "The Grove"), ("cabin", "The Cabin"), ("dream", "A Dream")]);
+        assert!(matches!(world.choices["home_choice_0"].actions[..], [Action::Ending(ref ending_id)] if ending_id == "grove"));
+    }
+
+    #[test]
+    fn endings_must_be_declared() {
+        let error = crate::world::load_world_from_markdown_content(
+            "# Endings\n\n@start home\n\n## @room home\nA cabin.\n\n### Walk out\n- end: grove\n",
+        )
+        .map(|_| ())
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("reaches undeclared ending 'grove'"), "{}", error);
+    }
+
+    #[test]
+    fn achievements_take_a_title_and_an_optional_condition() {
+        let patient = parse_achievement_directive("patient \"Baled all the hay\"").unwrap();
+        assert_eq!((patient.id.as_str(), patient.title.as_str()), ("patient", "Baled all the hay"));
+        assert!(patient.condition.is_none());
+
+        let rich = parse_achievement_directive("rich \"Saved up\" [gold > 99]").unwrap();
+        assert_eq!(rich.title, "Saved up");
+        assert!(matches!(rich.condition, Some(Condition::CounterGreaterThan(ref counter, 99)) if counter == "gold"));
+
+        let bare = parse_achievement_directive("bare").unwrap();
+        assert_eq!((bare.title.as_str(), bare.condition.is_none()), ("bare", true));
+    }
+
+    #[test]
+    fn achievements_with_broken_conditions_are_rejected() {
+        let error = parse_achievement_directive("rich \"Saved up\" [gold >= 99]").unwrap_err().to_string();
+        assert!(error.contains("Invalid condition for achievement 'rich': Unsupported operator: >="), "{}", error);
+        assert!(parse_achievement_directive("  ").is_err());
+
+        let error = crate::world::load_world_from_markdown_content("# Chores\n\n@start barn\n\n## @room barn\nA barn.\n\n### Bale hay\n- achieve patient\n")
+            .map(|_| ())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unlocks undeclared achievement 'patient'"), "{}", error);
+    }
+
+    #[test]
+    fn skill_checks_parse_dice_modifiers_and_outcomes() {
+        match parse_action("check 2d6 + courage + 2 - 1 >= 12: @bridge else: \"You slip.\"").unwrap() {
+            Action::SkillCheck { check, on_success, on_failure } => {
+                assert_eq!((check.dice, check.sides, check.bonus, check.target), (2, 6, 1, 12));
+                assert_eq!(check.modifiers, ["courage"]);
+                assert_eq!(check.to_string(), "2d6 + courage + 1 >= 12");
+                assert!(matches!(*on_success, Action::GoTo(ref room_id) if room_id == "bridge"));
+                assert!(matches!(on_failure.as_deref(), Some(Action::DisplayText(text)) if text == "You slip."));
+            }
+            other => panic!("expected a skill check, got {:?}", other),
+        }
+        match parse_action("check d20 >= 5: flag+ lucky").unwrap() {
+            Action::SkillCheck { check, on_failure, .. } => {
+                assert_eq!((check.dice, check.sides), (1, 20));
+                assert!(on_failure.is_none());
+            }
+            other => panic!("expected a skill check, got {:?}", other),
+        }
+        match parse_condition("check d20 + agility >= 15").unwrap() {
+            Condition::SkillCheck(check) => assert_eq!((check.modifiers[0].as_str(), check.target), ("agility", 15)),
+            other => panic!("expected a skill check, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn malformed_skill_checks_are_rejected() {
+        for action in ["check d20 + courage >= 12", "check d0 >= 3: @x", "check d20 >= high: @x", "check 20 >= 3: @x", "check d20 * 2 >= 3: @x"] {
+            assert!(parse_action(action).is_err(), "{}", action);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use serde_json::{json, Value};
+use crate::world::{ChoiceId, FlagId, World};
+use crate::game::GameState;
+use crate::engine::{Engine, GameEvent};
+use crate::errors::GameResult;
+
+/// Hooks for tools that react to a game as it is played: logging, analytics, effects.
+/// Add to a game's `Observers`; every method does nothing by default.
+pub trait GameObserver {
+    /// The player moved into `room_id` as the result of a choice.
+    fn room_entered(&mut self, _room_id: &str, _game_state: &GameState) {}
+
+    /// The player picked a choice; called before any of its actions run.
+    fn choice_selected(&mut self, _choice_id: &ChoiceId, _choice_text: &str, _game_state: &GameState) {}
+
+    fn flag_changed(&mut self, _flag: &FlagId, _is_set: bool) {}
+
+    fn counter_changed(&mut self, _counter: &str, _old_value: i32, _new_value: i32) {}
+
+    /// The story finished, through a named ending if `ending` is set.
+    fn game_ended(&mut self, _ending: Option<&str>, _game_state: &GameState) {}
+
+    /// The game jumped to another state: choices were undone or a save was loaded.
+    fn game_restored(&mut self, _game_state: &GameState) {}
+}
+
+/// The observers of one game. Plays choices through the engine and tells every
+/// observer what they changed; the engine itself stays free of side effects.
+#[derive(Default)]
+pub struct Observers {
+    observers: Vec<Box<dyn GameObserver>>,
+}
+
+impl Observers {
+    pub fn new() -> Self {
+        Observers::default()
+    }
+
+    pub fn add(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// `Engine::choose`, telling the observers about the choice before it runs and
+    /// about its changes afterwards.
+    pub fn choose(&mut self, world: &World, game_state: &mut GameState, choice_id: ChoiceId) -> GameResult<Vec<GameEvent>> {
+        Engine::check_choice(world, game_state, &choice_id)?;
+        if let Some(choice) = world.choices.get(&choice_id.0) {
+            self.notify(|observer| observer.choice_selected(&choice_id, &choice.text, game_state));
+        }
+        let events = Engine::choose(world, game_state, choice_id)?;
+        self.events(&events, game_state);
+        Ok(events)
+    }
+
+    /// `Engine::provide_input`, telling the observers about the rest of the choice.
+    pub fn provide_input(&mut self, world: &World, game_state: &mut GameState, answer: &str) -> GameResult<Vec<GameEvent>> {
+        let events = Engine::provide_input(world, game_state, answer)?;
+        self.events(&events, game_state);
+        Ok(events)
+    }
+
+    /// `Engine::rewind`, telling the observers where the game went back to.
+    pub fn rewind(&mut self, world: &World, game_state: &mut GameState, steps: usize) -> GameResult<usize> {
+        let undone = Engine::rewind(world, game_state, steps)?;
+        self.notify(|observer| observer.game_restored(game_state));
+        Ok(undone)
+    }
+
+    /// Carry on from a loaded save or an imported code instead of `game_state`.
+    pub fn load(&mut self, game_state: &mut GameState, loaded: GameState) {
+        *game_state = loaded;
+        self.notify(|observer| observer.game_restored(game_state));
+    }
+
+    /// End the game outside the story, as the `quit` command does.
+    pub fn quit(&mut self, game_state: &mut GameState) {
+        if game_state.has_quit {
+            return;
+        }
+        game_state.has_quit = true;
+        self.notify(|observer| observer.game_ended(game_state.ending.as_deref(), game_state));
+    }
+
+    // The state changes in a finished step
+    fn events(&mut self, events: &[GameEvent], game_state: &GameState) {
+        for event in events {
+            match event {
+                GameEvent::FlagChanged { flag, is_set } => self.notify(|observer| observer.flag_changed(flag, *is_set)),
+                GameEvent::CounterChanged { counter, old_value, new_value } => {
+                    self.notify(|observer| observer.counter_changed(counter, *old_value, *new_value))
+                }
+                GameEvent::RoomChanged { to, .. } => self.notify(|observer| observer.room_entered(to, game_state)),
+                GameEvent::Quit => self.notify(|observer| observer.game_ended(game_state.ending.as_deref(), game_state)),
+                _ => {}
+            }
+        }
+    }
+
+    fn notify(&mut self, mut callback: impl FnMut(&mut dyn GameObserver)) {
+        for observer in &mut self.observers {
+            callback(observer.as_mut());
+        }
+    }
+}
+
+/// Writes every observed change as one JSON object per line.
+pub struct JsonLinesLogger<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonLinesLogger<W> {
+    pub fn new(writer: W) -> Self {
+        JsonLinesLogger { writer }
+    }
+
+    fn log(&mut self, entry: Value) {
+        // A log that can't be written shouldn't stop the game
+        let _ = writeln!(self.writer, "{}", entry).and_then(|_| self.writer.flush());
+    }
+}
+
+impl JsonLinesLogger<BufWriter<File>> {
+    /// Log to `path`, replacing anything already there.
+    pub fn create(path: &str) -> GameResult<Self> {
+        Ok(JsonLinesLogger::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> GameObserver for JsonLinesLogger<W> {
+    fn room_entered(&mut self, room_id: &str, _game_state: &GameState) {
+        self.log(json!({ "event": "room_entered", "room": room_id }));
+    }
+
+    fn choice_selected(&mut self, choice_id: &ChoiceId, choice_text: &str, game_state: &GameState) {
+        self.log(json!({
+            "event": "choice_selected",
+            "choice": choice_id.0,
+            "text": choice_text,
+            "room": game_state.current_room_id,
+        }));
+    }
+
+    fn flag_changed(&mut self, flag: &FlagId, is_set: bool) {
+        self.log(json!({ "event": "flag_changed", "flag": flag.0, "set": is_set }));
+    }
+
+    fn counter_changed(&mut self, counter: &str, old_value: i32, new_value: i32) {
+        self.log(json!({ "event": "counter_changed", "counter": counter, "old": old_value, "new": new_value }));
+    }
+
+    fn game_ended(&mut self, ending: Option<&str>, game_state: &GameState) {
+        self.log(json!({ "event": "game_ended", "ending": ending, "room": game_state.current_room_id }));
+    }
+
+    fn game_restored(&mut self, game_state: &GameState) {
+        self.log(json!({ "event": "game_restored", "room": game_state.current_room_id }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::world::load_world_from_markdown_content;
+
+    const STORY: &str = "# Observed\n\n@start cellar\n\n@var courage 0..2 = 1\n@ending escaped \"Out\"\n\n## @room cellar\nA cellar.\n\n### Climb the stairs\n- flag+ lamp_lit\n- count+ courage\n- @hallway\n\n## @room hallway\nA hallway.\n\n### Sign the register\n- ask name \"Name?\"\n- \"Signed {name}.\"\n\n### Leave\n- end: escaped\n";
+
+    // Writes each hook call as a line the tests can compare
+    struct Recording(Rc<RefCell<Vec<String>>>);
+
+    impl GameObserver for Recording {
+        fn room_entered(&mut self, room_id: &str, _game_state: &GameState) {
+            self.0.borrow_mut().push(format!("room {}", room_id));
+        }
+
+        fn choice_selected(&mut self, choice_id: &ChoiceId, choice_text: &str, game_state: &GameState) {
+            self.0.borrow_mut().push(format!("choice {} '{}' in {}", choice_id.0, choice_text, game_state.current_room_id));
+        }
+
+        fn flag_changed(&mut self, flag: &FlagId, is_set: bool) {
+            self.0.borrow_mut().push(format!("flag {} {}", flag.0, is_set));
+        }
+
+        fn counter_changed(&mut self, counter: &str, old_value: i32, new_value: i32) {
+            self.0.borrow_mut().push(format!("counter {} {} -> {}", counter, old_value, new_value));
+        }
+
+        fn game_ended(&mut self, ending: Option<&str>, _game_state: &GameState) {
+            self.0.borrow_mut().push(format!("ended {:?}", ending));
+        }
+
+        fn game_restored(&mut self, game_state: &GameState) {
+            self.0.borrow_mut().push(format!("restored in {}", game_state.current_room_id));
+        }
+    }
+
+    // A log the test can still read after handing the logger over
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(bytes)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn observed() -> (World, GameState, Observers, Rc<RefCell<Vec<String>>>) {
+        let world = load_world_from_markdown_content(STORY).unwrap();
+        let game_state = GameState::for_world(&world, 1);
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let mut observers = Observers::new();
+        observers.add(Box::new(Recording(calls.clone())));
+        (world, game_state, observers, calls)
+    }
+
+    fn choice(id: &str) -> ChoiceId {
+        ChoiceId(id.to_string())
+    }
+
+    #[test]
+    fn observers_hear_a_choice_and_its_changes() {
+        let (world, mut game_state, mut observers, calls) = observed();
+        observers.choose(&world, &mut game_state, choice("cellar_choice_0")).unwrap();
+        assert_eq!(
+            *calls.borrow(),
+            [
+                "choice cellar_choice_0 'Climb the stairs' in cellar",
+                "flag lamp_lit true",
+                "counter courage 1 -> 2",
+                "room hallway",
+            ]
+        );
+    }
+
+    #[test]
+    fn observers_are_not_told_about_choices_that_cannot_be_made() {
+        let (world, mut game_state, mut observers, calls) = observed();
+        assert!(observers.choose(&world, &mut game_state, choice("hallway_choice_0")).is_err());
+        assert!(calls.borrow().is_empty());
+    }
+
+    #[test]
+    fn observers_hear_endings_once() {
+        let (world, mut game_state, mut observers, calls) = observed();
+        observers.choose(&world, &mut game_state, choice("cellar_choice_0")).unwrap();
+        calls.borrow_mut().clear();
+        observers.choose(&world, &mut game_state, choice("hallway_choice_1")).unwrap();
+        observers.quit(&mut game_state);
+        assert_eq!(*calls.borrow(), ["choice hallway_choice_1 'Leave' in hallway", "ended Some(\"escaped\")"]);
+    }
+
+    #[test]
+    fn observers_hear_undo_and_loads() {
+        let (world, mut game_state, mut observers, calls) = observed();
+        let start = game_state.clone();
+        observers.choose(&world, &mut game_state, choice("cellar_choice_0")).unwrap();
+        observers.load(&mut game_state, start);
+        assert_eq!(game_state.current_room_id, "cellar");
+        observers.choose(&world, &mut game_state, choice("cellar_choice_0")).unwrap();
+        calls.borrow_mut().clear();
+
+        assert_eq!(observers.rewind(&world, &mut game_state, 1).unwrap(), 1);
+        assert_eq!(*calls.borrow(), ["restored in cellar"]);
+    }
+
+    #[test]
+    fn logger_writes_one_json_line_per_change() {
+        let (world, mut game_state, mut observers, _) = observed();
+        let log = SharedBuffer::default();
+        observers.add(Box::new(JsonLinesLogger::new(log.clone())));
+
+        observers.choose(&world, &mut game_state, choice("cellar_choice_0")).unwrap();
+        observers.choose(&world, &mut game_state, choice("hallway_choice_0")).unwrap();
+        observers.provide_input(&world, &mut game_state, "Ada").unwrap();
+        observers.rewind(&world, &mut game_state, 1).unwrap();
+        observers.quit(&mut game_state);
+
+        let log = String::from_utf8(log.0.borrow().clone()).unwrap();
+        let lines: Vec<Value> = log.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(
+            lines,
+            [
+                json!({ "event": "choice_selected", "choice": "cellar_choice_0", "text": "Climb the stairs", "room": "cellar" }),
+                json!({ "event": "flag_changed", "flag": "lamp_lit", "set": true }),
+                json!({ "event": "counter_changed", "counter": "courage", "old": 1, "new": 2 }),
+                json!({ "event": "room_entered", "room": "hallway" }),
+                json!({ "event": "choice_selected", "choice": "hallway_choice_0", "text": "Sign the register", "room": "hallway" }),
+                json!({ "event": "game_restored", "room": "hallway" }),
+                json!({ "event": "game_ended", "ending": null, "room": "hallway" }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use regex::Regex;
+use std::sync::OnceLock;
+use crate::config::RevealMode;
+
+/// A piece of story text, split at the author's pacing markers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PacedText {
+    Text(String),
+    /// `[pause 800]`: hold for this many milliseconds before going on.
+    Pause(u64),
+    /// `[wait]`: wait for the player to continue.
+    Wait,
+    /// `---`: wait for the player, then start a fresh page.
+    PageBreak,
+}
+
+fn marker_regex() -> &'static Regex {
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    MARKER.get_or_init(|| {
+        Regex::new(r"(?m)\[pause\s+(\d+)\]|\[wait\]|^\s*---\s*$").expect("pacing marker pattern is valid")
+    })
+}
+
+/// Split text at `[pause N]`, `[wait]` and `---` markers, a `---` only counting on a line of its own.
+/// Text around a wait or page break is trimmed, since it starts a new paragraph.
+pub fn split_pacing(text: &str) -> Vec<PacedText> {
+    let mut segments = Vec::new();
+    let mut last_end = 0;
+
+    for captures in marker_regex().captures_iter(text) {
+        let marker = captures.get(0).expect("whole match");
+        push_text(&mut segments, &text[last_end..marker.start()]);
+        let segment = match captures.get(1) {
+            Some(millis) => PacedText::Pause(millis.as_str().parse().unwrap_or(0)),
+            None if marker.as_str().trim() == "[wait]" => PacedText::Wait,
+            None => PacedText::PageBreak,
+        };
+        segments.push(segment);
+        last_end = marker.end();
+    }
+    push_text(&mut segments, &text[last_end..]);
+
+    // A new paragraph shouldn't start or end with stray spaces
+    for index in 0..segments.len() {
+        let breaks_before = index > 0 && matches!(segments[index - 1], PacedText::Wait | PacedText::PageBreak);
+        let breaks_after = matches!(segments.get(index + 1), Some(PacedText::Wait | PacedText::PageBreak));
+        // "Wait. [pause 800] Then..." keeps one space, not two
+        let follows_spaced_pause = index >= 2
+            && matches!(segments[index - 1], PacedText::Pause(_))
+            && matches!(&segments[index - 2], PacedText::Text(before) if before.ends_with(char::is_whitespace));
+        if let PacedText::Text(text) = &mut segments[index] {
+            if breaks_before || follows_spaced_pause {
+                *text = text.trim_start().to_string();
+            }
+            if breaks_after {
+                *text = text.trim_end().to_string();
+            }
+        }
+    }
+    segments.retain(|segment| !matches!(segment, PacedText::Text(text) if text.is_empty()));
+    segments
+}
+
+fn push_text(segments: &mut Vec<PacedText>, text: &str) {
+    if !text.is_empty() {
+        segments.push(PacedText::Text(text.to_string()));
+    }
+}
+
+/// The text with its pacing removed, for front ends that can't pause.
+/// Waits become line breaks, page breaks blank lines, and pauses disappear. Only
+/// the spaces around the markers go; the caller's own line breaks are kept.
+pub fn strip_pacing(text: &str) -> String {
+    let mut result = String::new();
+    for segment in split_pacing(text) {
+        match segment {
+            PacedText::Text(text) => result.push_str(&text),
+            PacedText::Wait => result.push('\n'),
+            PacedText::PageBreak => result.push_str("\n\n"),
+            PacedText::Pause(_) => {}
+        }
+    }
+    result
+}
+
+/// Whether a whole line is a single pacing marker, so parsers keep it as text.
+pub fn is_pacing_marker(line: &str) -> bool {
+    matches!(split_pacing(line.trim()).as_slice(), [PacedText::Pause(_) | PacedText::Wait | PacedText::PageBreak])
+}
+
+/// How many characters at the start of `text` the typewriter shows in one step:
+/// one, a word with the spaces before it, or a sentence up to its closing
+/// punctuation (and any quote or bracket after it) or line break.
+pub fn reveal_step(text: &str, mode: &RevealMode) -> usize {
+    let ends_sentence = |c: char| matches!(c, '.' | '!' | '?' | '…');
+    let mut chars = text.chars().peekable();
+    let mut previous = None;
+    let mut count = 0;
+    while let Some(c) = chars.next() {
+        count += 1;
+        let next = chars.peek().copied();
+        let step_ends = match mode {
+            RevealMode::Character => return 1,
+            RevealMode::Word => !c.is_whitespace() && next.is_none_or(char::is_whitespace),
+            RevealMode::Sentence => {
+                let closes = ends_sentence(c)
+                    || (matches!(c, '"' | '\'' | '”' | '’' | ')') && previous.is_some_and(ends_sentence));
+                c == '\n' || (closes && next.is_none_or(char::is_whitespace))
+            }
+        };
+        if step_ends {
+            return count;
+        }
+        previous = Some(c);
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> PacedText {
+        PacedText::Text(text.to_string())
+    }
+
+    #[test]
+    fn pauses_keep_their_length_and_one_space() {
+        assert_eq!(
+            split_pacing("Wait. [pause 800] Then go."),
+            [text("Wait. "), PacedText::Pause(800), text("Then go.")]
+        );
+        assert_eq!(split_pacing("[pause 0]"), [PacedText::Pause(0)]);
+    }
+
+    #[test]
+    fn waits_trim_the_text_around_them() {
+        assert_eq!(split_pacing("First.  [wait]  Second."), [text("First."), PacedText::Wait, text("Second.")]);
+    }
+
+    #[test]
+    fn page_breaks_stand_on_their_own_line() {
+        assert_eq!(
+            split_pacing("The door shuts.\n---\nMorning comes."),
+            [text("The door shuts."), PacedText::PageBreak, text("Morning comes.")]
+        );
+        assert_eq!(split_pacing("  ---  "), [PacedText::PageBreak]);
+    }
+
+    #[test]
+    fn text_that_only_looks_like_a_marker_stays_text() {
+        for line in ["He paused --- then spoke.", "----", "[pause]", "[pause soon]", "[waiting]", "---more"] {
+            assert_eq!(split_pacing(line), [text(line)], "{:?}", line);
+            assert!(!is_pacing_marker(line), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn whole_lines_of_pacing_are_markers() {
+        assert!(is_pacing_marker("[pause 800]"));
+        assert!(is_pacing_marker(" [wait] "));
+        assert!(is_pacing_marker("---"));
+        assert!(!is_pacing_marker("Go. [wait]"));
+        assert!(!is_pacing_marker("[wait] [wait]"));
+    }
+
+    #[test]
+    fn stripping_turns_markers_into_line_breaks() {
+        assert_eq!(strip_pacing("Wait. [pause 800] Then go."), "Wait. Then go.");
+        assert_eq!(strip_pacing("First. [wait] Second."), "First.\nSecond.");
+        assert_eq!(strip_pacing("Night.\n---\nMorning."), "Night.\n\nMorning.");
+        assert_eq!(strip_pacing("One line.\nAnother -- and --- more."), "One line.\nAnother -- and --- more.");
+    }
+
+    #[test]
+    fn characters_reveal_one_at_a_time() {
+        assert_eq!(reveal_step("été", &RevealMode::Character), 1);
+        assert_eq!(reveal_step("", &RevealMode::Character), 0);
+    }
+
+    #[test]
+    fn words_reveal_with_the_spaces_before_them() {
+        assert_eq!(reveal_step("Café au lait", &RevealMode::Word), 4);
+        assert_eq!(reveal_step("  naïve soul", &RevealMode::Word), 7);
+        assert_eq!(reveal_step("fin", &RevealMode::Word), 3);
+    }
+
+    #[test]
+    fn sentences_reveal_up_to_their_punctuation() {
+        assert_eq!(reveal_step("Où es-tu? Ici.", &RevealMode::Sentence), 9);
+        assert_eq!(reveal_step("“Ça va.” Oui.", &RevealMode::Sentence), 8);
+        assert_eq!(reveal_step("\"Wait…” she said", &RevealMode::Sentence), 7);
+        assert_eq!(reveal_step("Line one\nLine two", &RevealMode::Sentence), 9);
+        assert_eq!(reveal_step("3.5 metres", &RevealMode::Sentence), 10);
+    }
+}
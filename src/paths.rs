@@ -0,0 +1,269 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use crate::game::fnv1a;
+use crate::errors::GameResult;
+
+/// Environment variable naming the config file to use.
+pub const CONFIG_ENV: &str = "RESTORATION_CONFIG";
+/// Environment variable naming the directory for saves and profiles.
+pub const DATA_DIR_ENV: &str = "RESTORATION_DATA_DIR";
+
+// Where the config lived before it moved to the platform config directory
+const LEGACY_CONFIG_FILE: &str = "restoration_config.json";
+const APP_DIR: &str = "restoration";
+// Left in a story's saves directory once its old files have been copied
+const LEGACY_MARKER: &str = ".legacy-copied";
+
+static CONFIG_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `path` as the config file for the rest of the process, as `--config` does.
+/// Only the first call has an effect.
+pub fn set_config_path(path: PathBuf) {
+    let _ = CONFIG_OVERRIDE.set(path);
+}
+
+/// The config file: `--config`, then `RESTORATION_CONFIG`, then `config.json` in
+/// the platform config directory (`~/.config/restoration` on Linux).
+pub fn config_path() -> PathBuf {
+    if let Some(path) = CONFIG_OVERRIDE.get() {
+        return path.clone();
+    }
+    if let Some(path) = std::env::var_os(CONFIG_ENV).filter(|path| !path.is_empty()) {
+        return PathBuf::from(path);
+    }
+    default_config_path()
+}
+
+fn default_config_path() -> PathBuf {
+    dirs_config_dir().map_or_else(|| PathBuf::from(LEGACY_CONFIG_FILE), |dir| dir.join(APP_DIR).join("config.json"))
+}
+
+/// Where saves and profiles are kept: `RESTORATION_DATA_DIR`, or the platform data
+/// directory (`~/.local/share/restoration` on Linux).
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return PathBuf::from(dir);
+    }
+    dirs_data_dir().map_or_else(|| PathBuf::from("."), |dir| dir.join(APP_DIR))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn dirs_config_dir() -> Option<PathBuf> {
+    dirs::config_dir()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn dirs_data_dir() -> Option<PathBuf> {
+    dirs::data_dir()
+}
+
+// The browser has no file system; web builds keep their data elsewhere
+#[cfg(target_arch = "wasm32")]
+fn dirs_config_dir() -> Option<PathBuf> {
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+fn dirs_data_dir() -> Option<PathBuf> {
+    None
+}
+
+// Saves and profiles are grouped by the story's file name without `.md`, followed
+// by a hash of where the story is so that two `story.md`s don't share them
+fn story_name(story_file: &str) -> String {
+    let path = Path::new(story_file);
+    let stem = path.file_stem().map_or_else(|| story_file.to_string(), |stem| stem.to_string_lossy().into_owned());
+    let location = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    format!("{}-{:08x}", stem, fnv1a(location.to_string_lossy().as_bytes()) as u32)
+}
+
+/// Directory holding every save slot of a story.
+pub fn saves_dir(story_file: &str) -> PathBuf {
+    data_dir().join("saves").join(story_name(story_file))
+}
+
+/// File holding the endings and achievements found in a story.
+pub fn profile_path(story_file: &str) -> PathBuf {
+    data_dir().join("profiles").join(format!("{}.json", story_name(story_file)))
+}
+
+/// Copy a `restoration_config.json` from the working directory, where older
+/// versions kept it, to the default config file when that doesn't exist yet.
+/// Returns a note describing the copy, if one was made.
+pub fn migrate_legacy_config() -> GameResult<Option<String>> {
+    let config_path = config_path();
+    let legacy = Path::new(LEGACY_CONFIG_FILE);
+    // A config chosen with --config or the environment is used as it is
+    if config_path != default_config_path() || config_path.exists() || !legacy.exists() || config_path == legacy {
+        return Ok(None);
+    }
+    copy_file(legacy, &config_path)?;
+    Ok(Some(format!("Copied {} to {}", legacy.display(), config_path.display())))
+}
+
+/// Copy saves and the profile that older versions kept next to the story file into
+/// the data directory. Files already in the data directory are left alone, and a
+/// marker records the copy so that saves deleted afterwards don't come back.
+/// Returns a note for each file copied.
+pub fn migrate_legacy_story_files(story_file: &str) -> GameResult<Vec<String>> {
+    let stem = story_file.strip_suffix(".md").unwrap_or(story_file);
+    let saves_dir = saves_dir(story_file);
+    let marker = saves_dir.join(LEGACY_MARKER);
+    if marker.exists() {
+        return Ok(Vec::new());
+    }
+    let mut moves = vec![
+        (PathBuf::from(format!("{}.save", stem)), saves_dir.join("quicksave.json")),
+        (PathBuf::from(format!("{}.profile.json", stem)), profile_path(story_file)),
+    ];
+    let legacy_slots = PathBuf::from(format!("{}.saves", stem));
+    if legacy_slots.is_dir() {
+        for entry in fs::read_dir(&legacy_slots)? {
+            let path = entry?.path();
+            if let Some(file_name) = path.file_name() {
+                moves.push((path.clone(), saves_dir.join(file_name)));
+            }
+        }
+    }
+
+    let mut notes = Vec::new();
+    let mut found_legacy = false;
+    for (legacy, target) in moves {
+        if legacy.is_file() {
+            found_legacy = true;
+            if !target.exists() {
+                copy_file(&legacy, &target)?;
+                notes.push(format!("Copied {} to {}", legacy.display(), target.display()));
+            }
+        }
+    }
+    if found_legacy {
+        fs::create_dir_all(&saves_dir)?;
+        fs::write(&marker, "")?;
+    }
+    Ok(notes)
+}
+
+// Tests that change the environment take this first, so they don't see each other's
+#[cfg(test)]
+pub(crate) fn lock_environment() -> std::sync::MutexGuard<'static, ()> {
+    static ENVIRONMENT: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    ENVIRONMENT.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// An empty directory for one test
+#[cfg(test)]
+pub(crate) fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("restoration-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("the temporary directory is writable");
+    dir
+}
+
+fn copy_file(from: &Path, to: &Path) -> GameResult<()> {
+    if let Some(dir) = to.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    fs::copy(from, to)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every path setting cleared, and the data directory moved to `data_dir`
+    fn data_in(data_dir: &Path) {
+        std::env::remove_var(CONFIG_ENV);
+        std::env::set_var(DATA_DIR_ENV, data_dir);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn linux_follows_xdg_directories() {
+        let _environment = lock_environment();
+        let dir = scratch_dir("paths-xdg");
+        std::env::remove_var(CONFIG_ENV);
+        std::env::remove_var(DATA_DIR_ENV);
+        std::env::set_var("XDG_CONFIG_HOME", dir.join("config"));
+        std::env::set_var("XDG_DATA_HOME", dir.join("data"));
+        let (config, data) = (config_path(), data_dir());
+        std::env::set_var(CONFIG_ENV, dir.join("chosen.json"));
+        std::env::set_var(DATA_DIR_ENV, dir.join("chosen"));
+        let (chosen_config, chosen_data) = (config_path(), data_dir());
+        for var in ["XDG_CONFIG_HOME", "XDG_DATA_HOME", CONFIG_ENV, DATA_DIR_ENV] {
+            std::env::remove_var(var);
+        }
+
+        assert_eq!(config, dir.join("config/restoration/config.json"));
+        assert_eq!(data, dir.join("data/restoration"));
+        assert_eq!(chosen_config, dir.join("chosen.json"));
+        assert_eq!(chosen_data, dir.join("chosen"));
+    }
+
+    #[test]
+    fn stories_with_the_same_name_keep_their_saves_apart() {
+        let _environment = lock_environment();
+        let dir = scratch_dir("paths-same-name");
+        data_in(&dir.join("data"));
+        for folder in ["a", "b"] {
+            fs::create_dir_all(dir.join(folder)).unwrap();
+            fs::write(dir.join(folder).join("story.md"), "# Story").unwrap();
+        }
+        let story = |folder: &str| dir.join(folder).join("story.md").to_string_lossy().into_owned();
+        let (a, b) = (saves_dir(&story("a")), saves_dir(&story("b")));
+        let a_again = saves_dir(&dir.join("a/../a/story.md").to_string_lossy());
+        let profile = profile_path(&story("a"));
+        std::env::remove_var(DATA_DIR_ENV);
+
+        assert_ne!(a, b);
+        assert_eq!(a, a_again);
+        assert!(a.file_name().unwrap().to_string_lossy().starts_with("story-"));
+        assert_eq!(profile.file_stem(), a.file_name());
+    }
+
+    #[test]
+    fn legacy_story_files_are_copied_once() {
+        let _environment = lock_environment();
+        let dir = scratch_dir("paths-legacy");
+        data_in(&dir.join("data"));
+        let story = dir.join("story.md").to_string_lossy().into_owned();
+        fs::write(dir.join("story.save"), "quick").unwrap();
+        fs::write(dir.join("story.profile.json"), "profile").unwrap();
+        fs::create_dir_all(dir.join("story.saves")).unwrap();
+        fs::write(dir.join("story.saves/chapter2.json"), "slot").unwrap();
+        let saves = saves_dir(&story);
+        fs::create_dir_all(&saves).unwrap();
+        fs::write(saves.join("chapter2.json"), "newer slot").unwrap();
+
+        let notes = migrate_legacy_story_files(&story).unwrap();
+        let quicksave = fs::read_to_string(saves.join("quicksave.json")).unwrap();
+        let profile = fs::read_to_string(profile_path(&story)).unwrap();
+        let slot = fs::read_to_string(saves.join("chapter2.json")).unwrap();
+        fs::remove_file(saves.join("quicksave.json")).unwrap();
+        let notes_again = migrate_legacy_story_files(&story).unwrap();
+        std::env::remove_var(DATA_DIR_ENV);
+
+        assert_eq!(notes.len(), 2, "{:?}", notes);
+        assert_eq!((quicksave.as_str(), profile.as_str(), slot.as_str()), ("quick", "profile", "newer slot"));
+        assert!(saves.join(LEGACY_MARKER).exists());
+        assert!(notes_again.is_empty());
+        assert!(!saves.join("quicksave.json").exists());
+        assert!(dir.join("story.save").exists());
+    }
+
+    #[test]
+    fn stories_without_legacy_files_get_no_marker() {
+        let _environment = lock_environment();
+        let dir = scratch_dir("paths-no-legacy");
+        data_in(&dir.join("data"));
+        let story = dir.join("story.md").to_string_lossy().into_owned();
+        let notes = migrate_legacy_story_files(&story).unwrap();
+        let marker = saves_dir(&story).join(LEGACY_MARKER);
+        std::env::remove_var(DATA_DIR_ENV);
+
+        assert!(notes.is_empty());
+        assert!(!marker.exists());
+    }
+}
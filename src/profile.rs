@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use crate::world::World;
+use crate::engine::GameEvent;
+use crate::paths;
+use crate::errors::{GameError, GameResult};
+
+/// Progress that outlives a single playthrough, kept apart from `GameState` saves.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerProfile {
+    #[serde(default)]
+    pub endings_found: BTreeSet<String>,
+    #[serde(default)]
+    pub achievements_unlocked: BTreeSet<String>,
+}
+
+impl PlayerProfile {
+    pub fn path_for_story(story_file: &str) -> String {
+        paths::profile_path(story_file).to_string_lossy().into_owned()
+    }
+
+    /// Load the profile, starting a fresh one if the file doesn't exist yet.
+    pub fn load_or_default(filename: &str) -> GameResult<Self> {
+        if !std::path::Path::new(filename).exists() {
+            return Ok(PlayerProfile::default());
+        }
+        let content = fs::read_to_string(filename)?;
+        serde_json::from_str(&content)
+            .map_err(|e| GameError::ValidationError(format!("Failed to parse profile: {}", e)))
+    }
+
+    pub fn save_to_file(&self, filename: &str) -> GameResult<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| GameError::ValidationError(format!("Failed to serialize profile: {}", e)))?;
+        if let Some(dir) = std::path::Path::new(filename).parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(filename, json)?;
+        Ok(())
+    }
+
+    /// Load the profile the web build keeps in `localStorage` for `story_name`,
+    /// starting a fresh one if there is none yet.
+    #[cfg(target_arch = "wasm32")]
+    pub fn load_from_local_storage(story_name: &str) -> GameResult<Self> {
+        use crate::saves::{local_storage, storage_error};
+        match local_storage()?.get_item(&local_storage_key(story_name)).map_err(storage_error)? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| GameError::ValidationError(format!("Failed to parse profile: {}", e))),
+            None => Ok(PlayerProfile::default()),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn save_to_local_storage(&self, story_name: &str) -> GameResult<()> {
+        use crate::saves::{local_storage, storage_error};
+        let json = serde_json::to_string(self)
+            .map_err(|e| GameError::ValidationError(format!("Failed to serialize profile: {}", e)))?;
+        local_storage()?.set_item(&local_storage_key(story_name), &json).map_err(storage_error)
+    }
+
+    /// Record endings from engine events; returns true if one was found for the first time.
+    pub fn record_endings(&mut self, events: &[GameEvent]) -> bool {
+        let mut found = false;
+        for event in events {
+            if let GameEvent::EndingReached { id, .. } = event {
+                found |= self.endings_found.insert(id.clone());
+            }
+        }
+        found
+    }
+
+    /// Record achievements from engine events; returns the titles unlocked for the first time.
+    pub fn record_achievements(&mut self, events: &[GameEvent]) -> Vec<String> {
+        let mut unlocked = Vec::new();
+        for event in events {
+            if let GameEvent::AchievementUnlocked { id, title } = event {
+                if self.achievements_unlocked.insert(id.clone()) {
+                    unlocked.push(title.clone());
+                }
+            }
+        }
+        unlocked
+    }
+
+    /// "Achievements: 2/5" followed by every declared achievement, locked or not.
+    pub fn achievements_list(&self, world: &World) -> Vec<String> {
+        let unlocked = world
+            .achievements
+            .iter()
+            .filter(|achievement| self.achievements_unlocked.contains(&achievement.id))
+            .count();
+
+        let mut lines = vec![format!("Achievements: {}/{}", unlocked, world.achievements.len())];
+        for achievement in &world.achievements {
+            let marker = if self.achievements_unlocked.contains(&achievement.id) { "🏆" } else { "🔒" };
+            lines.push(format!("  {} {}", marker, achievement.title));
+        }
+        lines
+    }
+
+    /// "Endings found: 3/7" followed by one line per declared ending, unfound ones hidden.
+    pub fn endings_gallery(&self, world: &World) -> Vec<String> {
+        let found = world
+            .endings
+            .iter()
+            .filter(|ending| self.endings_found.contains(&ending.id))
+            .count();
+
+        let mut lines = vec![format!("Endings found: {}/{}", found, world.endings.len())];
+        for ending in &world.endings {
+            if self.endings_found.contains(&ending.id) {
+                lines.push(format!("  ✓ {}", ending.title));
+            } else {
+                lines.push("  ? ???".to_string());
+            }
+        }
+        lines
+    }
+}
+
+// The entry holding a story's profile, beside its saves' `restoration:saves:<story>:<slot>`
+#[cfg(target_arch = "wasm32")]
+fn local_storage_key(story_name: &str) -> String {
+    format!("restoration:profile:{}", story_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::load_world_from_markdown_content;
+
+    fn ending(id: &str, title: &str) -> GameEvent {
+        GameEvent::EndingReached { id: id.to_string(), title: title.to_string() }
+    }
+
+    #[test]
+    fn endings_are_recorded_once_and_kept_across_loads() {
+        let path = paths::scratch_dir("profile-endings").join("profiles").join("story.json");
+        let filename = path.to_str().unwrap();
+        let mut profile = PlayerProfile::load_or_default(filename).unwrap();
+        assert!(profile.endings_found.is_empty());
+
+        assert!(profile.record_endings(&[GameEvent::Quit, ending("grove", "The Grove")]));
+        assert!(!profile.record_endings(&[ending("grove", "The Grove")]));
+        assert!(!profile.record_endings(&[GameEvent::Quit]));
+        profile.save_to_file(filename).unwrap();
+
+        let mut loaded = PlayerProfile::load_or_default(filename).unwrap();
+        assert_eq!(loaded.endings_found, ["grove".to_string()].into_iter().collect());
+        assert!(loaded.record_endings(&[ending("cabin", "The Cabin")]));
+    }
+
+    #[test]
+    fn gallery_hides_endings_not_found_yet() {
+        let world = load_world_from_markdown_content(
+            "# Endings\n\n@start home\n@ending grove \"The Grove\"\n@ending cabin \"The Cabin\"\n\n## @room home\nA cabin.\n\n### Walk out\n- end: grove\n\n### Stay in\n- end: cabin\n",
+        )
+        .unwrap();
+        let mut profile = PlayerProfile::default();
+        profile.record_endings(&[ending("cabin", "The Cabin")]);
+
+        assert_eq!(profile.endings_gallery(&world), ["Endings found: 1/2", "  ? ???", "  ✓ The Cabin"]);
+    }
+
+    #[test]
+    fn unreadable_profiles_are_reported() {
+        let path = paths::scratch_dir("profile-unreadable").join("story.json");
+        fs::write(&path, "not a profile").unwrap();
+
+        let error = PlayerProfile::load_or_default(path.to_str().unwrap()).unwrap_err().to_string();
+        assert!(error.contains("Failed to parse profile"), "{}", error);
+    }
+
+    fn achievement(id: &str, title: &str) -> GameEvent {
+        GameEvent::AchievementUnlocked { id: id.to_string(), title: title.to_string() }
+    }
+
+    #[test]
+    fn achievements_are_announced_once_and_kept_across_loads() {
+        let path = paths::scratch_dir("profile-achievements").join("story.json");
+        let filename = path.to_str().unwrap();
+        let mut profile = PlayerProfile::default();
+
+        let unlocked = profile.record_achievements(&[achievement("patient", "Baled all the hay"), GameEvent::Quit, achievement("patient", "Baled all the hay")]);
+        assert_eq!(unlocked, ["Baled all the hay"]);
+        assert!(profile.record_achievements(&[achievement("patient", "Baled all the hay")]).is_empty());
+        profile.save_to_file(filename).unwrap();
+
+        let mut loaded = PlayerProfile::load_or_default(filename).unwrap();
+        assert_eq!(loaded.achievements_unlocked, ["patient".to_string()].into_iter().collect());
+        assert!(loaded.endings_found.is_empty());
+        assert!(loaded.record_achievements(&[achievement("patient", "Baled all the hay")]).is_empty());
+    }
+
+    #[test]
+    fn achievement_list_shows_locked_ones_by_title() {
+        let world = load_world_from_markdown_content(
+            "# Chores\n\n@start barn\n@achievement patient \"Baled all the hay\"\n@achievement early \"Up at dawn\"\n\n## @room barn\nA barn.\n\n### Bale hay\n- achieve patient\n\n### Rise\n- achieve early\n",
+        )
+        .unwrap();
+        let mut profile = PlayerProfile::default();
+        profile.record_achievements(&[achievement("early", "Up at dawn")]);
+
+        assert_eq!(profile.achievements_list(&world), ["Achievements: 1/2", "  🔒 Baled all the hay", "  🏆 Up at dawn"]);
+    }
+}
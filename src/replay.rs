@@ -0,0 +1,298 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use serde::{Serialize, Deserialize};
+use crate::world::{ChoiceId, World};
+use crate::game::{GameState, get_available_choices};
+use crate::engine::{Engine, GameEvent};
+use crate::errors::{GameError, GameResult};
+
+/// One line of a recorded playthrough.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum RecordedStep {
+    /// How the run began: a new game from `seed`, or the loaded `state`.
+    Start {
+        seed: Option<u64>,
+        state: Option<GameState>,
+        state_hash: String,
+    },
+    Choice {
+        input: String,
+        choice: String,
+        /// Answers typed for `ask` prompts during the choice, in order.
+        #[serde(default)]
+        answers: Vec<String>,
+        state_hash: String,
+    },
+    Rewind {
+        input: String,
+        steps: usize,
+        state_hash: String,
+    },
+    Load {
+        input: String,
+        state: GameState,
+    },
+}
+
+impl RecordedStep {
+    /// The start of a run; a new game is stored as its seed so story changes show up.
+    pub fn start(world: &World, game_state: &GameState, seed: u64) -> Self {
+        let is_new_game = GameState::for_world(world, seed).state_hash() == game_state.state_hash();
+        RecordedStep::Start {
+            seed: is_new_game.then_some(seed),
+            state: (!is_new_game).then(|| game_state.clone()),
+            state_hash: game_state.state_hash(),
+        }
+    }
+
+    /// A choice and the answers given during it, taken from all of its `events`.
+    pub fn choice(input: &str, choice_id: &ChoiceId, events: &[GameEvent], game_state: &GameState) -> Self {
+        let answers = events
+            .iter()
+            .filter_map(|event| match event {
+                GameEvent::InputProvided { answer, .. } => Some(answer.clone()),
+                _ => None,
+            })
+            .collect();
+        RecordedStep::Choice {
+            input: input.to_string(),
+            choice: choice_id.0.clone(),
+            answers,
+            state_hash: game_state.state_hash(),
+        }
+    }
+
+    pub fn rewind(input: &str, steps: usize, game_state: &GameState) -> Self {
+        RecordedStep::Rewind {
+            input: input.to_string(),
+            steps,
+            state_hash: game_state.state_hash(),
+        }
+    }
+
+    pub fn load(input: &str, game_state: &GameState) -> Self {
+        RecordedStep::Load {
+            input: input.to_string(),
+            state: game_state.clone(),
+        }
+    }
+}
+
+/// Writes a playthrough as JSON lines for `restoration replay`.
+pub struct Recorder {
+    writer: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create(path: &str) -> GameResult<Self> {
+        Ok(Recorder { writer: BufWriter::new(File::create(path)?) })
+    }
+
+    pub fn record(&mut self, step: &RecordedStep) -> GameResult<()> {
+        let line = serde_json::to_string(step)
+            .map_err(|e| GameError::ValidationError(format!("Failed to serialize recording: {}", e)))?;
+        writeln!(self.writer, "{}", line)?;
+        // Flush every step so an interrupted game still leaves a usable recording
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+pub fn load_recording(path: &str) -> GameResult<Vec<RecordedStep>> {
+    let content = std::fs::read_to_string(path)?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| GameError::ValidationError(format!("Invalid recording line {}: {}", index + 1, e)))
+        })
+        .collect()
+}
+
+/// Where a replay stopped matching its recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    /// Position in the recording, counting the start as step 0.
+    pub step: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "step {}: {}", self.step, self.message)
+    }
+}
+
+/// Play a recording back against `world` without any UI. Returns the final state,
+/// or the first step whose choice is unavailable or whose resulting state differs.
+pub fn replay(world: &World, recording: &[RecordedStep]) -> Result<GameState, Divergence> {
+    let diverge = |step: usize, message: String| Divergence { step, message };
+
+    let mut steps = recording.iter().enumerate();
+    let mut game_state = match steps.next() {
+        Some((_, RecordedStep::Start { seed, state, state_hash })) => {
+            let game_state = match (state, seed) {
+                (Some(state), _) => state.clone(),
+                (None, Some(seed)) => GameState::for_world(world, *seed),
+                (None, None) => return Err(diverge(0, "the recording has neither a seed nor a starting state".to_string())),
+            };
+            if &game_state.state_hash() != state_hash {
+                return Err(diverge(0, "the starting state differs from the recording".to_string()));
+            }
+            game_state
+        }
+        _ => return Err(diverge(0, "the recording does not begin with a start step".to_string())),
+    };
+
+    for (index, step) in steps {
+        match step {
+            RecordedStep::Start { .. } => {
+                return Err(diverge(index, "unexpected second start step".to_string()));
+            }
+            RecordedStep::Choice { input, choice, answers, state_hash } => {
+                let available = get_available_choices(world, &game_state).map_err(|e| diverge(index, e.to_string()))?;
+                if !available.iter().any(|available_choice| &available_choice.id.0 == choice) {
+                    return Err(diverge(index, format!(
+                        "choice '{}' (input '{}') is not available in room '{}'",
+                        choice, input, game_state.current_room_id
+                    )));
+                }
+
+                let mut events = Engine::choose(world, &mut game_state, ChoiceId(choice.clone()))
+                    .map_err(|e| diverge(index, e.to_string()))?;
+                let mut answers = answers.iter();
+                while events.iter().any(|event| matches!(event, GameEvent::InputRequested { .. })) {
+                    let answer = answers
+                        .next()
+                        .ok_or_else(|| diverge(index, format!("'{}' asks for more text input than was recorded", input)))?;
+                    events = Engine::provide_input(world, &mut game_state, answer).map_err(|e| diverge(index, e.to_string()))?;
+                }
+
+                if &game_state.state_hash() != state_hash {
+                    return Err(diverge(index, format!("the state after '{}' differs from the recording", input)));
+                }
+            }
+            RecordedStep::Rewind { input, steps, state_hash } => {
+                Engine::rewind(world, &mut game_state, *steps).map_err(|e| diverge(index, e.to_string()))?;
+                if &game_state.state_hash() != state_hash {
+                    return Err(diverge(index, format!("the state after '{}' differs from the recording", input)));
+                }
+            }
+            RecordedStep::Load { state, .. } => {
+                game_state = state.clone();
+            }
+        }
+    }
+
+    Ok(game_state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::load_world_from_markdown_content;
+
+    const STORY: &str = "# Replay\n\n@start cellar\n\n## @room cellar\nA cellar.\n\n### Look around\n- flag+ has_key\n\n### Go up\n- @hallway\n\n## @room hallway\nA hallway.\n\n### Go down\n- @cellar\n";
+
+    fn world(content: &str) -> World {
+        load_world_from_markdown_content(content).expect("the test story loads")
+    }
+
+    // Play `choices` from a new game, recording every step
+    fn record(world: &World, choices: &[&str]) -> (Vec<RecordedStep>, GameState) {
+        let mut game_state = GameState::for_world(world, 3);
+        let mut recording = vec![RecordedStep::start(world, &game_state, 3)];
+        for choice in choices {
+            let choice_id = ChoiceId(choice.to_string());
+            let events = Engine::choose(world, &mut game_state, choice_id.clone()).unwrap();
+            recording.push(RecordedStep::choice(choice, &choice_id, &events, &game_state));
+        }
+        (recording, game_state)
+    }
+
+    #[test]
+    fn new_games_start_from_their_seed_and_loaded_ones_from_their_state() {
+        let world = world(STORY);
+        let mut game_state = GameState::for_world(&world, 3);
+        assert!(matches!(RecordedStep::start(&world, &game_state, 3), RecordedStep::Start { seed: Some(3), state: None, .. }));
+
+        game_state.current_room_id = "hallway".to_string();
+        let start = RecordedStep::start(&world, &game_state, 3);
+        assert!(matches!(start, RecordedStep::Start { seed: None, state: Some(_), .. }));
+        assert_eq!(replay(&world, &[start]).unwrap().current_room_id, "hallway");
+    }
+
+    #[test]
+    fn choices_rewinds_and_loads_replay() {
+        let world = world(STORY);
+        let (mut recording, mut game_state) = record(&world, &["cellar_choice_0", "cellar_choice_1"]);
+        Engine::rewind(&world, &mut game_state, 1).unwrap();
+        recording.push(RecordedStep::rewind("undo", 1, &game_state));
+        let loaded = GameState::for_world(&world, 5);
+        recording.push(RecordedStep::load("load", &loaded));
+
+        assert_eq!(replay(&world, &recording[..3]).unwrap().current_room_id, "hallway");
+        assert_eq!(replay(&world, &recording[..4]).unwrap().state_hash(), game_state.state_hash());
+        assert_eq!(replay(&world, &recording).unwrap().state_hash(), loaded.state_hash());
+    }
+
+    #[test]
+    fn unavailable_choices_diverge() {
+        let world = world(STORY);
+        let (mut recording, _) = record(&world, &["cellar_choice_1"]);
+        recording.push(RecordedStep::Choice {
+            input: "1".to_string(),
+            choice: "cellar_choice_0".to_string(),
+            answers: Vec::new(),
+            state_hash: String::new(),
+        });
+
+        let divergence = replay(&world, &recording).unwrap_err();
+        assert_eq!(divergence.step, 2);
+        assert_eq!(divergence.to_string(), "step 2: choice 'cellar_choice_0' (input '1') is not available in room 'hallway'");
+    }
+
+    #[test]
+    fn changed_stories_diverge_where_the_state_differs() {
+        let (recording, _) = record(&world(STORY), &["cellar_choice_1", "hallway_choice_0", "cellar_choice_0"]);
+        let changed = world(&STORY.replace("- flag+ has_key", "- flag+ has_lamp"));
+
+        let divergence = replay(&changed, &recording).unwrap_err();
+        assert_eq!(divergence, Divergence { step: 3, message: "the state after 'cellar_choice_0' differs from the recording".to_string() });
+    }
+
+    #[test]
+    fn recordings_need_one_start_step_first() {
+        let world = world(STORY);
+        let (recording, _) = record(&world, &["cellar_choice_0"]);
+
+        assert!(replay(&world, &recording[1..]).unwrap_err().message.contains("does not begin with a start step"));
+        let twice = [recording[0].clone(), recording[0].clone()];
+        assert_eq!(replay(&world, &twice).unwrap_err().step, 1);
+        let seedless = RecordedStep::Start { seed: None, state: None, state_hash: String::new() };
+        assert!(replay(&world, &[seedless]).unwrap_err().message.contains("neither a seed nor a starting state"));
+    }
+
+    #[test]
+    fn recorder_writes_lines_that_load_back() {
+        let world = world(STORY);
+        let (recording, game_state) = record(&world, &["cellar_choice_0", "cellar_choice_1"]);
+        let path = crate::paths::scratch_dir("replay-recorder").join("run.jsonl");
+        let mut recorder = Recorder::create(path.to_str().unwrap()).unwrap();
+        for step in &recording {
+            recorder.record(step).unwrap();
+        }
+
+        let loaded = load_recording(path.to_str().unwrap()).unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(replay(&world, &loaded).unwrap().state_hash(), game_state.state_hash());
+
+        std::fs::write(&path, "{\"step\":\"start\",\"seed\":1,\"state\":null,\"state_hash\":\"x\"}\n\nnot json\n").unwrap();
+        let error = load_recording(path.to_str().unwrap()).unwrap_err().to_string();
+        assert!(error.contains("Invalid recording line 3"), "{}", error);
+    }
+}
@@ -0,0 +1,882 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+use serde::{Serialize, Deserialize};
+use crate::world::{FlagId, Variable, World};
+use crate::game::{fnv1a, GameState};
+use crate::engine::Engine;
+use crate::pacing::strip_pacing;
+use crate::paths;
+use crate::errors::{GameError, GameResult};
+
+/// Version of the save layout written by this engine. Saves without one predate
+/// versioning and hold a bare `GameState`.
+pub const SAVE_FORMAT_VERSION: u32 = 1;
+
+/// The slot written automatically as the player moves, when `auto_save` is on.
+pub const AUTO_SAVE: &str = "autosave";
+
+/// The slot used by a plain `save` or `load`.
+pub const QUICK_SAVE: &str = "quicksave";
+
+/// Version of the save code layout written by `export_code`. Version 1 codes
+/// held the state as plain JSON; from version 2 it is deflated.
+pub const SAVE_CODE_VERSION: u8 = 2;
+
+// Largest game state a save code may inflate to, so a forged code can't exhaust memory
+const MAX_CODE_STATE_BYTES: usize = 1 << 20;
+
+// Longest description excerpt shown in slot lists
+const EXCERPT_LENGTH: usize = 80;
+
+/// What a slot list shows about a save without loading the game.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveMetadata {
+    /// Seconds since the Unix epoch.
+    pub saved_at: u64,
+    pub story_title: String,
+    pub room: String,
+    pub playtime_secs: u64,
+    /// The start of what the player was reading when they saved.
+    pub excerpt: String,
+}
+
+impl SaveMetadata {
+    pub fn new(world: &World, game_state: &GameState, playtime: Duration) -> Self {
+        let description = Engine::describe(world, game_state).unwrap_or_default();
+        SaveMetadata {
+            saved_at: now_millis() / 1000,
+            story_title: world.title.clone().unwrap_or_default(),
+            room: game_state.current_room_id.clone(),
+            playtime_secs: playtime.as_secs(),
+            excerpt: excerpt(&strip_pacing(&description)),
+        }
+    }
+
+    /// One line for slot lists, e.g. "hallway · played 12m · saved 5 minutes ago".
+    pub fn summary(&self) -> String {
+        format!(
+            "{} · played {} · saved {}",
+            self.room,
+            format_playtime(self.playtime_secs),
+            format_age(self.saved_at)
+        )
+    }
+}
+
+/// A save file: the game state, what wrote it, and metadata for slot lists.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveFile {
+    #[serde(default)]
+    pub format_version: u32,
+    /// Version of the engine that wrote the save.
+    #[serde(default)]
+    pub engine_version: String,
+    /// `World::structure_hash` of the story when the save was made.
+    #[serde(default)]
+    pub story_hash: String,
+    /// Missing for saves written before slots existed.
+    #[serde(default)]
+    pub metadata: Option<SaveMetadata>,
+    pub state: GameState,
+}
+
+impl SaveFile {
+    pub fn new(world: &World, game_state: &GameState) -> Self {
+        SaveFile {
+            format_version: SAVE_FORMAT_VERSION,
+            engine_version: env!("CARGO_PKG_VERSION").to_string(),
+            story_hash: world.structure_hash(),
+            metadata: None,
+            state: game_state.clone(),
+        }
+    }
+
+    pub fn to_json(&self) -> GameResult<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| GameError::ValidationError(format!("Failed to serialize save data: {}", e)))
+    }
+
+    /// Read a save, including the bare game state written by older versions.
+    pub fn from_json(json: &str) -> GameResult<Self> {
+        let save = match serde_json::from_str::<SaveFile>(json) {
+            Ok(save) => save,
+            Err(_) => {
+                let state = serde_json::from_str::<GameState>(json)
+                    .map_err(|e| GameError::ValidationError(format!("Failed to deserialize save data: {}", e)))?;
+                SaveFile {
+                    format_version: 0,
+                    engine_version: String::new(),
+                    story_hash: String::new(),
+                    metadata: None,
+                    state,
+                }
+            }
+        };
+        if save.format_version > SAVE_FORMAT_VERSION {
+            return Err(GameError::ValidationError(format!(
+                "This save was made by a newer version of the game ({}); please upgrade to load it",
+                save.engine_version
+            )));
+        }
+        Ok(save)
+    }
+
+    /// Bring the save in line with `world`, which may have changed since the save
+    /// was made: follow room renames and fix anything the story no longer has.
+    /// Returns a note for each change, or an error if the player's room is gone.
+    pub fn migrate(&mut self, world: &World) -> GameResult<Vec<String>> {
+        let mut notes = Vec::new();
+
+        // Checked even when the hash matches, since it doesn't cover flags and
+        // counters the story only uses without declaring
+        let (used_flags, used_counters) = world.variable_uses();
+        let knows_flag = |flag: &FlagId| used_flags.contains(&flag.0) || world.declares_flag(&flag.0);
+        let knows_counter = |counter: &String| used_counters.contains(counter) || world.declares_counter(counter);
+        let mut unknown_flags: Vec<&FlagId> = self.state.flags.iter().filter(|flag| !knows_flag(flag)).collect();
+        unknown_flags.sort_by(|a, b| a.0.cmp(&b.0));
+        for flag in unknown_flags {
+            notes.push(format!("Flag '{}' is no longer in the story, so it was dropped.", flag.0));
+        }
+        let mut unknown_counters: Vec<&String> = self.state.counters.keys().filter(|counter| !knows_counter(counter)).collect();
+        unknown_counters.sort();
+        for counter in unknown_counters {
+            notes.push(format!("Counter '{}' is no longer in the story, so it was dropped.", counter));
+        }
+        let forget_unknown = |state: &mut GameState| {
+            state.flags.retain(|flag| knows_flag(flag));
+            state.counters.retain(|counter, _| knows_counter(counter));
+        };
+        self.state.history.iter_mut().for_each(forget_unknown);
+        forget_unknown(&mut self.state);
+
+        if self.story_hash == world.structure_hash() {
+            return Ok(notes);
+        }
+
+        let room_id = world.resolve_room(&self.state.current_room_id).to_string();
+        if !world.rooms.contains_key(&room_id) {
+            return Err(GameError::ValidationError(format!(
+                "This save is in room '{0}', which the story no longer has; if it was renamed, add `@renamed {0} -> new_id` to the story",
+                self.state.current_room_id
+            )));
+        }
+        if room_id != self.state.current_room_id {
+            notes.push(format!("Room '{}' is now '{}'.", self.state.current_room_id, room_id));
+            self.state.current_room_id = room_id;
+        }
+
+        if let Some(node_id) = self.state.dialogue.take_if(|node_id| !world.dialogues.contains_key(node_id.as_str())) {
+            notes.push(format!("The conversation '{}' is no longer in the story, so it was left.", node_id));
+        }
+
+        for variable in &world.variables {
+            if let Variable::Counter { name, initial, range } = variable {
+                let value = match self.state.counters.get(name) {
+                    Some(value) => *value,
+                    None => {
+                        // Declared since the save was made
+                        self.state.counters.insert(name.clone(), *initial);
+                        notes.push(format!("New counter '{}' starts at {}.", name, initial));
+                        continue;
+                    }
+                };
+                if let Some((min, max)) = range {
+                    let clamped = value.clamp(*min, *max);
+                    if clamped != value {
+                        self.state.counters.insert(name.clone(), clamped);
+                        notes.push(format!("Counter '{}' was {}, outside {}..{}; it is now {}.", name, value, min, max, clamped));
+                    }
+                }
+            }
+        }
+
+        // Undo can't go back to rooms that no longer exist
+        let mut history = std::mem::take(&mut self.state.history);
+        for snapshot in &mut history {
+            snapshot.current_room_id = world.resolve_room(&snapshot.current_room_id).to_string();
+        }
+        if let Some(last_missing) = history.iter().rposition(|snapshot| !world.rooms.contains_key(&snapshot.current_room_id)) {
+            history.drain(..=last_missing);
+            notes.push("Undo history from rooms the story no longer has was dropped.".to_string());
+        }
+        self.state.history = history;
+
+        self.story_hash = world.structure_hash();
+        Ok(notes)
+    }
+
+    pub fn playtime_secs(&self) -> u64 {
+        self.metadata.as_ref().map_or(0, |metadata| metadata.playtime_secs)
+    }
+}
+
+/// A slot as listed by `SaveStore::list`.
+#[derive(Debug, Clone)]
+pub struct SlotInfo {
+    pub name: String,
+    pub metadata: Option<SaveMetadata>,
+}
+
+impl SlotInfo {
+    pub fn label(&self) -> String {
+        match &self.metadata {
+            Some(metadata) => format!("{} — {}", self.name, metadata.summary()),
+            None => self.name.clone(),
+        }
+    }
+
+    /// The label followed by the excerpt of where the save was made.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![self.label()];
+        if let Some(metadata) = self.metadata.as_ref().filter(|metadata| !metadata.excerpt.is_empty()) {
+            lines.push(format!("    \"{}\"", metadata.excerpt));
+        }
+        lines
+    }
+}
+
+/// The save slots of one story, one JSON file each in the story's directory under
+/// `paths::saves_dir`, or one `localStorage` entry each in the browser.
+pub struct SaveStore {
+    storage: SlotStorage,
+}
+
+enum SlotStorage {
+    Directory(PathBuf),
+    // Entries are keyed "<prefix><slot>" and hold the same JSON as save files
+    #[cfg(target_arch = "wasm32")]
+    LocalStorage(String),
+}
+
+impl SaveStore {
+    pub fn for_story(story_file: &str) -> Self {
+        SaveStore { storage: SlotStorage::Directory(paths::saves_dir(story_file)) }
+    }
+
+    /// Slots kept in the browser's `localStorage`, grouped by `story_name`.
+    #[cfg(target_arch = "wasm32")]
+    pub fn in_local_storage(story_name: &str) -> Self {
+        SaveStore { storage: SlotStorage::LocalStorage(format!("restoration:saves:{}:", story_name)) }
+    }
+
+    fn check_name(slot: &str) -> GameResult<()> {
+        let valid = !slot.is_empty() && slot.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(GameError::ValidationError(format!(
+                "Invalid save name '{}': use letters, numbers, '-' and '_'",
+                slot
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn exists(&self, slot: &str) -> bool {
+        self.read(slot).is_ok_and(|json| json.is_some())
+    }
+
+    pub fn save(&self, slot: &str, world: &World, game_state: &GameState, playtime: Duration) -> GameResult<()> {
+        let save = SaveFile {
+            metadata: Some(SaveMetadata::new(world, game_state, playtime)),
+            ..SaveFile::new(world, game_state)
+        };
+        self.write(slot, &save.to_json()?)
+    }
+
+    /// Update the autosave after a choice made in `previous_room`: when the room
+    /// changed, or after every choice if `every_choice` is set. A finished story
+    /// removes the autosave so there is nothing left to continue.
+    pub fn auto_save(&self, world: &World, game_state: &GameState, previous_room: &str, every_choice: bool, playtime: Duration) -> GameResult<()> {
+        if game_state.has_quit {
+            if self.exists(AUTO_SAVE) {
+                self.delete(AUTO_SAVE)?;
+            }
+            return Ok(());
+        }
+        if every_choice || game_state.current_room_id != previous_room {
+            self.save(AUTO_SAVE, world, game_state, playtime)?;
+        }
+        Ok(())
+    }
+
+    /// Load a slot and migrate it to `world`; see `SaveFile::migrate`.
+    pub fn restore(&self, slot: &str, world: &World) -> GameResult<(SaveFile, Vec<String>)> {
+        let mut save = self.load(slot)?;
+        let notes = save.migrate(world)?;
+        Ok((save, notes))
+    }
+
+    pub fn load(&self, slot: &str) -> GameResult<SaveFile> {
+        match self.read(slot)? {
+            Some(json) => SaveFile::from_json(&json),
+            None => Err(GameError::ValidationError(format!("No save named '{}'", slot))),
+        }
+    }
+
+    pub fn delete(&self, slot: &str) -> GameResult<()> {
+        if !self.exists(slot) {
+            return Err(GameError::ValidationError(format!("No save named '{}'", slot)));
+        }
+        match &self.storage {
+            SlotStorage::Directory(dir) => fs::remove_file(dir.join(format!("{}.json", slot)))?,
+            #[cfg(target_arch = "wasm32")]
+            SlotStorage::LocalStorage(prefix) => {
+                local_storage()?.remove_item(&format!("{}{}", prefix, slot)).map_err(storage_error)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Every slot with its metadata, most recently saved first.
+    pub fn list(&self) -> GameResult<Vec<SlotInfo>> {
+        // Unreadable saves are still listed so they can be deleted
+        let mut slots: Vec<SlotInfo> = self
+            .names()?
+            .into_iter()
+            .map(|name| {
+                let metadata = self
+                    .read(&name)
+                    .ok()
+                    .flatten()
+                    .and_then(|json| SaveFile::from_json(&json).ok())
+                    .and_then(|save| save.metadata);
+                SlotInfo { name, metadata }
+            })
+            .collect();
+        slots.sort_by_key(|slot| std::cmp::Reverse(slot.metadata.as_ref().map_or(0, |metadata| metadata.saved_at)));
+        Ok(slots)
+    }
+
+    fn read(&self, slot: &str) -> GameResult<Option<String>> {
+        SaveStore::check_name(slot)?;
+        match &self.storage {
+            SlotStorage::Directory(dir) => {
+                let path = dir.join(format!("{}.json", slot));
+                if !path.exists() {
+                    return Ok(None);
+                }
+                Ok(Some(fs::read_to_string(path)?))
+            }
+            #[cfg(target_arch = "wasm32")]
+            SlotStorage::LocalStorage(prefix) => {
+                local_storage()?.get_item(&format!("{}{}", prefix, slot)).map_err(storage_error)
+            }
+        }
+    }
+
+    fn write(&self, slot: &str, json: &str) -> GameResult<()> {
+        SaveStore::check_name(slot)?;
+        match &self.storage {
+            SlotStorage::Directory(dir) => {
+                fs::create_dir_all(dir)?;
+                write_atomically(&dir.join(format!("{}.json", slot)), json)
+            }
+            // A single setItem either stores the whole value or fails
+            #[cfg(target_arch = "wasm32")]
+            SlotStorage::LocalStorage(prefix) => {
+                local_storage()?.set_item(&format!("{}{}", prefix, slot), json).map_err(storage_error)
+            }
+        }
+    }
+
+    fn names(&self) -> GameResult<Vec<String>> {
+        let mut names = Vec::new();
+        match &self.storage {
+            SlotStorage::Directory(dir) => {
+                if dir.is_dir() {
+                    for entry in fs::read_dir(dir)? {
+                        let path = entry?.path();
+                        if path.extension().is_some_and(|extension| extension == "json") {
+                            if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                                names.push(name.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+            #[cfg(target_arch = "wasm32")]
+            SlotStorage::LocalStorage(prefix) => {
+                let storage = local_storage()?;
+                for index in 0..storage.length().map_err(storage_error)? {
+                    if let Some(key) = storage.key(index).map_err(storage_error)? {
+                        if let Some(name) = key.strip_prefix(prefix.as_str()) {
+                            names.push(name.to_string());
+                        }
+                    }
+                }
+            }
+        }
+        Ok(names)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn local_storage() -> GameResult<web_sys::Storage> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| GameError::IoError(std::io::Error::other("localStorage is not available in this browser")))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn storage_error(error: wasm_bindgen::JsValue) -> GameError {
+    GameError::IoError(std::io::Error::other(format!("localStorage: {:?}", error)))
+}
+
+// Write through a temporary file so a crash mid-write can't leave a half-written save
+fn write_atomically(path: &Path, contents: &str) -> GameResult<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    fs::write(&temp_path, contents)?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// A save as a short string the player can copy between machines and builds: the
+/// deflated game state without undo history, tagged with the story and a checksum,
+/// in URL-safe base64.
+pub fn export_code(world: &World, game_state: &GameState) -> GameResult<String> {
+    let state = GameState { history: Vec::new(), ..game_state.clone() };
+    let json = serde_json::to_vec(&state)
+        .map_err(|e| GameError::ValidationError(format!("Failed to serialize save data: {}", e)))?;
+    let story_hash = u64::from_str_radix(&world.structure_hash(), 16).unwrap_or(0);
+
+    let mut bytes = vec![SAVE_CODE_VERSION];
+    bytes.extend_from_slice(&title_fingerprint(world).to_be_bytes());
+    bytes.extend_from_slice(&story_hash.to_be_bytes());
+    bytes.extend_from_slice(&compress_to_vec(&json, 10));
+    let checksum = fnv1a(&bytes) as u32;
+    bytes.extend_from_slice(&checksum.to_be_bytes());
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Read a code made by `export_code` and migrate it to `world` like a save file.
+/// Codes that were mistyped, cut short or made for another story are rejected.
+pub fn import_code(world: &World, code: &str) -> GameResult<(GameState, Vec<String>)> {
+    let damaged = |reason: &str| GameError::ValidationError(format!("This save code is damaged: {}. Check that it was copied completely", reason));
+
+    // Codes may be pasted with line breaks or spaces in them
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.is_empty() {
+        return Err(GameError::ValidationError("No save code given".to_string()));
+    }
+    let bytes = URL_SAFE_NO_PAD.decode(code.as_bytes()).map_err(|_| damaged("it isn't valid base64"))?;
+    // Version, title, story hash and checksum around the state
+    if bytes.len() < 1 + 4 + 8 + 4 {
+        return Err(damaged("it is too short"));
+    }
+    let (body, checksum) = bytes.split_at(bytes.len() - 4);
+    if (fnv1a(body) as u32).to_be_bytes() != checksum {
+        return Err(damaged("its checksum doesn't match"));
+    }
+
+    let version = body[0];
+    if version > SAVE_CODE_VERSION {
+        return Err(GameError::ValidationError(
+            "This save code was made by a newer version of the game; please upgrade to load it".to_string(),
+        ));
+    }
+    if body[1..5] != title_fingerprint(world).to_be_bytes() {
+        return Err(GameError::ValidationError(match &world.title {
+            Some(title) => format!("This save code is for a different story, not '{}'", title),
+            None => "This save code is for a different story".to_string(),
+        }));
+    }
+    let story_hash = u64::from_be_bytes(body[5..13].try_into().unwrap_or_default());
+    let json = match version {
+        1 => body[13..].to_vec(),
+        _ => decompress_to_vec_with_limit(&body[13..], MAX_CODE_STATE_BYTES)
+            .map_err(|_| damaged("its game state can't be unpacked"))?,
+    };
+    let state = serde_json::from_slice::<GameState>(&json).map_err(|_| damaged("its game state can't be read"))?;
+
+    let mut save = SaveFile {
+        story_hash: format!("{:016x}", story_hash),
+        ..SaveFile::new(world, &state)
+    };
+    let notes = save.migrate(world)?;
+    Ok((save.state, notes))
+}
+
+// Tells stories apart in save codes; edits within a story keep it the same
+fn title_fingerprint(world: &World) -> u32 {
+    fnv1a(world.title.as_deref().unwrap_or_default().as_bytes()) as u32
+}
+
+/// Time spent playing, carried over from the save the game was loaded from.
+pub struct PlayClock {
+    earlier: Duration,
+    started_at: u64,
+}
+
+impl PlayClock {
+    pub fn start() -> Self {
+        PlayClock::resume(0)
+    }
+
+    pub fn resume(earlier_secs: u64) -> Self {
+        PlayClock { earlier: Duration::from_secs(earlier_secs), started_at: now_millis() }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.earlier + Duration::from_millis(now_millis().saturating_sub(self.started_at))
+    }
+}
+
+fn excerpt(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= EXCERPT_LENGTH {
+        return text;
+    }
+    let cut: String = text.chars().take(EXCERPT_LENGTH).collect();
+    // Break at a word boundary when there is one
+    let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(words, _)| words);
+    format!("{}…", cut.trim_end_matches(|c: char| c.is_ascii_punctuation()))
+}
+
+pub fn format_playtime(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// How long ago a save was made, e.g. "5 minutes ago".
+pub fn format_age(saved_at: u64) -> String {
+    let now = now_millis() / 1000;
+    let age = now.saturating_sub(saved_at);
+    let (count, unit) = match age {
+        0..=59 => return "just now".to_string(),
+        60..=3599 => (age / 60, "minute"),
+        3600..=86_399 => (age / 3600, "hour"),
+        _ => (age / 86_400, "day"),
+    };
+    format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" })
+}
+
+// Milliseconds since the Unix epoch; std's clocks panic in the browser
+#[cfg(not(target_arch = "wasm32"))]
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_millis() as u64).unwrap_or(0)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now_millis() -> u64 {
+    js_sys::Date::now() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::load_world_from_markdown_content;
+
+    fn world(title: &str) -> World {
+        load_world_from_markdown_content(&format!(
+            "# {}\n\n@start cellar\n\n## @room cellar\nA dusty cellar.\n\n### Look around\n- flag+ has_key\n",
+            title
+        ))
+        .expect("the test story loads")
+    }
+
+    fn played(world: &World) -> GameState {
+        let mut game_state = GameState::for_world(world, 7);
+        Engine::choose(world, &mut game_state, crate::world::ChoiceId("cellar_choice_0".to_string())).unwrap();
+        game_state
+    }
+
+    // Re-encode a code after changing its bytes, with a checksum that matches again
+    fn resealed(code: &str, change: impl FnOnce(&mut Vec<u8>)) -> String {
+        let mut bytes = URL_SAFE_NO_PAD.decode(code).unwrap();
+        bytes.truncate(bytes.len() - 4);
+        change(&mut bytes);
+        let checksum = fnv1a(&bytes) as u32;
+        bytes.extend_from_slice(&checksum.to_be_bytes());
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    #[test]
+    fn codes_round_trip_without_history() {
+        let world = world("The Cellar");
+        let game_state = played(&world);
+        let code = export_code(&world, &game_state).unwrap();
+
+        let (imported, notes) = import_code(&world, &code).unwrap();
+        assert!(notes.is_empty());
+        assert!(imported.history.is_empty());
+        assert_eq!(imported.state_hash(), game_state.state_hash());
+
+        // Line breaks from copying a long code are ignored
+        let wrapped: String = code.chars().enumerate().flat_map(|(i, c)| (i % 60 == 59).then_some('\n').into_iter().chain([c])).collect();
+        assert!(import_code(&world, &wrapped).is_ok());
+    }
+
+    // The same code in the version 1 layout, with the state as plain JSON
+    fn version_one(code: &str, game_state: &GameState) -> String {
+        let json = serde_json::to_vec(&GameState { history: Vec::new(), ..game_state.clone() }).unwrap();
+        resealed(code, |bytes| {
+            bytes[0] = 1;
+            bytes.truncate(13);
+            bytes.extend_from_slice(&json);
+        })
+    }
+
+    #[test]
+    fn codes_are_shorter_than_plain_json_ones() {
+        let world = world("The Cellar");
+        let game_state = played(&world);
+        let code = export_code(&world, &game_state).unwrap();
+        assert!(code.len() < version_one(&code, &game_state).len());
+    }
+
+    #[test]
+    fn codes_with_a_wrong_checksum_are_rejected() {
+        let world = world("The Cellar");
+        let code = export_code(&world, &played(&world)).unwrap();
+
+        let middle = code.len() / 2;
+        let replacement = if &code[middle..=middle] == "A" { "B" } else { "A" };
+        let mistyped = format!("{}{}{}", &code[..middle], replacement, &code[middle + 1..]);
+        let error = import_code(&world, &mistyped).unwrap_err().to_string();
+        assert!(error.contains("checksum doesn't match"), "{}", error);
+
+        let error = import_code(&world, &code[..code.len() - 6]).unwrap_err().to_string();
+        assert!(error.contains("damaged"), "{}", error);
+    }
+
+    #[test]
+    fn codes_for_another_story_are_rejected() {
+        let world = world("The Cellar");
+        let code = export_code(&world, &played(&world)).unwrap();
+
+        let error = import_code(&self::world("The Attic"), &code).unwrap_err().to_string();
+        assert!(error.contains("for a different story, not 'The Attic'"), "{}", error);
+    }
+
+    #[test]
+    fn codes_from_a_newer_version_are_rejected() {
+        let world = world("The Cellar");
+        let code = export_code(&world, &played(&world)).unwrap();
+
+        let newer = resealed(&code, |bytes| bytes[0] = SAVE_CODE_VERSION + 1);
+        let error = import_code(&world, &newer).unwrap_err().to_string();
+        assert!(error.contains("newer version of the game"), "{}", error);
+    }
+
+    #[test]
+    fn version_one_codes_still_import() {
+        let world = world("The Cellar");
+        let game_state = played(&world);
+        let code = export_code(&world, &game_state).unwrap();
+
+        let (imported, _) = import_code(&world, &version_one(&code, &game_state)).unwrap();
+        assert_eq!(imported.state_hash(), game_state.state_hash());
+    }
+
+    fn saved_in(world: &World, room_id: &str, change: impl FnOnce(&mut GameState)) -> SaveFile {
+        let mut game_state = GameState::for_world(world, 7);
+        game_state.current_room_id = room_id.to_string();
+        change(&mut game_state);
+        SaveFile::new(world, &game_state)
+    }
+
+    const TWO_ROOMS: &str = "# Two Rooms\n\n@start cellar\n@var courage 0..5 = 1\n\n## @room cellar\nA cellar.\n\n### Look around\n- flag+ has_key\n- count+ courage\n\n### Go up\n- @hallway\n\n## @room hallway\nA hallway.\n\n### Go down\n- @cellar\n";
+
+    #[test]
+    fn migrate_follows_renamed_rooms() {
+        let mut save = saved_in(&load_world_from_markdown_content(TWO_ROOMS).unwrap(), "cellar", |_| {});
+        let renamed = load_world_from_markdown_content(
+            &TWO_ROOMS.replace("@room cellar", "@room basement").replace("- @cellar", "- @basement").replace("@start cellar", "@start basement\n@renamed cellar -> basement"),
+        )
+        .unwrap();
+
+        let notes = save.migrate(&renamed).unwrap();
+        assert_eq!(save.state.current_room_id, "basement");
+        assert_eq!(notes, ["Room 'cellar' is now 'basement'."]);
+        assert_eq!(save.story_hash, renamed.structure_hash());
+    }
+
+    #[test]
+    fn migrate_clamps_counters_to_their_new_range() {
+        let mut save = saved_in(&load_world_from_markdown_content(TWO_ROOMS).unwrap(), "cellar", |game_state| {
+            game_state.counters.insert("courage".to_string(), 4);
+        });
+        let narrower = load_world_from_markdown_content(&TWO_ROOMS.replace("0..5", "0..2")).unwrap();
+
+        let notes = save.migrate(&narrower).unwrap();
+        assert_eq!(save.state.counters["courage"], 2);
+        assert_eq!(notes, ["Counter 'courage' was 4, outside 0..2; it is now 2."]);
+    }
+
+    #[test]
+    fn migrate_rejects_saves_in_a_missing_room() {
+        let mut save = saved_in(&load_world_from_markdown_content(TWO_ROOMS).unwrap(), "hallway", |_| {});
+        let smaller = load_world_from_markdown_content(
+            "# Two Rooms\n\n@start cellar\n@var courage 0..5 = 1\n\n## @room cellar\nA cellar.\n\n### Look around\n- flag+ has_key\n- count+ courage\n",
+        )
+        .unwrap();
+
+        let error = save.migrate(&smaller).unwrap_err().to_string();
+        assert!(error.contains("room 'hallway', which the story no longer has"), "{}", error);
+    }
+
+    #[test]
+    fn migrate_drops_unknown_flags_and_counters_even_when_the_story_matches() {
+        let world = load_world_from_markdown_content(TWO_ROOMS).unwrap();
+        let mut save = saved_in(&world, "cellar", |game_state| {
+            game_state.flags.insert(FlagId("has_key".to_string()));
+            game_state.flags.insert(FlagId("has_lamp".to_string()));
+            game_state.counters.insert("gold".to_string(), 3);
+            game_state.history.push(game_state.clone());
+        });
+        assert_eq!(save.story_hash, world.structure_hash());
+
+        let notes = save.migrate(&world).unwrap();
+        assert_eq!(
+            notes,
+            ["Flag 'has_lamp' is no longer in the story, so it was dropped.", "Counter 'gold' is no longer in the story, so it was dropped."]
+        );
+        assert_eq!(save.state.flags, [FlagId("has_key".to_string())].into_iter().collect());
+        assert!(!save.state.counters.contains_key("gold"));
+        assert!(!save.state.history[0].flags.contains(&FlagId("has_lamp".to_string())));
+        assert!(save.migrate(&world).unwrap().is_empty());
+    }
+
+    fn store_in(name: &str) -> (SaveStore, PathBuf) {
+        let dir = paths::scratch_dir(name);
+        (SaveStore { storage: SlotStorage::Directory(dir.clone()) }, dir)
+    }
+
+    #[test]
+    fn slots_save_load_and_delete_in_a_directory() {
+        let (store, dir) = store_in("save-slots");
+        let world = world("The Cellar");
+        let game_state = played(&world);
+
+        store.save(QUICK_SAVE, &world, &game_state, Duration::from_secs(90)).unwrap();
+        store.save("before-door", &world, &GameState::for_world(&world, 7), Duration::ZERO).unwrap();
+        assert!(dir.join("quicksave.json").is_file());
+        assert!(!dir.join("quicksave.json.tmp").exists());
+
+        let loaded = store.load(QUICK_SAVE).unwrap();
+        assert_eq!(loaded.state.state_hash(), game_state.state_hash());
+        let metadata = loaded.metadata.unwrap();
+        assert_eq!((metadata.story_title.as_str(), metadata.room.as_str(), metadata.playtime_secs), ("The Cellar", "cellar", 90));
+        let (restored, notes) = store.restore(QUICK_SAVE, &world).unwrap();
+        assert!(notes.is_empty());
+        assert_eq!(restored.state.state_hash(), game_state.state_hash());
+
+        let mut names: Vec<String> = store.list().unwrap().into_iter().map(|slot| slot.name).collect();
+        names.sort();
+        assert_eq!(names, ["before-door", "quicksave"]);
+
+        store.delete("before-door").unwrap();
+        assert!(!store.exists("before-door"));
+        assert!(store.load("before-door").unwrap_err().to_string().contains("No save named 'before-door'"));
+        assert!(store.delete("before-door").is_err());
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn slots_with_invalid_names_are_rejected() {
+        let (store, dir) = store_in("save-names");
+        let world = world("The Cellar");
+        let game_state = played(&world);
+
+        for name in ["", "../escape", "two words"] {
+            let error = store.save(name, &world, &game_state, Duration::ZERO).unwrap_err().to_string();
+            assert!(error.contains("Invalid save name"), "{}", error);
+        }
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn unreadable_slots_are_still_listed() {
+        let (store, dir) = store_in("save-unreadable");
+        fs::write(dir.join("broken.json"), "not a save").unwrap();
+
+        let slots = store.list().unwrap();
+        assert_eq!(slots.len(), 1);
+        assert_eq!(slots[0].name, "broken");
+        assert!(slots[0].metadata.is_none());
+        assert!(store.load("broken").is_err());
+        store.delete("broken").unwrap();
+    }
+
+    #[test]
+    fn auto_save_follows_room_changes_and_clears_at_the_end() {
+        let (store, _dir) = store_in("save-auto");
+        let world = load_world_from_markdown_content(TWO_ROOMS).unwrap();
+        let mut game_state = GameState::for_world(&world, 7);
+
+        store.auto_save(&world, &game_state, "cellar", false, Duration::ZERO).unwrap();
+        assert!(!store.exists(AUTO_SAVE));
+        store.auto_save(&world, &game_state, "cellar", true, Duration::ZERO).unwrap();
+        assert!(store.exists(AUTO_SAVE));
+
+        game_state.current_room_id = "hallway".to_string();
+        store.auto_save(&world, &game_state, "cellar", false, Duration::ZERO).unwrap();
+        assert_eq!(store.load(AUTO_SAVE).unwrap().state.current_room_id, "hallway");
+
+        game_state.has_quit = true;
+        store.auto_save(&world, &game_state, "hallway", false, Duration::ZERO).unwrap();
+        assert!(!store.exists(AUTO_SAVE));
+    }
+
+    #[test]
+    fn save_files_keep_the_undo_history() {
+        let world = load_world_from_markdown_content(TWO_ROOMS).unwrap();
+        let mut game_state = GameState::for_world(&world, 7);
+        Engine::choose(&world, &mut game_state, crate::world::ChoiceId("cellar_choice_0".to_string())).unwrap();
+        Engine::choose(&world, &mut game_state, crate::world::ChoiceId("cellar_choice_1".to_string())).unwrap();
+
+        let mut loaded = SaveFile::from_json(&SaveFile::new(&world, &game_state).to_json().unwrap()).unwrap();
+        assert_eq!(loaded.state.history.len(), 2);
+        Engine::rewind(&world, &mut loaded.state, 1).unwrap();
+        assert_eq!(loaded.state.current_room_id, "cellar");
+        assert_eq!(loaded.state.state_hash(), game_state.history[1].state_hash());
+    }
+
+    #[test]
+    fn atomic_writes_replace_the_old_file_and_leave_no_temporary_one() {
+        let path = paths::scratch_dir("save-atomic").join("autosave.json");
+        fs::write(&path, "old").unwrap();
+
+        write_atomically(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn slot_lists_put_the_newest_save_first() {
+        let (store, _dir) = store_in("save-order");
+        let world = world("The Cellar");
+        let game_state = played(&world);
+        for (slot, age) in [("older", 7200), ("newer", 120)] {
+            let mut metadata = SaveMetadata::new(&world, &game_state, Duration::from_secs(754));
+            metadata.saved_at -= age;
+            let save = SaveFile { metadata: Some(metadata), ..SaveFile::new(&world, &game_state) };
+            store.write(slot, &save.to_json().unwrap()).unwrap();
+        }
+
+        let slots = store.list().unwrap();
+        assert_eq!(slots.iter().map(|slot| slot.name.as_str()).collect::<Vec<_>>(), ["newer", "older"]);
+        assert_eq!(slots[0].lines(), ["newer — cellar · played 12m · saved 2 minutes ago", "    \"A dusty cellar.\""]);
+        assert_eq!(slots[1].label(), "older — cellar · played 12m · saved 2 hours ago");
+    }
+
+    #[test]
+    fn playtime_carries_over_from_the_loaded_save() {
+        let elapsed = PlayClock::resume(90).elapsed();
+        assert!(elapsed >= Duration::from_secs(90) && elapsed < Duration::from_secs(91), "{:?}", elapsed);
+        assert_eq!(format_playtime(59), "59s");
+        assert_eq!(format_playtime(3600 + 61), "1h 1m");
+        assert_eq!(format_age(now_millis() / 1000 + 30), "just now");
+        assert_eq!(format_age(now_millis() / 1000 - 86_400), "1 day ago");
+    }
+}
@@ -0,0 +1,200 @@
+use crate::world::{Choice, ChoiceId, World};
+use crate::game::{GameState, get_available_choices};
+use crate::engine::{Engine, GameEvent};
+use crate::observer::{GameObserver, Observers};
+use crate::saves::SaveFile;
+use crate::errors::{GameError, GameResult};
+
+/// Which choice to make: a position in `Session::choices` or a choice ID.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection {
+    Index(usize),
+    Id(String),
+}
+
+impl From<usize> for Selection {
+    fn from(index: usize) -> Self {
+        Selection::Index(index)
+    }
+}
+
+impl From<&str> for Selection {
+    fn from(id: &str) -> Self {
+        Selection::Id(id.to_string())
+    }
+}
+
+impl From<String> for Selection {
+    fn from(id: String) -> Self {
+        Selection::Id(id)
+    }
+}
+
+impl From<ChoiceId> for Selection {
+    fn from(id: ChoiceId) -> Self {
+        Selection::Id(id.0)
+    }
+}
+
+/// A story being played, without any UI. Bots, tests and other programs drive a
+/// story through this instead of reimplementing the game loop.
+pub struct Session {
+    world: World,
+    state: GameState,
+    observers: Observers,
+}
+
+impl Session {
+    /// Start the story from the beginning. The same `seed` always rolls the dice the
+    /// same way for the same choices.
+    pub fn new(world: World, seed: u64) -> Self {
+        let state = GameState::for_world(&world, seed);
+        Session { world, state, observers: Observers::new() }
+    }
+
+    /// Load and validate a story from Markdown, then start it.
+    pub fn from_markdown(content: &str, seed: u64) -> GameResult<Self> {
+        Ok(Session::new(crate::world::load_world_from_markdown_content(content)?, seed))
+    }
+
+    /// Have `observer` told about every choice made in this session.
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.add(observer);
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// What the player currently sees: the room description or the conversation.
+    pub fn description(&self) -> GameResult<String> {
+        Engine::describe(&self.world, &self.state)
+    }
+
+    /// The choices available right now, in the order they are shown.
+    pub fn choices(&self) -> GameResult<Vec<&Choice>> {
+        if self.state.has_quit {
+            return Ok(Vec::new());
+        }
+        get_available_choices(&self.world, &self.state)
+    }
+
+    /// Make a choice by zero-based position in `choices()` or by choice ID.
+    pub fn choose(&mut self, selection: impl Into<Selection>) -> GameResult<Vec<GameEvent>> {
+        if self.state.has_quit {
+            return Err(GameError::ValidationError("The story is over".to_string()));
+        }
+        let choice_id = match selection.into() {
+            Selection::Index(index) => {
+                let choices = self.choices()?;
+                let choice = choices.get(index).ok_or_else(|| {
+                    GameError::ValidationError(format!("There is no choice {}; {} are available", index, choices.len()))
+                })?;
+                choice.id.clone()
+            }
+            Selection::Id(id) => ChoiceId(id),
+        };
+        self.observers.choose(&self.world, &mut self.state, choice_id)
+    }
+
+    /// Whether the last choice stopped at an `ask` prompt; reply with `answer`.
+    pub fn is_waiting_for_input(&self) -> bool {
+        self.state.pending_input.is_some()
+    }
+
+    pub fn answer(&mut self, text: &str) -> GameResult<Vec<GameEvent>> {
+        self.observers.provide_input(&self.world, &mut self.state, text)
+    }
+
+    /// Take back the last `steps` choices; returns how many were undone.
+    pub fn undo(&mut self, steps: usize) -> GameResult<usize> {
+        self.observers.rewind(&self.world, &mut self.state, steps)
+    }
+
+    /// End the story early, as a player quitting would.
+    pub fn quit(&mut self) {
+        self.observers.quit(&mut self.state);
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.state.has_quit
+    }
+
+    /// The game state as JSON, in the same format as save files.
+    pub fn save(&self) -> GameResult<String> {
+        SaveFile::new(&self.world, &self.state).to_json()
+    }
+
+    /// Continue from JSON produced by `save` or read from a save file. Saves from an
+    /// older version of the story are migrated; returns a note for the player about
+    /// each change, see `SaveFile::migrate`.
+    pub fn restore(&mut self, save: &str) -> GameResult<Vec<String>> {
+        let mut save = SaveFile::from_json(save)?;
+        let notes = save.migrate(&self.world)?;
+        self.observers.load(&mut self.state, save.state);
+        Ok(notes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::DiceRng;
+
+    const STORY: &str = "# Session test\n\n@start cellar\n\n## @room cellar\nA dusty cellar.\n\n### Look around\n- \"You find a key.\"\n- flag+ has_key\n\n### Go north\n- @hallway\n\n## @room hallway\nA narrow hallway.\n\n### Leave\n- quit\n";
+
+    #[test]
+    fn new_sessions_start_in_the_first_room_with_the_given_seed() {
+        let session = Session::from_markdown(STORY, 7).unwrap();
+        assert_eq!(session.state().current_room_id, "cellar");
+        assert_eq!(session.state().rng, DiceRng::seeded(7));
+        assert!(session.description().unwrap().contains("A dusty cellar."));
+        assert!(!session.is_over());
+    }
+
+    #[test]
+    fn choices_are_made_by_position_or_id() {
+        let mut session = Session::from_markdown(STORY, 0).unwrap();
+        let events = session.choose(0).unwrap();
+        assert_eq!(events[0].display_text().as_deref(), Some("\"You find a key.\""));
+
+        session.choose("cellar_choice_1").unwrap();
+        assert_eq!(session.state().current_room_id, "hallway");
+        let error = session.choose(5).unwrap_err().to_string();
+        assert!(error.contains("There is no choice 5; 1 are available"), "{}", error);
+
+        session.choose(0).unwrap();
+        assert!(session.is_over());
+        assert!(session.choices().unwrap().is_empty());
+        assert!(session.choose(0).is_err());
+    }
+
+    #[test]
+    fn saves_restore_where_they_were_made() {
+        let mut session = Session::from_markdown(STORY, 3).unwrap();
+        session.choose(0).unwrap();
+        let save = session.save().unwrap();
+        let saved_hash = session.state().state_hash();
+
+        session.choose(1).unwrap();
+        assert_eq!(session.restore(&save).unwrap(), Vec::<String>::new());
+        assert_eq!(session.state().state_hash(), saved_hash);
+        assert!(session.restore("not a save").is_err());
+        assert_eq!(session.state().state_hash(), saved_hash);
+    }
+
+    #[test]
+    fn restoring_reports_what_was_migrated() {
+        let mut older = Session::from_markdown(&STORY.replace("has_key", "has_lamp"), 0).unwrap();
+        older.choose(0).unwrap();
+
+        let mut session = Session::from_markdown(STORY, 0).unwrap();
+        let notes = session.restore(&older.save().unwrap()).unwrap();
+        assert_eq!(notes, ["Flag 'has_lamp' is no longer in the story, so it was dropped."]);
+        assert!(session.state().flags.is_empty());
+    }
+}
@@ -6,19 +6,27 @@ use crossterm::{
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect, Size},
-    style::{Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph},
     Frame, Terminal,
 };
 use tui_scrollview::{ScrollView, ScrollViewState, ScrollbarVisibility};
+use std::collections::VecDeque;
 use std::io;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crate::config::GameConfig;
+use crate::keymap::{Key, KeyAction, Keymap};
+use crate::pacing::{reveal_step, split_pacing, PacedText};
+use crate::theme::Theme;
 use crate::world::Choice;
 use crate::ui_trait::{GameUI, WaitForInput};
 use std::error::Error;
 
+// How long a toast notification stays on screen
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+
 pub struct TerminalUi {
     terminal: Terminal<CrosstermBackend<io::Stdout>>,
     all_text: Vec<String>,  // All accumulated text including story and responses
@@ -27,6 +35,23 @@ pub struct TerminalUi {
     config: GameConfig,
     scroll_view_state: ScrollViewState,
     choice_list_state: ListState, // Separate state for choices
+    text_input: Option<String>,   // Text being typed when the story asks a question
+    toast: Option<(String, Instant)>, // Notification and when it was shown
+    speakers: Vec<String>,        // Names highlighted at the start of dialogue lines
+    unavailable: Vec<KeyAction>,  // Actions the story has nothing for, left out of the help
+    theme: Theme,
+    keymap: Keymap,
+    show_help: bool,              // Whether the key bindings are shown over the story
+    pending_keys: VecDeque<Event>, // Keys pressed while text was typing, for get_input
+}
+
+// The transcript plus the speaker names to highlight in it, the colours to use
+// and the keys to mention in hints
+struct StoryText<'a> {
+    lines: &'a [String],
+    speakers: &'a [String],
+    theme: &'a Theme,
+    keymap: &'a Keymap,
 }
 
 impl TerminalUi {
@@ -37,6 +62,8 @@ impl TerminalUi {
         execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
         let backend = CrosstermBackend::new(stdout);
         let terminal = Terminal::new(backend)?;
+        let theme = config.theme().unwrap_or_default();
+        let keymap = config.keymap().unwrap_or_default();
 
         Ok(TerminalUi {
             terminal,
@@ -46,6 +73,14 @@ impl TerminalUi {
             config,
             scroll_view_state: ScrollViewState::default(),
             choice_list_state: ListState::default(),
+            text_input: None,
+            toast: None,
+            speakers: Vec::new(),
+            unavailable: Vec::new(),
+            theme,
+            keymap,
+            show_help: false,
+            pending_keys: VecDeque::new(),
         })
     }
 
@@ -60,39 +95,88 @@ impl TerminalUi {
         Ok(())
     }
 
+    /// Names to highlight when a line of dialogue starts with them.
+    pub fn set_speakers(&mut self, speakers: Vec<String>) {
+        self.speakers = speakers;
+    }
+
+    /// Actions to leave out of the help overlay.
+    pub fn set_unavailable_actions(&mut self, unavailable: Vec<KeyAction>) {
+        self.unavailable = unavailable;
+    }
+
     pub fn display_text(&mut self, text: &str) {
-        // Add text to the scrolling history
-        if !text.trim().is_empty() {
-            if self.config.enable_typewriter {
-                self.display_text_with_typewriter(text);
-            } else {
-                self.all_text.push(text.to_string());
-                self.scroll_to_bottom();
+        // Add text to the scrolling history, honouring the author's pacing markers
+        if text.trim().is_empty() {
+            return;
+        }
+        let mut line_index = None;
+        // Set once the player skips; the rest of the text up to the next wait appears at once
+        let mut skipped = false;
+        for segment in split_pacing(text) {
+            match segment {
+                PacedText::Text(part) => {
+                    let index = *line_index.get_or_insert_with(|| {
+                        self.all_text.push(String::new());
+                        self.all_text.len() - 1
+                    });
+                    if self.config.enable_typewriter && !skipped {
+                        skipped = self.display_text_with_typewriter(index, &part);
+                    } else {
+                        self.all_text[index].push_str(&part);
+                        self.scroll_to_bottom();
+                    }
+                }
+                PacedText::Pause(millis) => {
+                    // Fast mode skips pauses along with the typewriter
+                    if self.config.enable_typewriter && !skipped {
+                        if let Err(e) = self.draw() {
+                            eprintln!("Error drawing during pause: {}", e);
+                        }
+                        skipped = self.skip_requested(Duration::from_millis(millis));
+                    }
+                }
+                PacedText::Wait | PacedText::PageBreak => {
+                    skipped = false;
+                    if let Err(e) = self.wait_for_continue() {
+                        eprintln!("Error waiting for input: {}", e);
+                    }
+                    if segment == PacedText::PageBreak {
+                        self.clear_text();
+                    }
+                    line_index = None;
+                }
             }
         }
     }
 
-    fn display_text_with_typewriter(&mut self, text: &str) {
-        // Add a new line for this text
-        self.all_text.push(String::new());
-        let current_line_index = self.all_text.len() - 1;
-        
-        let mut current_text = String::new();
+    // Types `text` onto the end of the transcript line at `line_index`, a step of
+    // the configured reveal mode at a time. Returns whether the player skipped it.
+    fn display_text_with_typewriter(&mut self, line_index: usize, text: &str) -> bool {
+        let mut current_text = self.all_text[line_index].clone();
+        let final_text = format!("{}{}", current_text, text);
+        let mut rest = text;
+        let mut skipped = false;
         
-        for ch in text.chars() {
-            current_text.push(ch);
+        while !rest.is_empty() {
+            let step_chars = reveal_step(rest, &self.config.reveal);
+            let step_len = rest.char_indices().nth(step_chars).map_or(rest.len(), |(index, _)| index);
+            current_text.push_str(&rest[..step_len]);
+            rest = &rest[step_len..];
             
             // Update the current line with the progress
-            self.all_text[current_line_index] = current_text.clone();
+            self.all_text[line_index] = current_text.clone();
             
             // Capture values needed for drawing
             let current_choices = self.current_choices.clone();
-            let mut scroll_view_state = self.scroll_view_state.clone();
+            let mut scroll_view_state = self.scroll_view_state;
             let mut choice_list_state = self.choice_list_state.clone();
             
             // Force a complete redraw for each character
+            let toast = self.active_toast();
             if let Err(e) = self.terminal.draw(|f| {
-                draw_terminal_content_with_scrollview(f, &self.all_text, &current_choices, &mut scroll_view_state, &mut choice_list_state);
+                let story = StoryText { lines: &self.all_text, speakers: &self.speakers, theme: &self.theme, keymap: &self.keymap };
+                draw_terminal_content_with_scrollview(f, &story, &current_choices, None, toast.as_deref(), &mut scroll_view_state, &mut choice_list_state);
             }) {
                 eprintln!("Error drawing during typewriter: {}", e);
                 break;
@@ -102,15 +186,50 @@ impl TerminalUi {
             self.scroll_view_state = scroll_view_state;
             self.choice_list_state = choice_list_state;
             
-            // Sleep for typewriter effect
-            std::thread::sleep(std::time::Duration::from_millis(self.config.typewriter_speed_ms));
+            // Wait out the typewriter delay, finishing at once if a key is pressed
+            if self.skip_requested(Duration::from_millis(self.config.typewriter_speed_ms * step_chars as u64)) {
+                skipped = true;
+                break;
+            }
         }
         
         // Ensure the final text is set
-        self.all_text[current_line_index] = text.to_string();
+        self.all_text[line_index] = final_text;
         
         // Auto-scroll to bottom after completion
         self.scroll_view_state.scroll_to_bottom();
+        skipped
+    }
+
+    // Waits for `delay`, returning early with true if the player presses a key that
+    // skips text. Other keys are kept for `get_input`
+    fn skip_requested(&mut self, delay: Duration) -> bool {
+        let deadline = Instant::now() + delay;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+            match event::poll(remaining) {
+                Ok(true) => {
+                    if let Ok(Event::Key(key)) = event::read() {
+                        if key.kind != KeyEventKind::Press {
+                            continue;
+                        }
+                        if skips_text(&self.keymap, key.code) {
+                            return true;
+                        }
+                        self.pending_keys.push_back(Event::Key(key));
+                    }
+                }
+                Ok(false) => return false,
+                Err(e) => {
+                    eprintln!("Error reading input during typewriter: {}", e);
+                    std::thread::sleep(remaining);
+                    return false;
+                }
+            }
+        }
     }
 
     fn scroll_to_bottom(&mut self) {
@@ -155,6 +274,13 @@ impl TerminalUi {
         }
     }
 
+    /// Show lines as they are, without the typewriter or pacing markers, e.g. for
+    /// text the player will copy.
+    pub fn display_verbatim(&mut self, lines: &[&str]) {
+        self.all_text.extend(lines.iter().map(|line| line.to_string()));
+        self.scroll_to_bottom();
+    }
+
     pub fn add_user_input(&mut self, input: &str) {
         // Show what the user typed
         self.all_text.push(format!("> {}", input));
@@ -166,83 +292,183 @@ impl TerminalUi {
         loop {
             self.draw()?;
             
-            if event::poll(Duration::from_millis(16))? {
-                let event = event::read()?;
-                match event {
-                    Event::Key(key) if key.kind == KeyEventKind::Press => {
-                        match key.code {
-                            KeyCode::Enter => {
-                                if !self.current_choices.is_empty() {
-                                    let choice_number = self.selected_choice + 1;
-                                    let input = choice_number.to_string();
-                                    self.add_user_input(&format!("Selected: {}", self.current_choices[self.selected_choice]));
+            // Keys pressed while text was typing come first
+            let event = match self.pending_keys.pop_front() {
+                Some(event) => event,
+                None if event::poll(Duration::from_millis(16))? => event::read()?,
+                None => continue,
+            };
+            match event {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    let Some(key) = key_of(key.code) else { continue };
+                    // Any key closes the help overlay
+                    if self.show_help {
+                        self.show_help = false;
+                        continue;
+                    }
+                    let has_choices = !self.current_choices.is_empty();
+                    match self.keymap.action(key) {
+                        Some(KeyAction::Confirm) if has_choices => {
+                            let choice_number = self.selected_choice + 1;
+                            let input = choice_number.to_string();
+                            self.add_user_input(&format!("Selected: {}", self.current_choices[self.selected_choice]));
+                            return Ok(input);
+                        }
+                        // Without choices the selection keys scroll instead
+                        Some(KeyAction::SelectPrev) if has_choices => self.move_selection_up(),
+                        Some(KeyAction::SelectNext) if has_choices => self.move_selection_down(),
+                        Some(KeyAction::SelectPrev | KeyAction::ScrollUp) => self.scroll_up(),
+                        Some(KeyAction::SelectNext | KeyAction::ScrollDown) => self.scroll_down(),
+                        Some(KeyAction::PageUp) => {
+                            for _ in 0..5 { self.scroll_up(); }
+                        }
+                        Some(KeyAction::PageDown) => {
+                            for _ in 0..5 { self.scroll_down(); }
+                        }
+                        Some(KeyAction::ScrollTop) => self.scroll_view_state.scroll_to_top(),
+                        Some(KeyAction::ScrollBottom) => self.scroll_view_state.scroll_to_bottom(),
+                        Some(KeyAction::Menu) => self.show_help = true,
+                        Some(KeyAction::Quit) => return Ok("quit".to_string()),
+                        Some(KeyAction::Undo) => return Ok("undo".to_string()),
+                        Some(KeyAction::Endings) => return Ok("endings".to_string()),
+                        Some(KeyAction::Achievements) => return Ok("achievements".to_string()),
+                        Some(KeyAction::Save) => return Ok("save".to_string()),
+                        Some(KeyAction::Load) => return Ok("load".to_string()),
+                        Some(KeyAction::ExportSave) => return Ok("export-save".to_string()),
+                        Some(KeyAction::ImportSave) => return Ok("import-save".to_string()),
+                        Some(KeyAction::Confirm | KeyAction::SkipText) => {}
+                        // Unbound number keys pick that choice
+                        None => {
+                            if let Some(digit) = key_digit(key) {
+                                let choice_index = (digit as usize).saturating_sub(1);
+                                if choice_index < self.current_choices.len() {
+                                    self.selected_choice = choice_index;
+                                    self.choice_list_state.select(Some(choice_index));
+                                    let input = digit.to_string();
+                                    self.add_user_input(&format!("Selected: {}", self.current_choices[choice_index]));
                                     return Ok(input);
                                 }
                             }
-                            KeyCode::Up => {
-                                if !self.current_choices.is_empty() {
-                                    self.move_selection_up();
-                                } else {
-                                    self.scroll_up();
-                                }
-                            }
-                            KeyCode::Down => {
-                                if !self.current_choices.is_empty() {
-                                    self.move_selection_down();
-                                } else {
-                                    self.scroll_down();
-                                }
-                            }
-                            // Add Ctrl+ combinations for scrolling even with choices present
-                            KeyCode::Char('k') => {
-                                self.scroll_up();
-                            }
-                            KeyCode::Char('j') => {
-                                self.scroll_down();
-                            }
-                            KeyCode::PageUp => {
+                        }
+                    }
+                }
+                Event::Mouse(mouse) => {
+                    match mouse.kind {
+                        MouseEventKind::ScrollUp => {
+                            self.scroll_up();
+                        }
+                        MouseEventKind::ScrollDown => {
+                            self.scroll_down();
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn show_toast(&mut self, message: &str) {
+        self.toast = Some((message.to_string(), Instant::now()));
+    }
+
+    fn active_toast(&self) -> Option<String> {
+        self.toast
+            .as_ref()
+            .filter(|(_, shown_at)| shown_at.elapsed() < TOAST_DURATION)
+            .map(|(message, _)| message.clone())
+    }
+
+    pub fn get_text_input(&mut self, prompt: &str) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(self.read_text_input(prompt, false)?.unwrap_or_default())
+    }
+
+    /// Like `get_text_input`, but returns None if the player backs out with Esc.
+    pub fn get_text_input_or_cancel(&mut self, prompt: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        self.read_text_input(&format!("{} (Esc to cancel)", prompt), true)
+    }
+
+    fn read_text_input(&mut self, prompt: &str, cancellable: bool) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        if !prompt.trim().is_empty() {
+            self.display_text(prompt);
+        }
+        self.text_input = Some(String::new());
+
+        let answer = loop {
+            self.draw()?;
+
+            if event::poll(Duration::from_millis(16))? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    let buffer = self.text_input.get_or_insert_with(String::new);
+                    match key.code {
+                        KeyCode::Enter if !buffer.trim().is_empty() => {
+                            break Some(buffer.trim().to_string());
+                        }
+                        KeyCode::Esc if cancellable => break None,
+                        KeyCode::Char(c) => buffer.push(c),
+                        KeyCode::Backspace => {
+                            buffer.pop();
+                        }
+                        // Letters are part of the answer, so only other keys page
+                        code => match key_of(code).and_then(|key| self.keymap.action(key)) {
+                            Some(KeyAction::PageUp) => {
                                 for _ in 0..5 { self.scroll_up(); }
                             }
-                            KeyCode::PageDown => {
+                            Some(KeyAction::PageDown) => {
                                 for _ in 0..5 { self.scroll_down(); }
                             }
-                            KeyCode::Home => {
-                                self.scroll_view_state.scroll_to_top();
-                            }
-                            KeyCode::End => {
-                                self.scroll_view_state.scroll_to_bottom();
-                            }
-                            KeyCode::Esc => {
-                                return Ok("quit".to_string());
-                            }
-                            // Allow number keys as shortcuts
-                            KeyCode::Char(c) if c.is_ascii_digit() => {
-                                if let Some(digit) = c.to_digit(10) {
-                                    let choice_index = (digit as usize).saturating_sub(1);
-                                    if choice_index < self.current_choices.len() {
-                                        self.selected_choice = choice_index;
-                                        self.choice_list_state.select(Some(choice_index));
-                                        let input = digit.to_string();
-                                        self.add_user_input(&format!("Selected: {}", self.current_choices[choice_index]));
-                                        return Ok(input);
-                                    }
-                                }
-                            }
                             _ => {}
-                        }
+                        },
                     }
-                    Event::Mouse(mouse) => {
-                        match mouse.kind {
-                            MouseEventKind::ScrollUp => {
-                                self.scroll_up();
-                            }
-                            MouseEventKind::ScrollDown => {
-                                self.scroll_down();
-                            }
-                            _ => {}
+                }
+            }
+        };
+
+        self.text_input = None;
+        if let Some(answer) = &answer {
+            self.add_user_input(answer);
+        }
+        Ok(answer)
+    }
+
+    /// Show `labels` in a pop-up list and return the index picked, or None if the
+    /// player backs out with Esc.
+    pub fn pick_slot(&mut self, title: &str, labels: &[String]) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+        let mut picker_state = ListState::default();
+        picker_state.select(Some(0));
+
+        loop {
+            let current_choices = self.current_choices.clone();
+            let mut scroll_view_state = self.scroll_view_state;
+            let mut choice_list_state = self.choice_list_state.clone();
+            self.terminal.draw(|f| {
+                let story = StoryText { lines: &self.all_text, speakers: &self.speakers, theme: &self.theme, keymap: &self.keymap };
+                draw_terminal_content_with_scrollview(f, &story, &current_choices, None, None, &mut scroll_view_state, &mut choice_list_state);
+                draw_slot_picker(f, title, labels, &self.theme, &mut picker_state);
+            })?;
+
+            if event::poll(Duration::from_millis(16))? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    let selected = picker_state.selected().unwrap_or(0);
+                    if key.code == KeyCode::Esc {
+                        return Ok(None);
+                    }
+                    match key_of(key.code).and_then(|key| self.keymap.action(key)) {
+                        Some(KeyAction::SelectPrev | KeyAction::ScrollUp) => {
+                            picker_state.select(Some(selected.saturating_sub(1)));
+                        }
+                        Some(KeyAction::SelectNext | KeyAction::ScrollDown) => {
+                            picker_state.select(Some((selected + 1).min(labels.len().saturating_sub(1))));
                         }
+                        Some(KeyAction::Confirm) if !labels.is_empty() => return Ok(Some(selected)),
+                        _ => {}
                     }
-                    _ => {}
                 }
             }
         }
@@ -250,11 +476,18 @@ impl TerminalUi {
 
     fn draw(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         let current_choices = self.current_choices.clone();
-        let mut scroll_view_state = self.scroll_view_state.clone();
+        let mut scroll_view_state = self.scroll_view_state;
         let mut choice_list_state = self.choice_list_state.clone();
+        let text_input = self.text_input.clone();
+        let toast = self.active_toast();
+        let help = self.show_help.then(|| self.keymap.help_lines(&self.unavailable));
         
         self.terminal.draw(|f| {
-            draw_terminal_content_with_scrollview(f, &self.all_text, &current_choices, &mut scroll_view_state, &mut choice_list_state);
+            let story = StoryText { lines: &self.all_text, speakers: &self.speakers, theme: &self.theme, keymap: &self.keymap };
+            draw_terminal_content_with_scrollview(f, &story, &current_choices, text_input.as_deref(), toast.as_deref(), &mut scroll_view_state, &mut choice_list_state);
+            if let Some(help) = &help {
+                draw_help(f, help, &self.theme);
+            }
         })?;
 
         // Update states back
@@ -312,12 +545,20 @@ impl GameUI for TerminalUi {
         Err("Invalid choice".into())
     }
     
+    fn get_text_input(&mut self, prompt: &str) -> Result<String, Box<dyn Error>> {
+        TerminalUi::get_text_input(self, prompt)
+    }
+    
     fn clear_choices(&mut self) {
         self.current_choices.clear();
         self.selected_choice = 0;
         self.choice_list_state.select(None);
     }
     
+    fn show_toast(&mut self, message: &str) {
+        TerminalUi::show_toast(self, message);
+    }
+    
     fn add_separator(&mut self) {
         self.clear_text();
     }
@@ -330,29 +571,42 @@ impl GameUI for TerminalUi {
 impl WaitForInput for TerminalUi {
     fn wait_for_continue(&mut self) -> Result<(), Box<dyn Error>> {
         // Display "Press Enter to continue..." and wait for input
+        let prompt = format!("Press {} to continue...", self.keymap.label(KeyAction::SkipText));
         self.all_text.push("".to_string());
-        self.all_text.push("Press Enter to continue...".to_string());
+        self.all_text.push(prompt.clone());
         self.scroll_to_bottom();
         
-        // Draw the current state
-        self.draw()?;
-        
-        // Wait for any key press
+        // Wait for a skip_text key; scrolling keys still scroll meanwhile
         loop {
+            self.draw()?;
             if event::poll(Duration::from_millis(16))? {
                 let event = event::read()?;
                 if let Event::Key(key) = event {
-                    if key.kind == KeyEventKind::Press {
-                        // Any key press continues
+                    if key.kind != KeyEventKind::Press {
+                        continue;
+                    }
+                    let Some(key) = key_of(key.code) else { continue };
+                    if self.keymap.is(key, KeyAction::SkipText) {
                         break;
                     }
+                    match self.keymap.action(key) {
+                        Some(KeyAction::ScrollUp) => self.scroll_up(),
+                        Some(KeyAction::ScrollDown) => self.scroll_down(),
+                        Some(KeyAction::PageUp) => {
+                            for _ in 0..5 { self.scroll_up(); }
+                        }
+                        Some(KeyAction::PageDown) => {
+                            for _ in 0..5 { self.scroll_down(); }
+                        }
+                        _ => {}
+                    }
                 }
             }
         }
         
         // Remove the "Press Enter to continue..." message
         if let Some(last) = self.all_text.last() {
-            if last == "Press Enter to continue..." {
+            if *last == prompt {
                 self.all_text.pop();
                 self.all_text.pop(); // Also remove the empty line
             }
@@ -365,12 +619,26 @@ impl WaitForInput for TerminalUi {
 // Standalone function for drawing terminal content with ScrollView to avoid borrowing issues
 fn draw_terminal_content_with_scrollview(
     f: &mut Frame, 
-    all_text: &[String], 
+    story: &StoryText, 
     current_choices: &[String], 
+    text_input: Option<&str>,
+    toast: Option<&str>,
     scroll_view_state: &mut ScrollViewState,
     choice_list_state: &mut ListState
 ) {
-    let main_layout = if !current_choices.is_empty() {
+    let theme = story.theme;
+    f.render_widget(Block::default().style(theme.narration), f.area());
+
+    let main_layout = if text_input.is_some() {
+        // Split into story area and a single-line input box
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(10),    // Story area
+                Constraint::Length(3),  // Input area (with borders)
+            ])
+            .split(f.area())
+    } else if !current_choices.is_empty() {
         // Split into story area and choices area when choices are available
         Layout::default()
             .direction(Direction::Vertical)
@@ -402,13 +670,16 @@ fn draw_terminal_content_with_scrollview(
     let content_area = story_chunks[1];
 
     // Create the content text from all text lines
-    let raw_content = all_text.join("\n");
+    let raw_content = story.lines.join("\n");
     
-    // Pre-wrap text to fit the content area width
+    // Style each whole line first so wrapped dialogue and counters keep their look,
+    // then pre-wrap it to fit the content area width
     let content_width = content_area.width.saturating_sub(2); // Account for borders
-    let wrapped_lines = wrap_text_to_width(&raw_content, content_width as usize);
-    let content_text = wrapped_lines.join("\n");
-    let content_height = wrapped_lines.len() as u16;
+    let content_text: Vec<Line> = raw_content
+        .lines()
+        .flat_map(|line| wrap_line_to_width(theme.line(line, story.speakers), content_width as usize))
+        .collect();
+    let content_height = content_text.len() as u16;
     
     let content_size = Size::new(content_width, content_height);
     
@@ -419,22 +690,37 @@ fn draw_terminal_content_with_scrollview(
         .horizontal_scrollbar_visibility(ScrollbarVisibility::Never);
     
     // Create a paragraph widget to render inside the scroll view (no wrap needed since we pre-wrapped)
-    let paragraph = Paragraph::new(content_text);
+    let paragraph = Paragraph::new(content_text).style(theme.narration);
     
     // Render the paragraph inside the scroll view
     let scroll_area = Rect::new(0, 0, content_width, content_height);
     scroll_view.render_widget(paragraph, scroll_area);
     
     // Create a bordered area for the scroll view
-    let block = Block::default().borders(Borders::ALL).title(title);
+    let block = Block::default().borders(Borders::ALL).border_style(theme.border).title(title);
     let inner_area = block.inner(content_area);
     
     // Render the block first, then the scroll view inside
     f.render_widget(block, content_area);
     f.render_stateful_widget(scroll_view, inner_area, scroll_view_state);
 
+    // Render the input box while the story is waiting for typed text
+    if let Some(input) = text_input {
+        let input_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(10),  // Left margin
+                Constraint::Percentage(80),  // Input content
+                Constraint::Percentage(10),  // Right margin
+            ])
+            .split(main_layout[1]);
+
+        let input_box = Paragraph::new(format!("> {}_", input))
+            .block(Block::default().borders(Borders::ALL).border_style(theme.border).title("Type your answer + Enter"));
+        f.render_widget(input_box, input_chunks[1]);
+    }
     // Render choices area if choices are available
-    if !current_choices.is_empty() {
+    else if !current_choices.is_empty() {
         let choices_area = main_layout[1];
         
         // Choices area with margins
@@ -456,44 +742,180 @@ fn draw_terminal_content_with_scrollview(
             .collect();
 
         // Create the choices list with highlighting
+        let keymap = story.keymap;
+        let title = format!(
+            "{}{} + {} to select | {}/{} or mouse wheel to scroll | {} for all keys",
+            keymap.label(KeyAction::SelectPrev),
+            keymap.label(KeyAction::SelectNext),
+            keymap.label(KeyAction::Confirm),
+            keymap.label(KeyAction::ScrollDown),
+            keymap.label(KeyAction::ScrollUp),
+            keymap.label(KeyAction::Menu),
+        );
         let choices_list = List::new(choice_items)
-            .block(Block::default().borders(Borders::ALL).title("↑↓ arrows + Enter to select | j/k or mouse wheel to scroll"))
-            .style(Style::default())
-            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            .block(Block::default().borders(Borders::ALL).border_style(theme.border).title(title))
+            .style(theme.choice)
+            .highlight_style(theme.selection);
 
         // Render the choices list with state for selection
         f.render_stateful_widget(choices_list, choices_content_area, choice_list_state);
     }
+
+    // Render the toast over the top-right corner of the story
+    if let Some(message) = toast {
+        let area = f.area();
+        let width = (message.chars().count() as u16 + 4).min(area.width);
+        let toast_area = Rect::new(area.width.saturating_sub(width + 1), 1, width, 3.min(area.height));
+        let toast_box = Paragraph::new(message)
+            .block(Block::default().borders(Borders::ALL))
+            .style(theme.narration.patch(theme.border).add_modifier(Modifier::BOLD));
+        f.render_widget(Clear, toast_area);
+        f.render_widget(toast_box, toast_area);
+    }
 }
 
-// Helper function to wrap text to a specific width
-fn wrap_text_to_width(text: &str, width: usize) -> Vec<String> {
-    let mut wrapped_lines = Vec::new();
-    
-    for line in text.lines() {
-        if line.is_empty() {
-            wrapped_lines.push(String::new());
-            continue;
-        }
-        
-        let mut current_line = String::new();
-        
-        for word in line.split_whitespace() {
-            if current_line.is_empty() {
-                current_line = word.to_string();
-            } else if current_line.len() + 1 + word.len() <= width {
-                current_line.push(' ');
-                current_line.push_str(word);
-            } else {
-                wrapped_lines.push(current_line);
-                current_line = word.to_string();
+// Centered pop-up list of save slots; labels may span several lines
+fn draw_slot_picker(f: &mut Frame, title: &str, labels: &[String], theme: &Theme, picker_state: &mut ListState) {
+    let area = f.area();
+    let item_lines: usize = labels.iter().map(|label| label.lines().count().max(1)).sum();
+    let width = (area.width * 4 / 5).max(20).min(area.width);
+    let height = (item_lines as u16 + 2).min(area.height);
+    let picker_area = Rect::new(
+        (area.width - width) / 2,
+        (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    let items: Vec<ListItem> = labels.iter().map(|label| ListItem::new(label.as_str())).collect();
+    let picker = List::new(items)
+        .block(Block::default().borders(Borders::ALL).border_style(theme.border).title(format!("{} | ↑↓ + Enter | Esc to cancel", title)))
+        .style(theme.narration.patch(theme.choice))
+        .highlight_style(theme.selection);
+    f.render_widget(Clear, picker_area);
+    f.render_stateful_widget(picker, picker_area, picker_state);
+}
+
+// Centered pop-up listing the key bindings
+fn draw_help(f: &mut Frame, lines: &[String], theme: &Theme) {
+    let area = f.area();
+    let width = (lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u16 + 4).min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let help_area = Rect::new((area.width - width) / 2, (area.height - height) / 2, width, height);
+
+    let help = Paragraph::new(lines.iter().map(|line| Line::raw(line.as_str())).collect::<Vec<_>>())
+        .block(Block::default().borders(Borders::ALL).border_style(theme.border).title("Keys | any key to close"))
+        .style(theme.narration);
+    f.render_widget(Clear, help_area);
+    f.render_widget(help, help_area);
+}
+
+fn key_of(code: KeyCode) -> Option<Key> {
+    let key = match code {
+        KeyCode::Char(c) => Key::from_char(c),
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Esc => Key::Esc,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Tab => Key::Tab,
+        _ => return None,
+    };
+    Some(key)
+}
+
+// A skip_text key skips, and so does one without an action of its own
+fn skips_text(keymap: &Keymap, code: KeyCode) -> bool {
+    key_of(code).is_some_and(|key| keymap.is(key, KeyAction::SkipText) || keymap.action(key).is_none())
+}
+
+fn key_digit(key: Key) -> Option<u32> {
+    match key {
+        Key::Char(c) => c.to_digit(10),
+        _ => None,
+    }
+}
+
+// Wrap a styled line to a specific width; every word keeps the style it had
+fn wrap_line_to_width(line: Line<'_>, width: usize) -> Vec<Line<'static>> {
+    // A word can run across spans, e.g. a speaker's name and the colon after it
+    let mut words: Vec<Vec<Span<'static>>> = Vec::new();
+    let mut in_word = false;
+    for span in &line.spans {
+        let mut rest = span.content.as_ref();
+        while let Some(c) = rest.chars().next() {
+            if c.is_whitespace() {
+                in_word = false;
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+            let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let piece = Span::styled(rest[..word_end].to_string(), span.style);
+            match words.last_mut() {
+                Some(word) if in_word => word.push(piece),
+                _ => words.push(vec![piece]),
             }
+            in_word = true;
+            rest = &rest[word_end..];
         }
-        
-        if !current_line.is_empty() {
-            wrapped_lines.push(current_line);
+    }
+
+    let mut wrapped_lines = Vec::new();
+    let mut current_line: Vec<Span<'static>> = Vec::new();
+    let mut current_width = 0;
+
+    for word in words {
+        let word_width: usize = word.iter().map(|span| span.content.len()).sum();
+        if current_width > 0 && current_width + 1 + word_width > width {
+            wrapped_lines.push(Line::from(std::mem::take(&mut current_line)).style(line.style));
+            current_width = 0;
+        }
+        if current_width > 0 {
+            current_line.push(Span::styled(" ", word[0].style));
+            current_width += 1;
         }
+        current_width += word_width;
+        current_line.extend(word);
     }
-    
+
+    if !current_line.is_empty() || wrapped_lines.is_empty() {
+        wrapped_lines.push(Line::from(current_line).style(line.style));
+    }
+
     wrapped_lines
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skip_keys_and_unbound_keys_skip_text() {
+        let keymap = Keymap::default();
+        assert!(skips_text(&keymap, KeyCode::Enter));
+        assert!(skips_text(&keymap, KeyCode::Char(' ')));
+        assert!(skips_text(&keymap, KeyCode::Char('z')));
+    }
+
+    #[test]
+    fn keys_with_actions_are_kept() {
+        let keymap = Keymap::default();
+        assert!(!skips_text(&keymap, KeyCode::Esc));
+        assert!(!skips_text(&keymap, KeyCode::Char('u')));
+        assert!(!skips_text(&keymap, KeyCode::Up));
+        assert!(!skips_text(&keymap, KeyCode::F(1)));
+    }
+
+    #[test]
+    fn rebound_skip_keys_skip_text() {
+        let overrides = [("skip_text".to_string(), vec!["u".to_string()])].into_iter().collect();
+        let keymap = Keymap::new(&overrides).unwrap();
+        assert!(skips_text(&keymap, KeyCode::Char('u')));
+        assert!(!skips_text(&keymap, KeyCode::Esc));
+    }
+}
@@ -0,0 +1,266 @@
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Names of the themes every config can use.
+pub const BUILT_IN_THEMES: [&str; 4] = ["default", "high-contrast", "sepia", "monochrome"];
+
+/// Styles the full-screen and web UIs draw the story with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// Narration, and the background everything else is drawn on.
+    pub narration: Style,
+    pub dialogue: Style,
+    /// The speaker's name at the start of a dialogue line.
+    pub speaker: Style,
+    pub choice: Style,
+    /// The highlighted choice or save slot.
+    pub selection: Style,
+    /// The rule between rooms.
+    pub separator: Style,
+    /// `[counter: old → new]` messages.
+    pub counter: Style,
+    /// Borders, titles, prompts and notifications.
+    pub border: Style,
+}
+
+/// A theme defined in the config file under `palettes`. Colours are names
+/// (`yellow`, `lightblue`), `#rrggbb` or 0-255 indexes; anything left out comes
+/// from `base`, or from the default theme.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Palette {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub narration: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dialogue: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub choice: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selection: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selection_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub separator: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub counter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border: Option<String>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            narration: Style::default(),
+            dialogue: Style::default().fg(Color::LightCyan),
+            speaker: Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            choice: Style::default(),
+            selection: Style::default().add_modifier(Modifier::REVERSED),
+            separator: Style::default().fg(Color::DarkGray),
+            counter: Style::default().fg(Color::Yellow),
+            border: Style::default(),
+        }
+    }
+}
+
+impl Theme {
+    /// A built-in theme, or a palette from the config, by name.
+    pub fn named(name: &str, palettes: &BTreeMap<String, Palette>) -> Result<Theme, String> {
+        if let Some(palette) = palettes.get(name) {
+            return palette.theme(name, palettes);
+        }
+        built_in(name).ok_or_else(|| {
+            let mut known: Vec<&str> = BUILT_IN_THEMES.to_vec();
+            known.extend(palettes.keys().map(String::as_str));
+            format!("unknown theme '{}' (expected one of: {})", name, known.join(", "))
+        })
+    }
+
+    /// A transcript line styled by what it is: a separator, a counter message,
+    /// dialogue (with the speaker's name picked out) or narration.
+    pub fn line<'a>(&self, line: &'a str, speakers: &[String]) -> Line<'a> {
+        if !line.is_empty() && line.chars().all(|c| c == '─') {
+            return Line::styled(line, self.separator);
+        }
+        if line.starts_with('[') && line.ends_with(']') && line.contains(" → ") {
+            return Line::styled(line, self.counter);
+        }
+        for speaker in speakers {
+            let name_end = speaker.len() + 1;
+            if line.starts_with(speaker.as_str()) && line[speaker.len()..].starts_with(": ") {
+                return Line::from(vec![
+                    Span::styled(&line[..name_end], self.speaker),
+                    Span::styled(&line[name_end..], self.dialogue),
+                ]);
+            }
+        }
+        Line::styled(line, self.narration)
+    }
+}
+
+fn built_in(name: &str) -> Option<Theme> {
+    let theme = match name {
+        "default" => Theme::default(),
+        "high-contrast" => Theme {
+            narration: Style::default().fg(Color::White).bg(Color::Black),
+            dialogue: Style::default().fg(Color::LightYellow),
+            speaker: Style::default().fg(Color::LightCyan).add_modifier(Modifier::BOLD),
+            choice: Style::default().fg(Color::White),
+            selection: Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD),
+            separator: Style::default().fg(Color::White),
+            counter: Style::default().fg(Color::LightGreen).add_modifier(Modifier::BOLD),
+            border: Style::default().fg(Color::White),
+        },
+        "sepia" => Theme {
+            narration: Style::default().fg(Color::Rgb(0x5b, 0x46, 0x36)).bg(Color::Rgb(0xf4, 0xec, 0xd8)),
+            dialogue: Style::default().fg(Color::Rgb(0x7a, 0x4e, 0x2d)),
+            speaker: Style::default().fg(Color::Rgb(0x8b, 0x3a, 0x1a)).add_modifier(Modifier::BOLD),
+            choice: Style::default().fg(Color::Rgb(0x5b, 0x46, 0x36)),
+            selection: Style::default().fg(Color::Rgb(0x2b, 0x21, 0x18)).bg(Color::Rgb(0xc9, 0xa6, 0x6b)),
+            separator: Style::default().fg(Color::Rgb(0xb5, 0x9f, 0x7b)),
+            counter: Style::default().fg(Color::Rgb(0x8a, 0x6d, 0x3b)).add_modifier(Modifier::ITALIC),
+            border: Style::default().fg(Color::Rgb(0x8b, 0x73, 0x55)),
+        },
+        // Only the terminal's own colours; emphasis comes from modifiers
+        "monochrome" => Theme {
+            narration: Style::default(),
+            dialogue: Style::default(),
+            speaker: Style::default().add_modifier(Modifier::BOLD),
+            choice: Style::default(),
+            selection: Style::default().add_modifier(Modifier::REVERSED),
+            separator: Style::default().add_modifier(Modifier::DIM),
+            counter: Style::default().add_modifier(Modifier::ITALIC),
+            border: Style::default(),
+        },
+        _ => return None,
+    };
+    Some(theme)
+}
+
+impl Palette {
+    fn theme(&self, name: &str, palettes: &BTreeMap<String, Palette>) -> Result<Theme, String> {
+        let mut theme = match self.base.as_deref() {
+            // A palette can build on another one, as long as they don't go round in circles
+            Some(base) if base == name => return Err(format!("palette '{}' can't be based on itself", name)),
+            Some(base) => {
+                let mut others = palettes.clone();
+                others.remove(name);
+                Theme::named(base, &others).map_err(|e| format!("palette '{}': {}", name, e))?
+            }
+            None => Theme::default(),
+        };
+
+        let colour = |value: &Option<String>| -> Result<Option<Color>, String> {
+            value
+                .as_deref()
+                .map(|value| Color::from_str(value).map_err(|_| format!("palette '{}': unknown colour '{}'", name, value)))
+                .transpose()
+        };
+        if let Some(background) = colour(&self.background)? {
+            theme.narration = theme.narration.bg(background);
+        }
+        let foregrounds = [
+            (&self.narration, &mut theme.narration),
+            (&self.dialogue, &mut theme.dialogue),
+            (&self.speaker, &mut theme.speaker),
+            (&self.choice, &mut theme.choice),
+            (&self.selection_text, &mut theme.selection),
+            (&self.separator, &mut theme.separator),
+            (&self.counter, &mut theme.counter),
+            (&self.border, &mut theme.border),
+        ];
+        for (value, style) in foregrounds {
+            if let Some(fg) = colour(value)? {
+                *style = style.fg(fg);
+            }
+        }
+        if let Some(selection) = colour(&self.selection)? {
+            // A coloured highlight replaces the reversed one
+            theme.selection = theme.selection.bg(selection).remove_modifier(Modifier::REVERSED);
+        }
+        Ok(theme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palettes(entries: &[(&str, Palette)]) -> BTreeMap<String, Palette> {
+        entries.iter().map(|(name, palette)| (name.to_string(), palette.clone())).collect()
+    }
+
+    #[test]
+    fn built_in_themes_resolve_by_name() {
+        for name in BUILT_IN_THEMES {
+            assert!(Theme::named(name, &BTreeMap::new()).is_ok(), "{}", name);
+        }
+        let custom = palettes(&[("night", Palette::default())]);
+        let error = Theme::named("neon", &custom).unwrap_err();
+        assert_eq!(error, "unknown theme 'neon' (expected one of: default, high-contrast, sepia, monochrome, night)");
+    }
+
+    #[test]
+    fn palettes_build_on_a_chain_of_bases() {
+        let custom = palettes(&[
+            ("parchment", Palette { base: Some("sepia".to_string()), counter: Some("red".to_string()), ..Palette::default() }),
+            ("candlelit", Palette { base: Some("parchment".to_string()), dialogue: Some("#112233".to_string()), ..Palette::default() }),
+        ]);
+        let sepia = Theme::named("sepia", &custom).unwrap();
+
+        let theme = Theme::named("candlelit", &custom).unwrap();
+        assert_eq!(theme.dialogue, sepia.dialogue.fg(Color::Rgb(0x11, 0x22, 0x33)));
+        assert_eq!(theme.counter, sepia.counter.fg(Color::Red));
+        assert_eq!(theme.speaker, sepia.speaker);
+        assert_eq!(theme.narration, sepia.narration);
+    }
+
+    #[test]
+    fn palettes_without_a_base_start_from_the_default_theme() {
+        let custom = palettes(&[("plain", Palette { background: Some("black".to_string()), selection: Some("42".to_string()), ..Palette::default() })]);
+
+        let theme = Theme::named("plain", &custom).unwrap();
+        assert_eq!(theme.narration, Style::default().bg(Color::Black));
+        assert_eq!(theme.selection.bg, Some(Color::Indexed(42)));
+        assert!(!theme.selection.add_modifier.contains(Modifier::REVERSED));
+        assert_eq!(theme.dialogue, Theme::default().dialogue);
+    }
+
+    #[test]
+    fn circular_and_broken_palettes_are_rejected() {
+        let looped = palettes(&[("self", Palette { base: Some("self".to_string()), ..Palette::default() })]);
+        assert_eq!(Theme::named("self", &looped).unwrap_err(), "palette 'self' can't be based on itself");
+
+        let circle = palettes(&[
+            ("day", Palette { base: Some("night".to_string()), ..Palette::default() }),
+            ("night", Palette { base: Some("day".to_string()), ..Palette::default() }),
+        ]);
+        assert!(Theme::named("day", &circle).unwrap_err().starts_with("palette 'day': palette 'night': unknown theme 'day'"));
+
+        let broken = palettes(&[("muddy", Palette { choice: Some("mud".to_string()), ..Palette::default() })]);
+        assert_eq!(Theme::named("muddy", &broken).unwrap_err(), "palette 'muddy': unknown colour 'mud'");
+    }
+
+    #[test]
+    fn lines_are_styled_by_what_they_are() {
+        let theme = Theme::default();
+        let speakers = vec!["Alvin".to_string()];
+
+        assert_eq!(theme.line("────", &speakers), Line::styled("────", theme.separator));
+        assert_eq!(theme.line("[courage: 1 → 2]", &speakers), Line::styled("[courage: 1 → 2]", theme.counter));
+        assert_eq!(
+            theme.line("Alvin: \"Evening.\"", &speakers),
+            Line::from(vec![Span::styled("Alvin:", theme.speaker), Span::styled(" \"Evening.\"", theme.dialogue)])
+        );
+        assert_eq!(theme.line("Alvinson: hi", &speakers), Line::styled("Alvinson: hi", theme.narration));
+        assert_eq!(theme.line("[Ending: The Grove]", &speakers), Line::styled("[Ending: The Grove]", theme.narration));
+    }
+}
@@ -1,10 +1,10 @@
 #[cfg(not(target_arch = "wasm32"))]
 use std::io::{self, Write};
+use crate::config::GameConfig;
 #[cfg(not(target_arch = "wasm32"))]
-use std::thread;
+use crate::ui_trait::{GameUI, WaitForInput};
 #[cfg(not(target_arch = "wasm32"))]
-use std::time::Duration;
-use crate::config::GameConfig;
+use std::error::Error;
 
 const IDEAL_LINE_LENGTH: usize = 80;
 const MIN_MARGIN: usize = 4;
@@ -57,7 +57,8 @@ fn wrap_text(text: &str, width: usize) -> Vec<String> {
 #[cfg(not(target_arch = "wasm32"))]
 pub fn print_game_text(text: &str, config: &GameConfig) {
     use crate::config::UiMode;
-    use std::io::{self, Write};
+    // Plain output doesn't pace; page breaks just leave a blank line
+    let text = &crate::pacing::strip_pacing(text);
     if config.ui_mode == UiMode::Centered {
         let lines = wrap_and_center_text(text);
         for line in lines {
@@ -70,9 +71,9 @@ pub fn print_game_text(text: &str, config: &GameConfig) {
 
 #[cfg(not(target_arch = "wasm32"))]
 fn print_line(text: &str, config: &GameConfig) {
-    use std::io::{self, Write};
     use std::thread;
     use std::time::Duration;
+    use crate::pacing::reveal_step;
     if config.enable_typewriter {
         // Print leading whitespace instantly, then animate the content
         let leading_spaces = text.len() - text.trim_start().len();
@@ -81,11 +82,15 @@ fn print_line(text: &str, config: &GameConfig) {
             io::stdout().flush().unwrap();
         }
         
-        // Animate the actual content
-        for c in text.trim_start().chars() {
-            print!("{}", c);
+        // Animate the actual content a character, word or sentence at a time
+        let mut rest = text.trim_start();
+        while !rest.is_empty() {
+            let step = reveal_step(rest, &config.reveal);
+            let step_len = rest.char_indices().nth(step).map_or(rest.len(), |(index, _)| index);
+            print!("{}", &rest[..step_len]);
             io::stdout().flush().unwrap();
-            thread::sleep(Duration::from_millis(config.typewriter_speed_ms));
+            rest = &rest[step_len..];
+            thread::sleep(Duration::from_millis(config.typewriter_speed_ms * step as u64));
         }
         println!();
     } else {
@@ -96,20 +101,16 @@ fn print_line(text: &str, config: &GameConfig) {
 // Keep the old function for backwards compatibility
 #[cfg(not(target_arch = "wasm32"))]
 pub fn print_typewriter_effect(text: &str, config: &GameConfig) {
-    use std::io::{self, Write};
-    use std::thread;
-    use std::time::Duration;
     print_game_text(text, config);
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 pub fn get_user_input(config: &GameConfig) -> Result<String, io::Error> {
-    use std::io;
     use crate::config::UiMode;
     if config.ui_mode == UiMode::Centered {
         // Align with the choice margin
         let margin = get_content_margin();
-        print!("{}{}", " ".repeat(margin), "> ");
+        print!("{}> ", " ".repeat(margin));
     } else {
         print!("> ");
     }
@@ -120,9 +121,16 @@ pub fn get_user_input(config: &GameConfig) -> Result<String, io::Error> {
     Ok(input.trim().to_string())
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_text_input(prompt: &str, config: &GameConfig) -> Result<String, io::Error> {
+    if !prompt.trim().is_empty() {
+        print_game_text(&format!("\n{}", prompt), config);
+    }
+    get_user_input(config)
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub fn wait_for_enter() {
-    use std::io::{self, Write};
     print!("\nPress Enter to continue...");
     io::stdout().flush().unwrap();
     
@@ -215,4 +223,76 @@ pub fn parse_user_choice(input: &str, choices: &[&crate::world::Choice], config:
     }
     
     None
-}
\ No newline at end of file
+}
+
+/// Line-by-line stdin/stdout front end used by the plain UI mode.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct PlainUi {
+    config: GameConfig,
+    current_choices: Vec<crate::world::Choice>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PlainUi {
+    pub fn new(config: GameConfig) -> Self {
+        PlainUi {
+            config,
+            current_choices: Vec::new(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl GameUI for PlainUi {
+    fn display_texts(&mut self, texts: &[String]) -> Result<(), Box<dyn Error>> {
+        // Plain mode prints everything straight away; the typewriter provides the pacing
+        for text in texts {
+            print_typewriter_effect(&format!("\n{}", text), &self.config);
+        }
+        Ok(())
+    }
+    
+    fn display_text(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
+        print_game_text(text, &self.config);
+        Ok(())
+    }
+    
+    fn display_choices(&mut self, choices: &[&crate::world::Choice]) {
+        self.current_choices = choices.iter().map(|choice| (*choice).clone()).collect();
+        display_choices(choices, &self.config);
+    }
+    
+    fn get_user_choice(&mut self) -> Result<usize, Box<dyn Error>> {
+        let input = get_user_input(&self.config)?;
+        let choices: Vec<&crate::world::Choice> = self.current_choices.iter().collect();
+        parse_user_choice(&input, &choices, &self.config).ok_or_else(|| "Invalid choice".into())
+    }
+    
+    fn get_text_input(&mut self, prompt: &str) -> Result<String, Box<dyn Error>> {
+        Ok(get_text_input(prompt, &self.config)?.trim().to_string())
+    }
+    
+    fn clear_choices(&mut self) {
+        self.current_choices.clear();
+    }
+    
+    fn show_toast(&mut self, message: &str) {
+        print_game_text(&format!("\n{}", message), &self.config);
+    }
+    
+    fn add_separator(&mut self) {
+        // Room descriptions already start with a blank line in plain mode
+    }
+    
+    fn cleanup(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl WaitForInput for PlainUi {
+    fn wait_for_continue(&mut self) -> Result<(), Box<dyn Error>> {
+        // Plain mode never paused between rooms; keep it that way
+        Ok(())
+    }
+}
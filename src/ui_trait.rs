@@ -18,9 +18,15 @@ pub trait GameUI {
     /// Get the user's choice selection (returns choice index).
     fn get_user_choice(&mut self) -> Result<usize, Box<dyn Error>>;
     
+    /// Ask the user a question and return the free text they typed (trimmed).
+    fn get_text_input(&mut self, prompt: &str) -> Result<String, Box<dyn Error>>;
+    
     /// Clear currently displayed choices.
     fn clear_choices(&mut self);
     
+    /// Briefly show a notification outside the story text (achievement unlocks, etc.).
+    fn show_toast(&mut self, message: &str);
+    
     /// Add a visual separator (for room transitions).
     fn add_separator(&mut self);
     
@@ -1,9 +1,10 @@
 use ratzilla::{DomBackend, WebRenderer};
 use ratzilla::event::{KeyCode, KeyEvent};
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    text::Line,
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame, Terminal,
 };
 use std::rc::Rc;
@@ -12,17 +13,39 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
 use crate::config::GameConfig;
+use crate::keymap::{Key, KeyAction, Keymap};
 use crate::world::{Choice, World};
 use crate::game::{GameState, get_available_choices, get_room_description};
+use crate::engine::{Engine, GameEvent};
+use crate::observer::Observers;
+use crate::profile::PlayerProfile;
+use crate::saves::{export_code, import_code, PlayClock, SaveStore, SlotInfo, AUTO_SAVE, QUICK_SAVE};
+use crate::pacing::{reveal_step, split_pacing, strip_pacing, PacedText};
+use crate::theme::Theme;
 use crate::ui_trait::{GameUI, WaitForInput};
 use std::error::Error;
 
+// One stretch of text in a sequence; the player continues between them
+struct PendingText {
+    text: String,
+    new_page: bool, // Starts after a `---` page break
+}
+
+// A list of saves the player picks from; `None` starts a new game instead
+struct SlotPicker {
+    title: String,
+    slots: Vec<Option<String>>,
+    labels: Vec<String>,
+    selected: usize,
+}
+
 // Global reference to the app for typewriter timer callbacks
 static mut TYPEWRITER_APP: Option<Rc<App>> = None;
 
 pub struct App {
     world: World,
     game_state: RefCell<GameState>,
+    observers: RefCell<Observers>,          // Told about every choice, undo and load
     all_text: RefCell<Vec<String>>,
     current_choices: RefCell<Vec<String>>,
     available_choices: RefCell<Vec<Choice>>,
@@ -33,19 +56,41 @@ pub struct App {
     typewriter_complete: RefCell<bool>,     // Whether current typewriter effect is complete
     typewriter_current_line: RefCell<usize>, // Current line being typed
     typewriter_current_char: RefCell<usize>, // Current character position in line
-    pending_texts: RefCell<Vec<String>>,    // Texts waiting to be displayed with pauses
+    typewriter_pauses: RefCell<Vec<(usize, usize, u64)>>, // Author pauses as (line, char, milliseconds)
+    typewriter_line_started: RefCell<bool>, // Whether the current line is already in all_text
+    pending_texts: RefCell<Vec<PendingText>>, // Texts waiting to be displayed with pauses
     pending_text_index: RefCell<usize>,     // Current index in pending_texts
     waiting_for_continue: RefCell<bool>,    // Whether we're waiting for user to continue
     pending_room_change: RefCell<bool>,     // Whether there's a room change pending after text sequence
     room_text_sequence: RefCell<bool>,      // Whether current text sequence is for room description
+    text_input: RefCell<Option<String>>,    // Answer being typed while the story asks a question
+    speakers: Vec<String>,                  // Names highlighted at the start of dialogue lines
+    theme: Theme,
+    keymap: Keymap,
+    show_help: RefCell<bool>,               // Whether the key bindings are shown over the story
+    story_name: String,                     // Names the story's saves and profile in localStorage
+    profile: RefCell<PlayerProfile>,        // Endings and achievements found across playthroughs
+    toast: RefCell<Option<String>>,         // Notification shown until the next choice
+    saves: SaveStore,                       // Save slots kept in localStorage
+    clock: RefCell<PlayClock>,              // Time played, carried over from the loaded save
+    slot_picker: RefCell<Option<SlotPicker>>, // Save list shown over the story
+    save_name: RefCell<Option<String>>,     // Name being typed for a new save
     config: GameConfig,
 }
 
 impl App {
-    pub fn new(world: World, game_state: GameState, config: GameConfig) -> Self {
+    pub fn new(story_name: &str, world: World, game_state: GameState, config: GameConfig, observers: Observers) -> Self {
+        let speakers = world.speakers();
+        let profile = PlayerProfile::load_from_local_storage(story_name).unwrap_or_else(|e| {
+            web_sys::console::warn_1(&format!("Starting a new profile: {}", e).into());
+            PlayerProfile::default()
+        });
+        let theme = config.theme().unwrap_or_default();
+        let keymap = config.keymap().unwrap_or_default();
         let app = App {
             world,
             game_state: RefCell::new(game_state),
+            observers: RefCell::new(observers),
             all_text: RefCell::new(Vec::new()),
             current_choices: RefCell::new(Vec::new()),
             available_choices: RefCell::new(Vec::new()),
@@ -56,16 +101,31 @@ impl App {
             typewriter_complete: RefCell::new(true),
             typewriter_current_line: RefCell::new(0),
             typewriter_current_char: RefCell::new(0),
+            typewriter_pauses: RefCell::new(Vec::new()),
+            typewriter_line_started: RefCell::new(false),
             pending_texts: RefCell::new(Vec::new()),
             pending_text_index: RefCell::new(0),
             waiting_for_continue: RefCell::new(false),
             pending_room_change: RefCell::new(false),
             room_text_sequence: RefCell::new(false),
+            text_input: RefCell::new(None),
+            speakers,
+            theme,
+            keymap,
+            show_help: RefCell::new(false),
+            story_name: story_name.to_string(),
+            profile: RefCell::new(profile),
+            toast: RefCell::new(None),
+            saves: SaveStore::in_local_storage(story_name),
+            clock: RefCell::new(PlayClock::start()),
+            slot_picker: RefCell::new(None),
+            save_name: RefCell::new(None),
             config,
         };
 
         // Initialize the game with welcome message and first room
         app.initialize_game();
+        app.offer_continue();
         app
     }
 
@@ -153,128 +213,399 @@ impl App {
             let choice = available_choices[selected_index].clone();
             drop(available_choices);
             
-            // Store the current room ID before executing actions
-            let previous_room_id = self.game_state.borrow().current_room_id.clone();
-            
-            // Prepare initial text to display
-            let initial_text = vec![
-                format!("> {}", choice.text),
-                "".to_string(),
-            ];
-            
             // Enable auto-scroll when new content is added
             *self.auto_scroll.borrow_mut() = true;
+            *self.toast.borrow_mut() = None;
             
-            // Execute the choice actions using the unified approach
-            let mut game_state = self.game_state.borrow_mut();
-            let has_quit = game_state.has_quit;
-            
-            // Use a simplified version of execute_choice for web that handles text sequencing
-            let action_texts = self.execute_actions_for_web(&choice, &mut game_state);
-            let current_room_id = game_state.current_room_id.clone(); // Get room ID AFTER actions
-            drop(game_state);
-            
-            // Add initial text to display
-            self.all_text.borrow_mut().extend(initial_text);
-            
-            // Check if game has ended
-            if has_quit {
-                self.all_text.borrow_mut().push("🎉 Thank you for playing!".to_string());
-                self.current_choices.borrow_mut().clear();
-                self.available_choices.borrow_mut().clear();
-                return;
+            // Run the choice through the shared engine
+            let previous_room = self.game_state.borrow().current_room_id.clone();
+            let result = self.observers.borrow_mut().choose(&self.world, &mut self.game_state.borrow_mut(), choice.id.clone());
+            if result.is_ok() {
+                self.auto_save(&previous_room);
             }
             
-            // Check if we have text actions
-            let has_text_actions = !action_texts.is_empty();
-            
-            // Start the text sequence if there are texts to display
-            if has_text_actions {
-                *self.room_text_sequence.borrow_mut() = false; // This is a choice action text sequence
-                self.start_text_sequence(action_texts);
+            // Show what the player picked
+            {
+                let mut all_text = self.all_text.borrow_mut();
+                all_text.push(format!("> {}", choice.text));
+                all_text.push("".to_string());
             }
             
-            // Handle room change and choice updates
-            if previous_room_id != current_room_id {
-                *self.pending_room_change.borrow_mut() = true;
-                // If we're not in a text sequence, handle room change immediately
-                if !has_text_actions {
-                    self.handle_room_change(current_room_id);
-                    *self.pending_room_change.borrow_mut() = false;
-                } 
-                // If we have text actions, room change will be handled after text sequence completes
-            } else {
-                *self.pending_room_change.borrow_mut() = false;
-                // No room change, update choices if not in text sequence
-                if !has_text_actions {
-                    self.update_choices();
-                }
+            match result {
+                Ok(events) => self.present_events(events),
+                Err(e) => self.all_text.borrow_mut().push(format!("Error executing choice: {}", e)),
             }
         }
     }
     
-    fn execute_actions_for_web(&self, choice: &Choice, game_state: &mut GameState) -> Vec<String> {
-        use crate::world::Action;
-        use crate::game::check_single_condition;
-        
-        let mut text_actions = Vec::new();
-        
-        // Execute immediate actions first
-        for action in &choice.actions {
-            match action {
-                Action::GoTo(room_id) => game_state.current_room_id = room_id.clone(),
-                Action::SetFlag(flag_id) => {
-                    game_state.flags.insert(flag_id.clone());
-                }
-                Action::RemoveFlag(flag_id) => {
-                    game_state.flags.remove(flag_id);
-                }
-                Action::Quit => game_state.has_quit = true,
-                Action::IncrementCounter(counter) => {
-                    let old_value = *game_state.counters.get(counter).unwrap_or(&0);
-                    *game_state.counters.entry(counter.clone()).or_insert(0) += 1;
-                    let new_value = *game_state.counters.get(counter).unwrap();
-                    text_actions.push(format!("[{}: {} → {}]", counter, old_value, new_value));
-                }
-                Action::DecrementCounter(counter) => {
-                    let old_value = *game_state.counters.get(counter).unwrap_or(&0);
-                    *game_state.counters.entry(counter.clone()).or_insert(0) -= 1;
-                    let new_value = *game_state.counters.get(counter).unwrap();
-                    text_actions.push(format!("[{}: {} → {}]", counter, old_value, new_value));
-                }
-                Action::SetCounter(counter, value) => {
-                    let old_value = *game_state.counters.get(counter).unwrap_or(&0);
-                    game_state.counters.insert(counter.clone(), *value);
-                    text_actions.push(format!("[{}: {} → {}]", counter, old_value, value));
-                }
-                Action::DisplayText(text) => {
-                    text_actions.push(text.clone());
-                }
-                Action::DisplayTextConditional { condition, text_if_true, text_if_false } => {
-                    let text = if check_single_condition(condition, game_state) {
-                        text_if_true.clone()
-                    } else {
-                        text_if_false.clone()
-                    };
-                    text_actions.push(text);
+    fn present_events(&self, events: Vec<GameEvent>) {
+        let mut action_texts = Vec::new();
+        let mut new_room_id = None;
+        let mut has_quit = false;
+        
+        let unlocked = self.profile.borrow_mut().record_achievements(&events);
+        let found_ending = self.profile.borrow_mut().record_endings(&events);
+        let profile_changed = found_ending || !unlocked.is_empty();
+        for title in unlocked {
+            self.set_toast(format!("🏆 Achievement unlocked: {}", title));
+        }
+        if profile_changed {
+            self.save_profile();
+        }
+        
+        for event in events {
+            if let Some(text) = event.display_text() {
+                action_texts.push(text);
+                continue;
+            }
+            match event {
+                GameEvent::InputRequested { prompt, .. } => {
+                    if !prompt.trim().is_empty() {
+                        action_texts.push(prompt);
+                    }
+                    *self.text_input.borrow_mut() = Some(String::new());
                 }
+                GameEvent::RoomChanged { to, .. } => new_room_id = Some(to),
+                GameEvent::Quit => has_quit = true,
+                _ => {}
+            }
+        }
+        
+        // Check if game has ended
+        if has_quit {
+            let mut all_text = self.all_text.borrow_mut();
+            all_text.extend(action_texts);
+            all_text.push("🎉 Thank you for playing!".to_string());
+            if !self.world.endings.is_empty() {
+                all_text.push("".to_string());
+                all_text.extend(self.profile.borrow().endings_gallery(&self.world));
             }
+            drop(all_text);
+            self.current_choices.borrow_mut().clear();
+            self.available_choices.borrow_mut().clear();
+            return;
+        }
+        
+        // Check if we have text actions
+        let has_text_actions = !action_texts.is_empty();
+        
+        // Start the text sequence if there are texts to display
+        if has_text_actions {
+            *self.room_text_sequence.borrow_mut() = false; // This is a choice action text sequence
+            self.start_text_sequence(action_texts);
+        }
+        
+        // Choices come back once the player has answered the question
+        if self.is_awaiting_input() {
+            self.current_choices.borrow_mut().clear();
+            self.available_choices.borrow_mut().clear();
+            *self.pending_room_change.borrow_mut() = false;
+            return;
         }
         
-        text_actions
+        // Handle room change and choice updates
+        if let Some(room_id) = new_room_id {
+            *self.pending_room_change.borrow_mut() = true;
+            // If we're not in a text sequence, handle room change immediately
+            if !has_text_actions {
+                self.handle_room_change(room_id);
+                *self.pending_room_change.borrow_mut() = false;
+            } 
+            // If we have text actions, room change will be handled after text sequence completes
+        } else {
+            *self.pending_room_change.borrow_mut() = false;
+            // No room change, update choices if not in text sequence
+            if !has_text_actions {
+                self.update_choices();
+            }
+        }
     }
     
-    fn handle_room_change(&self, room_id: String) {
-        let current_room = match self.world.rooms.get(&room_id) {
-            Some(room) => room,
-            None => {
-                self.all_text.borrow_mut().push(format!("Error: Room '{}' not found!", room_id));
+    fn undo_last_choice(&self) {
+        let result = self.observers.borrow_mut().rewind(&self.world, &mut self.game_state.borrow_mut(), 1);
+        *self.auto_scroll.borrow_mut() = true;
+        
+        match result {
+            Ok(_) => {
+                self.all_text.borrow_mut().push("⏪ Undid the last choice.".to_string());
+                let room_id = self.game_state.borrow().current_room_id.clone();
+                self.handle_room_change(room_id);
+            }
+            Err(e) => self.all_text.borrow_mut().push(format!("Failed to undo: {}", e)),
+        }
+    }
+    
+    fn set_toast(&self, message: String) {
+        *self.toast.borrow_mut() = Some(message);
+    }
+
+    fn show_achievements(&self) {
+        if self.world.achievements.is_empty() {
+            return;
+        }
+        let mut all_text = self.all_text.borrow_mut();
+        all_text.push("".to_string());
+        all_text.extend(self.profile.borrow().achievements_list(&self.world));
+        drop(all_text);
+        *self.auto_scroll.borrow_mut() = true;
+    }
+
+    fn save_profile(&self) {
+        if let Err(e) = self.profile.borrow().save_to_local_storage(&self.story_name) {
+            self.set_toast(format!("Failed to save your endings and achievements: {}", e));
+        }
+    }
+
+    fn auto_save(&self, previous_room: &str) {
+        if !self.config.auto_save {
+            return;
+        }
+        let playtime = self.clock.borrow().elapsed();
+        let result = self.saves.auto_save(&self.world, &self.game_state.borrow(), previous_room, self.config.auto_save_every_choice, playtime);
+        if let Err(e) = result {
+            self.set_toast(format!("Failed to autosave: {}", e));
+        }
+    }
+
+    // Offer the autosave and quick save, most recent first, ahead of a new game
+    fn offer_continue(&self) {
+        let slots: Vec<SlotInfo> = self
+            .saves
+            .list()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|slot| slot.name == AUTO_SAVE || slot.name == QUICK_SAVE)
+            .collect();
+        if slots.is_empty() {
+            return;
+        }
+        let mut labels: Vec<String> = slots
+            .iter()
+            .map(|slot| {
+                let action = if slot.name == AUTO_SAVE { "Continue" } else { "Load quick save" };
+                match &slot.metadata {
+                    Some(metadata) => format!("{} — {}", action, metadata.summary()),
+                    None => action.to_string(),
+                }
+            })
+            .collect();
+        labels.push("New game".to_string());
+        let mut slots: Vec<Option<String>> = slots.into_iter().map(|slot| Some(slot.name)).collect();
+        slots.push(None);
+        self.open_slot_picker("Welcome back", slots, labels);
+    }
+
+    fn open_load_picker(&self) {
+        let slots = match self.saves.list() {
+            Ok(slots) if slots.is_empty() => {
+                self.set_toast("No saves yet.".to_string());
+                return;
+            }
+            Ok(slots) => slots,
+            Err(e) => {
+                self.set_toast(format!("Failed to list saves: {}", e));
                 return;
             }
         };
+        let labels = slots.iter().map(|slot| slot.lines().join("\n")).collect();
+        let slots = slots.into_iter().map(|slot| Some(slot.name)).collect();
+        self.open_slot_picker("Load a save", slots, labels);
+    }
+
+    fn open_slot_picker(&self, title: &str, slots: Vec<Option<String>>, labels: Vec<String>) {
+        *self.slot_picker.borrow_mut() = Some(SlotPicker { title: title.to_string(), slots, labels, selected: 0 });
+    }
+
+    fn is_picking_slot(&self) -> bool {
+        self.slot_picker.borrow().is_some()
+    }
+
+    fn move_slot_selection(&self, down: bool) {
+        if let Some(picker) = self.slot_picker.borrow_mut().as_mut() {
+            picker.selected = if down {
+                (picker.selected + 1).min(picker.slots.len().saturating_sub(1))
+            } else {
+                picker.selected.saturating_sub(1)
+            };
+        }
+    }
+
+    fn confirm_slot(&self) {
+        let picker = self.slot_picker.borrow_mut().take();
+        if let Some(Some(slot)) = picker.and_then(|picker| picker.slots.get(picker.selected).cloned()) {
+            self.load_slot(&slot);
+        }
+    }
+
+    fn close_slot_picker(&self) {
+        *self.slot_picker.borrow_mut() = None;
+    }
+
+    fn load_slot(&self, slot: &str) {
+        let (save, mut notes) = match self.saves.restore(slot, &self.world) {
+            Ok(restored) => restored,
+            Err(e) => {
+                self.set_toast(format!("Failed to load save: {}", e));
+                return;
+            }
+        };
+        *self.clock.borrow_mut() = PlayClock::resume(save.playtime_secs());
+        notes.insert(0, "Game loaded.".to_string());
+        self.show_restored_game(save.state, notes);
+    }
+
+    // Drop whatever was on screen and start again from a loaded game
+    fn show_restored_game(&self, game_state: GameState, notes: Vec<String>) {
+        self.skip_typewriter();
+        self.pending_texts.borrow_mut().clear();
+        *self.pending_text_index.borrow_mut() = 0;
+        *self.waiting_for_continue.borrow_mut() = false;
+        *self.pending_room_change.borrow_mut() = false;
+        *self.text_input.borrow_mut() = game_state.pending_input.as_ref().map(|_| String::new());
+        self.observers.borrow_mut().load(&mut self.game_state.borrow_mut(), game_state);
+        self.current_choices.borrow_mut().clear();
+        self.available_choices.borrow_mut().clear();
+        self.enable_auto_scroll();
+        {
+            let mut all_text = self.all_text.borrow_mut();
+            all_text.clear();
+            all_text.extend(notes);
+        }
+        let room_id = self.game_state.borrow().current_room_id.clone();
+        self.handle_room_change(room_id);
+    }
+
+    fn export_save(&self) {
+        let code = match export_code(&self.world, &self.game_state.borrow()) {
+            Ok(code) => code,
+            Err(e) => {
+                self.set_toast(format!("Failed to export save: {}", e));
+                return;
+            }
+        };
+        {
+            let mut all_text = self.all_text.borrow_mut();
+            all_text.push("".to_string());
+            all_text.push("Your save code (press i to import it here or use 'import-save CODE' in the terminal):".to_string());
+            // Long codes are split over lines; import ignores the breaks
+            all_text.extend(code.as_bytes().chunks(60).filter_map(|chunk| std::str::from_utf8(chunk).ok()).map(str::to_string));
+        }
+        self.enable_auto_scroll();
+        // The browser's prompt shows the code selected, ready to copy
+        if let Some(window) = web_sys::window() {
+            let _ = window.prompt_with_message_and_default("Copy your save code:", &code);
+        }
+    }
+
+    fn import_save(&self) {
+        let code = match web_sys::window().and_then(|window| window.prompt_with_message("Paste a save code:").ok().flatten()) {
+            Some(code) if !code.trim().is_empty() => code,
+            _ => return,
+        };
+        match import_code(&self.world, &code) {
+            Ok((state, mut notes)) => {
+                notes.insert(0, "Save code imported.".to_string());
+                self.show_restored_game(state, notes);
+            }
+            Err(e) => self.set_toast(format!("Failed to import save code: {}", e)),
+        }
+    }
+
+    fn is_naming_save(&self) -> bool {
+        self.save_name.borrow().is_some()
+    }
+
+    fn start_naming_save(&self) {
+        *self.save_name.borrow_mut() = Some(String::new());
+    }
+
+    fn type_save_name_char(&self, c: char) {
+        if let Some(name) = self.save_name.borrow_mut().as_mut() {
+            name.push(c);
+        }
+    }
+
+    fn delete_save_name_char(&self) {
+        if let Some(name) = self.save_name.borrow_mut().as_mut() {
+            name.pop();
+        }
+    }
+
+    fn cancel_save(&self) {
+        *self.save_name.borrow_mut() = None;
+    }
+
+    // An empty name saves to the quick save slot
+    fn submit_save_name(&self) {
+        let name = match self.save_name.borrow().as_ref() {
+            Some(name) if name.trim().is_empty() => QUICK_SAVE.to_string(),
+            Some(name) => name.trim().to_string(),
+            None => return,
+        };
+        let playtime = self.clock.borrow().elapsed();
+        match self.saves.save(&name, &self.world, &self.game_state.borrow(), playtime) {
+            Ok(()) => {
+                *self.save_name.borrow_mut() = None;
+                self.set_toast(format!("Game saved to '{}'.", name));
+            }
+            // Keep the prompt open so the name can be fixed
+            Err(e) => self.set_toast(format!("Failed to save game: {}", e)),
+        }
+    }
+
+    fn is_awaiting_input(&self) -> bool {
+        self.text_input.borrow().is_some()
+    }
+    
+    fn type_input_char(&self, c: char) {
+        if let Some(buffer) = self.text_input.borrow_mut().as_mut() {
+            buffer.push(c);
+        }
+    }
+    
+    fn delete_input_char(&self) {
+        if let Some(buffer) = self.text_input.borrow_mut().as_mut() {
+            buffer.pop();
+        }
+    }
+    
+    fn submit_text_input(&self) {
+        let answer = match self.text_input.borrow().as_ref() {
+            Some(buffer) => buffer.trim().to_string(),
+            None => return,
+        };
+        if answer.is_empty() {
+            return;
+        }
+        *self.text_input.borrow_mut() = None;
+        
+        {
+            let mut all_text = self.all_text.borrow_mut();
+            all_text.push(format!("> {}", answer));
+            all_text.push("".to_string());
+        }
+        *self.auto_scroll.borrow_mut() = true;
         
+        let previous_room = self.game_state.borrow().current_room_id.clone();
+        let result = self.observers.borrow_mut().provide_input(&self.world, &mut self.game_state.borrow_mut(), &answer);
+        if result.is_ok() {
+            self.auto_save(&previous_room);
+        }
+        match result {
+            Ok(events) => self.present_events(events),
+            Err(e) => self.all_text.borrow_mut().push(format!("Error: {}", e)),
+        }
+    }
+    
+    fn handle_room_change(&self, room_id: String) {
+        if !self.world.rooms.contains_key(&room_id) {
+            self.all_text.borrow_mut().push(format!("Error: Room '{}' not found!", room_id));
+            return;
+        }
+        
+        // The room's description, or the conversation the player is having there
         let game_state = self.game_state.borrow();
-        let room_desc = get_room_description(current_room, &game_state);
+        let room_desc = Engine::describe(&self.world, &game_state).unwrap_or_default();
         drop(game_state);
         
         if room_desc.is_empty() {
@@ -349,6 +680,11 @@ impl App {
         }
     }
 
+    fn scroll_to_top(&self) {
+        *self.auto_scroll.borrow_mut() = false;
+        *self.scroll_offset.borrow_mut() = 0;
+    }
+
     fn enable_auto_scroll(&self) {
         *self.auto_scroll.borrow_mut() = true;
         *self.scroll_offset.borrow_mut() = 0;
@@ -356,22 +692,43 @@ impl App {
 
     fn add_text_with_typewriter(&self, new_text: Vec<String>) {
         if self.config.enable_typewriter && !new_text.is_empty() {
+            // Pull the author's pauses out of the text and remember where they go
+            let mut visible_lines = Vec::new();
+            let mut pauses = Vec::new();
+            for (line_index, line) in new_text.iter().enumerate() {
+                let mut visible = String::new();
+                for segment in split_pacing(line) {
+                    match segment {
+                        PacedText::Text(text) => visible.push_str(&text),
+                        PacedText::Pause(millis) => pauses.push((line_index, visible.chars().count(), millis)),
+                        PacedText::Wait | PacedText::PageBreak => {}
+                    }
+                }
+                visible_lines.push(visible);
+            }
+            
             // Start typewriter effect for new text
             *self.typewriter_complete.borrow_mut() = false;
-            *self.typewriter_text.borrow_mut() = new_text;
+            *self.typewriter_text.borrow_mut() = visible_lines;
+            *self.typewriter_pauses.borrow_mut() = pauses;
+            *self.typewriter_line_started.borrow_mut() = false;
             *self.typewriter_current_line.borrow_mut() = 0;
             *self.typewriter_current_char.borrow_mut() = 0;
             self.start_typewriter_effect();
         } else {
             // Add text immediately if typewriter is disabled or no text
-            self.all_text.borrow_mut().extend(new_text);
+            self.all_text.borrow_mut().extend(new_text.iter().map(|text| strip_pacing(text)));
         }
     }
 
     fn start_typewriter_effect(&self) {
+        let speed_ms = if self.config.typewriter_speed_ms == 0 { 1 } else { self.config.typewriter_speed_ms };
+        self.schedule_typewriter_tick(speed_ms);
+    }
+
+    fn schedule_typewriter_tick(&self, delay_ms: u64) {
         // Set up the timer for typewriter effect
         let window = web_sys::window().unwrap();
-        let speed_ms = if self.config.typewriter_speed_ms == 0 { 1 } else { self.config.typewriter_speed_ms };
         
         let closure = Closure::wrap(Box::new(move || {
             typewriter_tick();
@@ -379,7 +736,7 @@ impl App {
         
         window.set_timeout_with_callback_and_timeout_and_arguments_0(
             closure.as_ref().unchecked_ref(),
-            speed_ms as i32,
+            delay_ms as i32,
         ).unwrap();
         
         closure.forget(); // Prevent cleanup
@@ -410,6 +767,7 @@ impl App {
             }
             
             drop(all_text);
+            self.typewriter_pauses.borrow_mut().clear();
             *self.typewriter_complete.borrow_mut() = true;
         }
     }
@@ -432,29 +790,53 @@ impl App {
         let line = &typewriter_text[*current_line];
         let line_chars: Vec<char> = line.chars().collect();
         
-        if *current_char == 0 {
+        if *current_char == 0 && !*self.typewriter_line_started.borrow() {
             // Starting a new line - add it to all_text as empty initially
             self.all_text.borrow_mut().push(String::new());
+            *self.typewriter_line_started.borrow_mut() = true;
+        }
+
+        // Hold for an author's [pause N] before typing on
+        let pause_here = {
+            let mut pauses = self.typewriter_pauses.borrow_mut();
+            let position = pauses.iter().position(|&(line, ch, _)| line == *current_line && ch == *current_char);
+            position.map(|index| pauses.remove(index).2)
+        };
+        if let Some(millis) = pause_here {
+            self.schedule_typewriter_tick(millis);
+            return;
         }
 
+        let mut step = 1;
         if *current_char < line_chars.len() {
-            // Add next character to the current line
+            // Add the next character, word or sentence, stopping short of any pause
+            let rest: String = line_chars[*current_char..].iter().collect();
+            let next_pause = self.typewriter_pauses.borrow().iter()
+                .filter(|&&(line, ch, _)| line == *current_line && ch > *current_char)
+                .map(|&(_, ch, _)| ch)
+                .min();
+            step = reveal_step(&rest, &self.config.reveal);
+            if let Some(pause_char) = next_pause {
+                step = step.min(pause_char - *current_char);
+            }
             let all_text_len = self.all_text.borrow().len();
             if all_text_len > 0 {
                 let mut all_text = self.all_text.borrow_mut();
                 let last_line_idx = all_text_len - 1;
-                all_text[last_line_idx] = line_chars[..*current_char + 1].iter().collect();
+                all_text[last_line_idx] = line_chars[..*current_char + step].iter().collect();
             }
-            *current_char += 1;
+            *current_char += step;
         } else {
             // Current line complete, move to next
             *current_line += 1;
             *current_char = 0;
+            *self.typewriter_line_started.borrow_mut() = false;
         }
 
-        // Continue typewriter effect if not complete
+        // Continue typewriter effect if not complete, taking longer after a longer step
         if *current_line < typewriter_text.len() {
-            self.start_typewriter_effect();
+            let speed_ms = if self.config.typewriter_speed_ms == 0 { 1 } else { self.config.typewriter_speed_ms };
+            self.schedule_typewriter_tick(speed_ms * step as u64);
         } else {
             *self.typewriter_complete.borrow_mut() = true;
         }
@@ -465,7 +847,25 @@ impl App {
             return;
         }
         
-        *self.pending_texts.borrow_mut() = texts;
+        // [wait] and --- split a text into stretches the player continues between
+        let mut pending = Vec::new();
+        for text in texts {
+            let mut current = String::new();
+            let mut new_page = false;
+            for segment in split_pacing(&text) {
+                match segment {
+                    PacedText::Text(part) => current.push_str(&part),
+                    PacedText::Pause(millis) => current.push_str(&format!("[pause {}]", millis)),
+                    PacedText::Wait | PacedText::PageBreak => {
+                        pending.push(PendingText { text: std::mem::take(&mut current), new_page });
+                        new_page = segment == PacedText::PageBreak;
+                    }
+                }
+            }
+            pending.push(PendingText { text: current, new_page });
+        }
+        
+        *self.pending_texts.borrow_mut() = pending;
         *self.pending_text_index.borrow_mut() = 0;
         *self.waiting_for_continue.borrow_mut() = false;
         // Note: room_text_sequence flag is set by the caller if needed
@@ -480,9 +880,17 @@ impl App {
         let total_texts = pending_texts.len();
         
         if *pending_index < total_texts {
-            let text = pending_texts[*pending_index].clone();
+            let text = pending_texts[*pending_index].text.clone();
+            let new_page = pending_texts[*pending_index].new_page;
             drop(pending_texts);
             
+            if new_page {
+                let mut all_text = self.all_text.borrow_mut();
+                all_text.push("".to_string());
+                all_text.push("─".repeat(60));
+                all_text.push("".to_string());
+            }
+            
             // Display this text with typewriter effect
             self.add_text_with_typewriter(vec![text]);
             
@@ -509,7 +917,9 @@ impl App {
         let was_room_sequence = *self.room_text_sequence.borrow();
         *self.room_text_sequence.borrow_mut() = false;
         
-        if was_room_sequence {
+        if self.is_awaiting_input() {
+            // Choices come back once the player has answered the question
+        } else if was_room_sequence {
             // Room description sequence complete, update choices
             self.update_choices();
         } else if *self.pending_room_change.borrow() {
@@ -538,12 +948,43 @@ impl App {
     }
     
 
+    // The web build has no endings list and nothing to quit to
+    fn help_lines(&self) -> Vec<String> {
+        let mut hidden = KeyAction::unavailable_in(&self.world);
+        hidden.extend([KeyAction::Endings, KeyAction::Quit]);
+        self.keymap.help_lines(&hidden)
+    }
+
+    fn toggle_help(&self) {
+        let mut show_help = self.show_help.borrow_mut();
+        *show_help = !*show_help;
+    }
+
+    fn is_showing_help(&self) -> bool {
+        *self.show_help.borrow()
+    }
+
+    fn key_action(&self, code: &KeyCode) -> Option<KeyAction> {
+        key_of(code).and_then(|key| self.keymap.action(key))
+    }
+
     pub fn render(&self, f: &mut Frame) {
         let all_text = self.all_text.borrow();
         let current_choices = self.current_choices.borrow();
         let selected_choice = *self.selected_choice.borrow();
+        let awaiting_input = self.is_awaiting_input();
+        let theme = &self.theme;
+        f.render_widget(Block::default().style(theme.narration), f.area());
 
-        let main_layout = if !current_choices.is_empty() {
+        let main_layout = if awaiting_input {
+            Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Min(10),        // Story area
+                    Constraint::Length(3),      // Input area
+                ])
+                .split(f.area())
+        } else if !current_choices.is_empty() {
             Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
@@ -653,17 +1094,38 @@ impl App {
             }
         };
 
-        let paragraph = Paragraph::new(content_text)
-            .block(Block::default().borders(Borders::ALL).title("Restoration Project"))
+        let content_lines: Vec<Line> = content_text
+            .lines()
+            .map(|line| theme.line(line, &self.speakers))
+            .collect();
+
+        let paragraph = Paragraph::new(content_lines)
+            .block(Block::default().borders(Borders::ALL).border_style(theme.border).title("Restoration Project"))
             .wrap(Wrap { trim: true })
-            .style(Style::default().fg(Color::White));
+            .style(theme.narration);
 
         f.render_widget(paragraph, content_area);
 
         // Render choices if available, or prompts if waiting/typing
         let waiting = self.is_waiting_for_continue();
         let typewriter_active = !self.is_typewriter_complete();
-        if !current_choices.is_empty() && !waiting && !typewriter_active {
+        if awaiting_input && !waiting && !typewriter_active {
+            let input_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Percentage(5),   // Left margin
+                    Constraint::Percentage(90),  // Input content
+                    Constraint::Percentage(5),   // Right margin
+                ])
+                .split(main_layout[1]);
+
+            let buffer = self.text_input.borrow().clone().unwrap_or_default();
+            let input_box = Paragraph::new(format!("> {}_", buffer))
+                .block(Block::default().borders(Borders::ALL).border_style(theme.border).title("Type your answer + Enter"))
+                .style(theme.narration);
+
+            f.render_widget(input_box, input_chunks[1]);
+        } else if !current_choices.is_empty() && !waiting && !typewriter_active {
             let choices_area = main_layout[1];
             
             let choices_chunks = Layout::default()
@@ -682,18 +1144,22 @@ impl App {
                 .iter()
                 .enumerate()
                 .map(|(i, choice)| {
-                    let style = if i == selected_choice {
-                        Style::default().fg(Color::Black).bg(Color::White).add_modifier(Modifier::BOLD)
-                    } else {
-                        Style::default().fg(Color::White)
-                    };
+                    let style = if i == selected_choice { theme.selection } else { theme.choice };
                     ListItem::new(choice.as_str()).style(style)
                 })
                 .collect();
 
+            let keymap = &self.keymap;
+            let title = format!(
+                "Use {}{} + {}, or press 1-9 | {} for all keys",
+                keymap.label(KeyAction::SelectPrev),
+                keymap.label(KeyAction::SelectNext),
+                keymap.label(KeyAction::Confirm),
+                keymap.label(KeyAction::Menu),
+            );
             let choices_list = List::new(choice_items)
-                .block(Block::default().borders(Borders::ALL).title("Use ↑↓ arrow keys + Enter, or press 1-9"))
-                .style(Style::default().fg(Color::White));
+                .block(Block::default().borders(Borders::ALL).border_style(theme.border).title(title))
+                .style(theme.narration);
 
             f.render_widget(choices_list, choices_content_area);
         } else if waiting {
@@ -711,9 +1177,9 @@ impl App {
 
             let continue_content_area = continue_chunks[1];
 
-            let continue_prompt = Paragraph::new("Press Enter to continue...")
-                .block(Block::default().borders(Borders::ALL))
-                .style(Style::default().fg(Color::Yellow));
+            let continue_prompt = Paragraph::new(format!("Press {} to continue...", self.keymap.label(KeyAction::SkipText)))
+                .block(Block::default().borders(Borders::ALL).border_style(theme.border))
+                .style(theme.narration.patch(theme.border));
 
             f.render_widget(continue_prompt, continue_content_area);
         } else if typewriter_active {
@@ -732,11 +1198,43 @@ impl App {
             let skip_content_area = skip_chunks[1];
 
             let skip_prompt = Paragraph::new("Press any key to skip...")
-                .block(Block::default().borders(Borders::ALL))
-                .style(Style::default().fg(Color::Cyan));
+                .block(Block::default().borders(Borders::ALL).border_style(theme.border))
+                .style(theme.narration.patch(theme.border));
 
             f.render_widget(skip_prompt, skip_content_area);
         }
+
+        if *self.show_help.borrow() {
+            draw_help(f, &self.help_lines(), theme);
+        } else if let Some(picker) = self.slot_picker.borrow().as_ref() {
+            draw_slot_picker(f, picker, theme);
+        } else if let Some(name) = self.save_name.borrow().as_ref() {
+            let area = f.area();
+            let width = (area.width * 3 / 5).max(20).min(area.width);
+            let prompt_area = Rect::new((area.width - width) / 2, area.height.saturating_sub(3) / 2, width, 3.min(area.height));
+            let prompt = Paragraph::new(format!("> {}_", name))
+                .block(Block::default().borders(Borders::ALL).border_style(theme.border).title(format!("Name this save (Enter for '{}') | Esc to cancel", QUICK_SAVE)))
+                .style(theme.narration);
+            f.render_widget(Clear, prompt_area);
+            f.render_widget(prompt, prompt_area);
+        }
+
+        // Toast notification in the top-right corner
+        if let Some(message) = self.toast.borrow().as_ref() {
+            let area = f.area();
+            let width = (message.chars().count() as u16 + 4).min(area.width);
+            let toast_area = Rect {
+                x: area.x + area.width - width,
+                y: area.y,
+                width,
+                height: 3.min(area.height),
+            };
+            let toast = Paragraph::new(message.as_str())
+                .block(Block::default().borders(Borders::ALL))
+                .style(theme.narration.patch(theme.border).add_modifier(Modifier::BOLD));
+            f.render_widget(Clear, toast_area);
+            f.render_widget(toast, toast_area);
+        }
     }
 }
 
@@ -747,7 +1245,7 @@ impl GameUI for App {
     }
     
     fn display_text(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
-        self.all_text.borrow_mut().push(text.to_string());
+        self.all_text.borrow_mut().push(strip_pacing(text));
         Ok(())
     }
     
@@ -779,6 +1277,20 @@ impl GameUI for App {
         Ok(*self.selected_choice.borrow())
     }
     
+    fn get_text_input(&mut self, prompt: &str) -> Result<String, Box<dyn Error>> {
+        // Like choices, typed answers arrive through the event system;
+        // return whatever has been typed so far
+        if !prompt.trim().is_empty() {
+            self.all_text.borrow_mut().push(prompt.to_string());
+        }
+        let buffer = self.text_input.borrow().clone();
+        Ok(buffer.unwrap_or_default())
+    }
+    
+    fn show_toast(&mut self, message: &str) {
+        self.set_toast(message.to_string());
+    }
+    
     fn clear_choices(&mut self) {
         self.current_choices.borrow_mut().clear();
         self.available_choices.borrow_mut().clear();
@@ -807,6 +1319,62 @@ impl WaitForInput for App {
     }
 }
 
+fn draw_help(f: &mut Frame, lines: &[String], theme: &Theme) {
+    let area = f.area();
+    let width = (lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u16 + 4).min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let help_area = Rect::new((area.width - width) / 2, (area.height - height) / 2, width, height);
+
+    let help = Paragraph::new(lines.iter().map(|line| Line::raw(line.as_str())).collect::<Vec<_>>())
+        .block(Block::default().borders(Borders::ALL).border_style(theme.border).title("Keys | any key to close"))
+        .style(theme.narration);
+    f.render_widget(Clear, help_area);
+    f.render_widget(help, help_area);
+}
+
+fn key_of(code: &KeyCode) -> Option<Key> {
+    let key = match code {
+        KeyCode::Char(c) => Key::from_char(*c),
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Esc => Key::Esc,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Tab => Key::Tab,
+        _ => return None,
+    };
+    Some(key)
+}
+
+fn draw_slot_picker(f: &mut Frame, picker: &SlotPicker, theme: &Theme) {
+    let area = f.area();
+    let item_lines: usize = picker.labels.iter().map(|label| label.lines().count().max(1)).sum();
+    let width = (area.width * 4 / 5).max(20).min(area.width);
+    let height = (item_lines as u16 + 2).min(area.height);
+    let picker_area = Rect::new((area.width - width) / 2, (area.height - height) / 2, width, height);
+
+    let items: Vec<ListItem> = picker
+        .labels
+        .iter()
+        .enumerate()
+        .map(|(i, label)| {
+            let style = if i == picker.selected { theme.selection } else { theme.choice };
+            ListItem::new(label.as_str()).style(style)
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).border_style(theme.border).title(format!("{} | ↑↓ + Enter | Esc to cancel", picker.title)))
+        .style(theme.narration);
+    f.render_widget(Clear, picker_area);
+    f.render_widget(list, picker_area);
+}
+
 // Global typewriter tick function that can be called from JavaScript timers
 fn typewriter_tick() {
     unsafe {
@@ -816,11 +1384,11 @@ fn typewriter_tick() {
     }
 }
 
-pub fn run_web_game(world: World, game_state: GameState, config: GameConfig) {
+pub fn run_web_game(story_name: &str, world: World, game_state: GameState, config: GameConfig, observers: Observers) {
     let backend = DomBackend::new().expect("Failed to create DOM backend");
     let terminal = Terminal::new(backend).expect("Failed to create terminal");
     
-    let app = Rc::new(App::new(world, game_state, config));
+    let app = Rc::new(App::new(story_name, world, game_state, config, observers));
     let app_clone = app.clone();
     
     // Set global reference for typewriter callbacks
@@ -830,83 +1398,82 @@ pub fn run_web_game(world: World, game_state: GameState, config: GameConfig) {
     
     // Set up event handling
     terminal.on_key_event(move |event| {
-        match event.code {
-            KeyCode::Up => {
-                if app_clone.is_typewriter_complete() {
-                    app_clone.move_selection_up();
-                } else {
-                    app_clone.skip_typewriter();
-                }
-            }
-            KeyCode::Down => {
-                if app_clone.is_typewriter_complete() {
-                    app_clone.move_selection_down();
-                } else {
-                    app_clone.skip_typewriter();
-                }
-            }
-            KeyCode::Enter => {
-                if app_clone.is_typewriter_complete() {
-                    if app_clone.is_waiting_for_continue() {
-                        app_clone.continue_text_sequence();
-                    } else {
-                        app_clone.select_choice();
-                    }
-                } else {
-                    app_clone.skip_typewriter();
-                }
-            }
-            KeyCode::PageUp => {
-                if app_clone.is_typewriter_complete() {
-                    app_clone.scroll_up();
-                } else {
-                    app_clone.skip_typewriter();
-                }
+        // The save list and the save name prompt sit over the story and take every key
+        if app_clone.is_picking_slot() {
+            match app_clone.key_action(&event.code) {
+                _ if matches!(event.code, KeyCode::Esc) => app_clone.close_slot_picker(),
+                Some(KeyAction::SelectPrev | KeyAction::ScrollUp) => app_clone.move_slot_selection(false),
+                Some(KeyAction::SelectNext | KeyAction::ScrollDown) => app_clone.move_slot_selection(true),
+                Some(KeyAction::Confirm) => app_clone.confirm_slot(),
+                _ => {}
             }
-            KeyCode::PageDown => {
-                if app_clone.is_typewriter_complete() {
-                    app_clone.scroll_down();
-                } else {
-                    app_clone.skip_typewriter();
-                }
+            return;
+        }
+        if app_clone.is_naming_save() {
+            match event.code {
+                KeyCode::Char(c) => app_clone.type_save_name_char(c),
+                KeyCode::Backspace => app_clone.delete_save_name_char(),
+                KeyCode::Enter => app_clone.submit_save_name(),
+                KeyCode::Esc => app_clone.cancel_save(),
+                _ => {}
             }
-            KeyCode::End => {
-                if app_clone.is_typewriter_complete() {
-                    app_clone.enable_auto_scroll();
-                } else {
-                    app_clone.skip_typewriter();
-                }
+            return;
+        }
+        // While the story is asking a question, keys type the answer
+        if app_clone.is_awaiting_input() && app_clone.is_typewriter_complete() && !app_clone.is_waiting_for_continue() {
+            match event.code {
+                KeyCode::Char(c) => app_clone.type_input_char(c),
+                KeyCode::Backspace => app_clone.delete_input_char(),
+                KeyCode::Enter => app_clone.submit_text_input(),
+                code => match app_clone.key_action(&code) {
+                    Some(KeyAction::PageUp) => app_clone.scroll_up(),
+                    Some(KeyAction::PageDown) => app_clone.scroll_down(),
+                    _ => {}
+                },
             }
-            KeyCode::Char(c) => {
-                if app_clone.is_typewriter_complete() {
-                    match c {
-                        c if c.is_ascii_digit() => {
-                            if let Some(digit) = c.to_digit(10) {
-                                app_clone.select_by_number(digit as usize);
-                            }
-                        }
-                        'k' | 'K' => {
-                            app_clone.scroll_up();
-                        }
-                        'j' | 'J' => {
-                            app_clone.scroll_down();
-                        }
-                        ' ' => {
-                            app_clone.enable_auto_scroll();
-                        }
-                        _ => {}
+            return;
+        }
+        // Any key closes the help overlay
+        if app_clone.is_showing_help() {
+            app_clone.toggle_help();
+            return;
+        }
+        let Some(key) = key_of(&event.code) else { return };
+        let typing = !app_clone.is_typewriter_complete();
+        let waiting = app_clone.is_waiting_for_continue();
+        let action = app_clone.keymap.action(key);
+        if typing && (app_clone.keymap.is(key, KeyAction::SkipText) || action.is_none()) {
+            // skip_text keys, and keys without an action of their own, finish the text
+            app_clone.skip_typewriter();
+            return;
+        }
+        if waiting && app_clone.keymap.is(key, KeyAction::SkipText) {
+            app_clone.continue_text_sequence();
+            return;
+        }
+        match action {
+            Some(KeyAction::SelectPrev) if !typing => app_clone.move_selection_up(),
+            Some(KeyAction::SelectNext) if !typing => app_clone.move_selection_down(),
+            Some(KeyAction::Confirm) if !typing && !waiting => app_clone.select_choice(),
+            Some(KeyAction::ScrollUp | KeyAction::PageUp) => app_clone.scroll_up(),
+            Some(KeyAction::ScrollDown | KeyAction::PageDown) => app_clone.scroll_down(),
+            Some(KeyAction::ScrollTop) => app_clone.scroll_to_top(),
+            Some(KeyAction::ScrollBottom) => app_clone.enable_auto_scroll(),
+            Some(KeyAction::Menu) => app_clone.toggle_help(),
+            Some(KeyAction::Undo) if !typing && !waiting => app_clone.undo_last_choice(),
+            Some(KeyAction::Save) if !typing && !waiting => app_clone.start_naming_save(),
+            Some(KeyAction::Load) if !typing => app_clone.open_load_picker(),
+            Some(KeyAction::ExportSave) if !typing => app_clone.export_save(),
+            Some(KeyAction::ImportSave) if !typing && !waiting => app_clone.import_save(),
+            Some(KeyAction::Achievements) if !typing => app_clone.show_achievements(),
+            None if !typing => {
+                if let Key::Char(c) = key {
+                    if let Some(digit) = c.to_digit(10) {
+                        app_clone.select_by_number(digit as usize);
                     }
-                } else {
-                    // Any key press skips typewriter effect
-                    app_clone.skip_typewriter();
-                }
-            }
-            _ => {
-                // Any other key skips typewriter effect
-                if !app_clone.is_typewriter_complete() {
-                    app_clone.skip_typewriter();
                 }
             }
+            _ => {}
         }
     });
     
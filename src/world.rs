@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::game::fnv1a;
 use crate::errors::{GameError, GameResult};
 
 // --- ID Types for Type Safety ---
@@ -18,22 +19,259 @@ pub struct World {
     pub rooms: HashMap<String, Room>,
     pub choices: HashMap<String, Choice>,
     pub starting_room_id: String,
+    /// The story's `# Title`, if it has one.
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub no_undo: bool,
+    #[serde(default)]
+    pub endings: Vec<Ending>,
+    #[serde(default)]
+    pub achievements: Vec<Achievement>,
+    /// Format for skill check results; see `engine::DEFAULT_CHECK_MESSAGE`.
+    #[serde(default)]
+    pub check_message: Option<String>,
+    #[serde(default)]
+    pub dialogues: HashMap<String, DialogueNode>,
+    /// Flags and counters declared with `@flag` and `@var`, in story order.
+    #[serde(default)]
+    pub variables: Vec<Variable>,
+    /// Old room IDs mapped to their new ones with `@renamed`, so older saves still load.
+    #[serde(default)]
+    pub renamed_rooms: HashMap<String, String>,
+    /// Config overrides from the story's front matter, as written; see `ConfigLayers`.
+    #[serde(default)]
+    pub config: BTreeMap<String, String>,
+}
+
+impl World {
+    /// Everyone who speaks in a dialogue, for front ends that style speaker names.
+    pub fn speakers(&self) -> Vec<String> {
+        let mut speakers: Vec<String> = self
+            .dialogues
+            .values()
+            .flat_map(|node| node.lines.iter().filter_map(|line| line.speaker.clone()))
+            .collect();
+        speakers.sort();
+        speakers.dedup();
+        speakers
+    }
+
+    /// Every action a player can trigger from a room, including nested actions and
+    /// the responses of any conversation started there.
+    pub fn actions_from_room(&self, room_id: &str) -> Vec<&Action> {
+        let mut actions = Vec::new();
+        let mut pending: Vec<&String> = self.rooms.get(room_id).map(|room| room.choices.iter().collect()).unwrap_or_default();
+        let mut seen_dialogues = HashSet::new();
+
+        while let Some(choice_id) = pending.pop() {
+            let Some(choice) = self.choices.get(choice_id) else {
+                continue;
+            };
+            for action in choice.actions.iter().flat_map(Action::flattened) {
+                if let Action::Dialogue(node_id) = action {
+                    if seen_dialogues.insert(node_id) {
+                        if let Some(node) = self.dialogues.get(node_id) {
+                            pending.extend(node.choices.iter());
+                        }
+                    }
+                }
+                actions.push(action);
+            }
+        }
+
+        actions
+    }
+
+    pub fn declares_flag(&self, name: &str) -> bool {
+        self.variables.iter().any(|variable| matches!(variable, Variable::Flag { name: flag, .. } if flag == name))
+    }
+
+    pub fn declares_counter(&self, name: &str) -> bool {
+        self.variables.iter().any(|variable| matches!(variable, Variable::Counter { name: counter, .. } if counter == name))
+    }
+
+    /// Every flag and counter name read or changed anywhere in the story, declared or not.
+    pub fn variable_uses(&self) -> (HashSet<String>, HashSet<String>) {
+        let mut flags = HashSet::new();
+        let mut counters = HashSet::new();
+
+        let conditions = self.choices.values().filter_map(|choice| choice.condition.as_ref())
+            .chain(self.rooms.values().flat_map(|room| room.descriptions.iter().filter_map(|description| description.condition.as_ref())))
+            .chain(self.dialogues.values().flat_map(|node| node.lines.iter().filter_map(|line| line.condition.as_ref())))
+            .chain(self.achievements.iter().filter_map(|achievement| achievement.condition.as_ref()));
+        for condition in conditions {
+            collect_names_from_condition(condition, &mut flags, &mut counters);
+        }
+
+        for action in self.choices.values().flat_map(|choice| choice.actions.iter().flat_map(Action::flattened)) {
+            match action {
+                Action::SetFlag(flag) | Action::RemoveFlag(flag) => {
+                    flags.insert(flag.0.clone());
+                }
+                Action::IncrementCounter(counter) | Action::DecrementCounter(counter) | Action::SetCounter(counter, _) => {
+                    counters.insert(counter.clone());
+                }
+                Action::DisplayTextConditional { condition, .. } => {
+                    collect_names_from_condition(condition, &mut flags, &mut counters);
+                }
+                Action::SkillCheck { check, .. } => {
+                    counters.extend(check.modifiers.iter().cloned());
+                }
+                _ => {}
+            }
+        }
+
+        (flags, counters)
+    }
+
+    /// The current ID of a room that may have been renamed since a save was made.
+    pub fn resolve_room<'a>(&'a self, room_id: &'a str) -> &'a str {
+        let mut resolved = room_id;
+        // Follow chains of renames; the bound stops a cycle from looping forever
+        for _ in 0..=self.renamed_rooms.len() {
+            match self.renamed_rooms.get(resolved) {
+                Some(new_id) => resolved = new_id,
+                None => break,
+            }
+        }
+        resolved
+    }
+
+    /// Fingerprint of the story's shape: its rooms, conversations, choices and
+    /// declared variables with their initial values and ranges. Editing text
+    /// leaves it unchanged.
+    pub fn structure_hash(&self) -> String {
+        let mut parts: Vec<String> = self
+            .rooms
+            .iter()
+            .map(|(room_id, room)| format!("room {} {}", room_id, room.choices.join(",")))
+            .chain(self.dialogues.iter().map(|(node_id, node)| format!("dialogue {} {}", node_id, node.choices.join(","))))
+            .chain(self.variables.iter().map(|variable| match variable {
+                Variable::Flag { name, initial } => format!("flag {} {}", name, initial),
+                Variable::Counter { name, initial, range } => format!("counter {} {} {:?}", name, initial, range),
+            }))
+            .collect();
+        parts.sort();
+        format!("{:016x}", fnv1a(parts.join("\n").as_bytes()))
+    }
+
+    /// The inclusive bounds a counter is kept within, if it was declared with a range.
+    pub fn counter_range(&self, name: &str) -> Option<(i32, i32)> {
+        self.variables.iter().find_map(|variable| match variable {
+            Variable::Counter { name: counter, range, .. } if counter == name => *range,
+            _ => None,
+        })
+    }
+}
+
+fn collect_names_from_condition(condition: &Condition, flags: &mut HashSet<String>, counters: &mut HashSet<String>) {
+    match condition {
+        Condition::HasFlag(flag) | Condition::NotHasFlag(flag) => {
+            flags.insert(flag.0.clone());
+        }
+        Condition::HasAllFlags(flag_list) | Condition::HasAnyFlags(flag_list) => {
+            flags.extend(flag_list.iter().map(|flag| flag.0.clone()));
+        }
+        Condition::CounterGreaterThan(counter, _) | Condition::CounterLessThan(counter, _) | Condition::CounterEquals(counter, _) => {
+            counters.insert(counter.clone());
+        }
+        Condition::SkillCheck(check) => {
+            counters.extend(check.modifiers.iter().cloned());
+        }
+        Condition::StringEquals(_, _) | Condition::StringNotEquals(_, _) => {}
+        Condition::And(left, right) | Condition::Or(left, right) => {
+            collect_names_from_condition(left, flags, counters);
+            collect_names_from_condition(right, flags, counters);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ending {
+    pub id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Achievement {
+    pub id: String,
+    pub title: String,
+    /// Unlocks automatically as soon as this holds after a choice.
+    pub condition: Option<Condition>,
+}
+
+/// A flag or counter declared up front with its starting value.
+#[derive(Debug, Clone, Deserialize)]
+pub enum Variable {
+    Flag {
+        name: String,
+        initial: bool,
+    },
+    Counter {
+        name: String,
+        initial: i32,
+        /// Inclusive bounds; changes outside them are clamped.
+        range: Option<(i32, i32)>,
+    },
+}
+
+impl Variable {
+    pub fn name(&self) -> &str {
+        match self {
+            Variable::Flag { name, .. } | Variable::Counter { name, .. } => name,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Room {
     pub descriptions: Vec<ConditionalDescription>,
     pub choices: Vec<String>,
+    #[serde(default)]
+    pub description_mode: DescriptionMode,
+}
+
+/// How a room combines its conditional descriptions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum DescriptionMode {
+    /// Show only the first description that applies.
+    #[default]
+    FirstMatch,
+    /// Show every description that applies, in order.
+    Additive,
+}
+
+/// One step of a conversation: what is said, then the player's possible responses.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogueNode {
+    pub lines: Vec<DialogueLine>,
+    pub choices: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogueLine {
+    pub condition: Option<Condition>,
+    /// Who says the line; narration has no speaker.
+    pub speaker: Option<String>,
+    pub text: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct ConditionalDescription {
     pub condition: Option<Condition>,
     pub text: String,
+    /// An `[else]` block: applies when the description just before it did not.
+    #[serde(default)]
+    pub is_else: bool,
+    /// Followed by a blank line, so additive descriptions start a new paragraph after it.
+    #[serde(default)]
+    pub ends_paragraph: bool,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Choice {
+    pub id: ChoiceId,
     pub text: String,
     pub condition: Option<Condition>,
     pub actions: Vec<Action>,
@@ -48,6 +286,10 @@ pub enum Condition {
     CounterGreaterThan(String, i32),
     CounterLessThan(String, i32),
     CounterEquals(String, i32),
+    StringEquals(String, String),
+    StringNotEquals(String, String),
+    /// Passes if a one-shot roll meets the target.
+    SkillCheck(SkillCheck),
     And(Box<Condition>, Box<Condition>),
     Or(Box<Condition>, Box<Condition>),
 }
@@ -67,6 +309,85 @@ pub enum Action {
     IncrementCounter(String),
     DecrementCounter(String),
     SetCounter(String, i32),
+    AskText {
+        variable: String,
+        prompt: String,
+    },
+    Ending(String),
+    Achieve(String),
+    /// Start a conversation, or jump to another node of the current one.
+    Dialogue(String),
+    /// Leave the conversation and return to the room it started in.
+    ExitDialogue,
+    SkillCheck {
+        check: SkillCheck,
+        on_success: Box<Action>,
+        on_failure: Option<Box<Action>>,
+    },
+}
+
+impl Action {
+    /// This action followed by every action nested inside it, such as both outcomes of a check.
+    pub fn flattened(&self) -> Vec<&Action> {
+        let mut actions = vec![self];
+        if let Action::SkillCheck { on_success, on_failure, .. } = self {
+            actions.extend(on_success.flattened());
+            if let Some(on_failure) = on_failure {
+                actions.extend(on_failure.flattened());
+            }
+        }
+        actions
+    }
+}
+
+/// A dice roll plus counter modifiers compared against a target, e.g. `d20 + courage >= 12`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkillCheck {
+    pub dice: u32,
+    pub sides: u32,
+    /// Counters added to the roll; the first one names the check.
+    pub modifiers: Vec<String>,
+    pub bonus: i32,
+    pub target: i32,
+}
+
+impl SkillCheck {
+    /// Name shown in roll messages: "Courage" for `d20 + courage`, otherwise the dice.
+    pub fn label(&self) -> String {
+        match self.modifiers.first() {
+            Some(counter) => {
+                let mut chars = counter.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect::<String>().replace('_', " "),
+                    None => counter.clone(),
+                }
+            }
+            None => self.dice_notation(),
+        }
+    }
+
+    pub fn dice_notation(&self) -> String {
+        if self.dice == 1 {
+            format!("d{}", self.sides)
+        } else {
+            format!("{}d{}", self.dice, self.sides)
+        }
+    }
+}
+
+impl std::fmt::Display for SkillCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.dice_notation())?;
+        for counter in &self.modifiers {
+            write!(f, " + {}", counter)?;
+        }
+        if self.bonus > 0 {
+            write!(f, " + {}", self.bonus)?;
+        } else if self.bonus < 0 {
+            write!(f, " - {}", -self.bonus)?;
+        }
+        write!(f, " >= {}", self.target)
+    }
 }
 
 // --- Markdown Loading ---
@@ -87,9 +408,35 @@ fn validate_world(world: &World) -> GameResult<()> {
         return Err(GameError::InvalidStartingRoom(world.starting_room_id.clone()));
     }
 
+    // Check declarations are unique and start inside their ranges
+    let mut declared = HashSet::new();
+    for variable in &world.variables {
+        if !declared.insert(variable.name()) {
+            return Err(GameError::ValidationError(format!("Variable '{}' is declared more than once", variable.name())));
+        }
+        if let Variable::Counter { name, initial, range: Some((min, max)) } = variable {
+            if min > max {
+                return Err(GameError::ValidationError(format!("Counter '{}' has an empty range {}..{}", name, min, max)));
+            }
+            if initial < min || initial > max {
+                return Err(GameError::ValidationError(format!("Counter '{}' starts at {}, outside its range {}..{}", name, initial, min, max)));
+            }
+        }
+    }
+
+    // Check renames point at rooms that exist, from IDs that are no longer used
+    for (old_id, new_id) in &world.renamed_rooms {
+        if world.rooms.contains_key(old_id) {
+            return Err(GameError::ValidationError(format!("Room '{}' is renamed to '{}' but still exists", old_id, new_id)));
+        }
+        if !world.rooms.contains_key(world.resolve_room(new_id)) {
+            return Err(GameError::MissingRoom(format!("Room '{}' is renamed to missing room '{}'", old_id, new_id)));
+        }
+    }
+
     // Check all room references in choices
     for (choice_id, choice) in &world.choices {
-        for action in &choice.actions {
+        for action in choice.actions.iter().flat_map(Action::flattened) {
             if let Action::GoTo(room_id) = action {
                 if !world.rooms.contains_key(room_id) {
                     return Err(GameError::MissingRoom(format!("Choice '{}' references missing room '{}'", choice_id, room_id)));
@@ -98,6 +445,55 @@ fn validate_world(world: &World) -> GameResult<()> {
         }
     }
 
+    // Check all endings reached by choices are declared
+    for (choice_id, choice) in &world.choices {
+        for action in choice.actions.iter().flat_map(Action::flattened) {
+            if let Action::Ending(ending_id) = action {
+                if !world.endings.iter().any(|ending| &ending.id == ending_id) {
+                    return Err(GameError::ValidationError(format!("Choice '{}' reaches undeclared ending '{}'", choice_id, ending_id)));
+                }
+            }
+        }
+    }
+
+    // Check all achievements unlocked by choices are declared
+    for (choice_id, choice) in &world.choices {
+        for action in choice.actions.iter().flat_map(Action::flattened) {
+            if let Action::Achieve(achievement_id) = action {
+                if !world.achievements.iter().any(|achievement| &achievement.id == achievement_id) {
+                    return Err(GameError::ValidationError(format!("Choice '{}' unlocks undeclared achievement '{}'", choice_id, achievement_id)));
+                }
+            }
+        }
+    }
+
+    // Check all dialogue nodes jumped to are defined
+    for (choice_id, choice) in &world.choices {
+        for action in choice.actions.iter().flat_map(Action::flattened) {
+            if let Action::Dialogue(node_id) = action {
+                if !world.dialogues.contains_key(node_id) {
+                    return Err(GameError::ValidationError(format!("Choice '{}' jumps to missing dialogue '{}'", choice_id, node_id)));
+                }
+            }
+        }
+    }
+
+    // Check every dialogue node has a response, or the player could never leave it
+    for (node_id, node) in &world.dialogues {
+        if node.choices.is_empty() {
+            return Err(GameError::ValidationError(format!("Dialogue '{}' has no responses to leave it by", node_id)));
+        }
+    }
+
+    // Check all choice references in dialogue nodes
+    for (node_id, node) in &world.dialogues {
+        for choice_id in &node.choices {
+            if !world.choices.contains_key(choice_id) {
+                return Err(GameError::MissingChoice(format!("Dialogue '{}' references missing choice '{}'", node_id, choice_id)));
+            }
+        }
+    }
+
     // Check all choice references in rooms
     for (room_id, room) in &world.rooms {
         for choice_id in &room.choices {
@@ -0,0 +1,64 @@
+// Builds examples/c/play.c against the cdylib and checks what it prints
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn library_dir() -> PathBuf {
+    // Test binaries live in target/<profile>/deps, next to the library's directory
+    let exe = std::env::current_exe().expect("test binary path");
+    exe.parent().and_then(Path::parent).expect("target directory").to_path_buf()
+}
+
+#[test]
+fn c_example_plays_a_story() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let library_dir = library_dir();
+    let program = library_dir.join("c_api_play");
+
+    let compiled = match Command::new("cc")
+        .arg(crate_dir.join("examples/c/play.c"))
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg(format!("-I{}", crate_dir.join("include").display()))
+        .arg(format!("-L{}", library_dir.display()))
+        .arg("-lrestoration_project")
+        .arg("-o")
+        .arg(&program)
+        .status()
+    {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!("Skipping C API test, no C compiler: {}", e);
+            return;
+        }
+    };
+    assert!(compiled.success(), "the C example failed to compile");
+
+    let output = Command::new(&program)
+        .arg(crate_dir.join("the_cellar.md"))
+        .env("LD_LIBRARY_PATH", &library_dir)
+        .env("DYLD_LIBRARY_PATH", &library_dir)
+        .output()
+        .expect("the C example runs");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "the C example failed:\n{}", stdout);
+    assert!(stdout.contains("You are in a dusty cellar."));
+    assert!(stdout.contains(r#""flag":"has_key","is_set":true"#));
+    assert!(stdout.contains(r#"choices: [{"id":"cellar_choice_3""#));
+    assert!(stdout.contains("restore notes: []"));
+    assert!(stdout.contains(r#"restored choices: [{"id":"cellar_choice_2""#));
+    assert!(stdout.contains("bad choice failed: Validation error: There is no choice 99"));
+}
+
+#[test]
+fn committed_header_is_up_to_date() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let generated = std::fs::read_to_string(Path::new(env!("OUT_DIR")).join("restoration.h"))
+        .expect("the build script generates the header");
+    let committed = std::fs::read_to_string(crate_dir.join("include/restoration.h")).expect("include/restoration.h exists");
+    assert!(
+        committed == generated,
+        "include/restoration.h is out of date; copy {}/restoration.h over it",
+        env!("OUT_DIR")
+    );
+}
@@ -0,0 +1,43 @@
+// Plays recordings back against stories without a UI
+use std::path::Path;
+use restoration_project::engine::Engine;
+use restoration_project::game::GameState;
+use restoration_project::replay::{load_recording, replay, RecordedStep};
+use restoration_project::world::{load_world_from_markdown, load_world_from_markdown_content, ChoiceId};
+
+#[test]
+fn cellar_recording_replays() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let world = load_world_from_markdown(crate_dir.join("the_cellar.md").to_str().unwrap()).expect("the cellar loads");
+    let recording = load_recording(crate_dir.join("tests/recordings/the_cellar.jsonl").to_str().unwrap())
+        .expect("the recording loads");
+
+    let game_state = replay(&world, &recording).expect("the recording replays");
+    assert!(game_state.has_quit);
+    assert_eq!(game_state.current_room_id, "hallway");
+}
+
+#[test]
+fn answers_are_recorded_as_given() {
+    let world = load_world_from_markdown_content(
+        "# Names\n\n@start hall\n\n## @room hall\nA hall.\n\n### Sign twice\n- ask name \"Name?\"\n- ask name \"Again?\"\n- \"Signed {name}.\"\n",
+    )
+    .expect("the story loads");
+    let mut game_state = GameState::for_world(&world, 1);
+    let start = RecordedStep::start(&world, &game_state, 1);
+
+    let choice_id = ChoiceId("hall_choice_0".to_string());
+    let mut events = Engine::choose(&world, &mut game_state, choice_id.clone()).unwrap();
+    events.extend(Engine::provide_input(&world, &mut game_state, "Ada").unwrap());
+    events.extend(Engine::provide_input(&world, &mut game_state, "Grace").unwrap());
+    assert_eq!(game_state.strings.get("name").map(String::as_str), Some("Grace"));
+
+    let step = RecordedStep::choice("1", &choice_id, &events, &game_state);
+    match &step {
+        RecordedStep::Choice { answers, .. } => assert_eq!(answers, &["Ada", "Grace"]),
+        other => panic!("expected a choice step, got {:?}", other),
+    }
+
+    let replayed = replay(&world, &[start, step]).expect("the recording replays");
+    assert_eq!(replayed.state_hash(), game_state.state_hash());
+}